@@ -37,7 +37,7 @@ const ChatElement = React.memo((
 
             return controller!==null&&!controller.availableAbilityData.grayedOut;
         },
-        ["yourPlayerIndex", "yourAllowedControllers"]
+        ["yourPlayerIndex", "yourAllowedControllers", "yourAllowedControllersUpdate"]
     );
     const myIndex = usePlayerState(
         playerState => playerState.myIndex,
@@ -97,10 +97,34 @@ export default function RoleOutlineSelector(props: RoleOutlineSelectorProps): Re
                                 options[index].winIfAny = old.winIfAny;
                             if("insiderGroups" in old)
                                 options[index].insiderGroups = old.insiderGroups;
+                            if("weight" in old)
+                                options[index].weight = old.weight;
 
                             props.onChange(options);
                         }}
                     />
+                    <input
+                        type="number"
+                        className="role-picker-weight"
+                        min={0}
+                        max={65535}
+                        disabled={props.disabled}
+                        placeholder={translate("menu.roleOutline.weight")}
+                        title={translate("menu.roleOutline.weight.description")}
+                        value={option.weight ?? ""}
+                        onChange={e => {
+                            const options = [...props.roleOutline];
+                            const weight = parseInt(e.target.value);
+
+                            if(isNaN(weight)) {
+                                delete options[index].weight;
+                            } else {
+                                options[index].weight = Math.min(Math.max(weight, 0), 65535);
+                            }
+
+                            props.onChange(options)
+                        }}
+                    />
                     <Button
                         disabled={props.disabled}
                         onClick={() => {
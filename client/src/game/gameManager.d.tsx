@@ -3,7 +3,7 @@ import { DoomsayerGuess } from "../menu/game/gameScreenContent/AbilityMenu/RoleS
 import { AbilityInput } from "./abilityInput";
 import { PhaseType, PhaseTimes, PlayerIndex, State, Verdict, ModifierType } from "./gameState.d";
 import { ToClientPacket, ToServerPacket } from "./packet";
-import { RoleList, RoleOutline } from "./roleListState.d";
+import { RoleList, RoleListConstraint, RoleOutline } from "./roleListState.d";
 import { Role } from "./roleState.d";
 
 export type Server = {
@@ -27,6 +27,8 @@ export type GameManager = {
     
 
     state: State,
+    /** The number of the last packet the room sent, so a rejoin can be sent only what was missed */
+    lastSequence: number,
     updateChatFilter(filter: PlayerIndex | null): void,
 
     server: Server,
@@ -56,15 +58,27 @@ export type GameManager = {
      * @returns A promise that will be fulfilled as true if the join was 
      *          successful and false if the join was unsuccessful
      */
-    sendRejoinPacket(roomCode: number, playerId: number): Promise<boolean>;
+    sendRejoinPacket(roomCode: string, playerId: number, reconnectToken: string): Promise<boolean>;
+    /**
+     * After the connection drops, opens a new one and rejoins the room this client is still in,
+     * keeping its state so it's only sent what it missed
+     * @returns A promise that will be fulfilled as true if the rejoin was 
+     *          successful and false if the rejoin was unsuccessful
+     */
+    resumeRoom(): Promise<boolean>;
     /**
      * @returns A promise that will be fulfilled as true if the join was 
      *          successful and false if the join was unsuccessful
      */
-    sendJoinPacket(roomCode: number): Promise<boolean>;
+    sendJoinPacket(roomCode: string, password?: string, inviteCode?: string): Promise<boolean>;
     sendKickPlayerPacket(playerId: number): void;
     sendSetPlayerHostPacket(playerId: number): void;
     sendRelinquishHostPacket(): void;
+    sendAddBotPacket(): void;
+    sendSetRoomPrivatePacket(isPrivate: boolean): void;
+    /** An empty password removes it */
+    sendSetRoomPasswordPacket(password: string): void;
+    sendCreateInviteCodePacket(): void;
     sendSetSpectatorPacket(spectator: boolean): void;
     sendSetNamePacket(name: string): void;
     sendReadyUpPacket(ready: boolean): void;
@@ -77,6 +91,8 @@ export type GameManager = {
     sendSetRoleListPacket(roleListEntries: RoleList): void;
     sendSetRoleOutlinePacket(index: number, roleOutline: RoleOutline): void;
     sendSimplifyRoleListPacket(): void;
+    sendAnalyzeRoleListPacket(): void;
+    sendSetRoleListConstraintsPacket(constraints: RoleListConstraint[]): void;
     
     sendJudgementPacket(judgement: Verdict): void;
     sendSaveWillPacket(will: string): void;
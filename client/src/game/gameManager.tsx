@@ -5,14 +5,20 @@ import messageListener from "./messageListener";
 import React from "react";
 import { PhaseType, PhaseTimes, Verdict, PlayerIndex } from "./gameState.d";
 import { GameManager, Server, StateListener } from "./gameManager.d";
-import { LobbyPreviewData, ToClientPacket, ToServerPacket } from "./packet";
-import { RoleOutline } from "./roleListState.d";
+import { decodeMessagePack } from "./messagePack";
+import { CAPABILITIES, LobbyPreviewData, PROTOCOL_VERSION, ToClientPacket, ToServerPacket } from "./packet";
+import { RoleListConstraint, RoleOutline } from "./roleListState.d";
 import translate from "./lang";
 import PlayMenu from "../menu/main/PlayMenu";
 import { createGameState, createLobbyState } from "./gameState";
-import { deleteReconnectData } from "./localStorage";
+import { deleteReconnectData, loadReconnectData } from "./localStorage";
 import AudioController from "../menu/AudioController";
 import ListMap from "../ListMap";
+import LoadingScreen from "../menu/LoadingScreen";
+
+/** How many times a dropped connection is opened again to resume the room, and how long to wait between */
+const RESUME_ATTEMPTS = 5;
+const RESUME_RETRY_DELAY_MS = 2000;
 
 export function createGameManager(): GameManager {
 
@@ -55,6 +61,8 @@ export function createGameManager(): GameManager {
             if(gameState!=null){
                 GAME_MANAGER.state.roomCode = gameState.roomCode;
                 GAME_MANAGER.state.lobbyName = gameState.lobbyName;
+                GAME_MANAGER.state.private = gameState.private;
+                GAME_MANAGER.state.hasPassword = gameState.hasPassword;
                 GAME_MANAGER.state.roleList = gameState.roleList;
                 GAME_MANAGER.state.phaseTimes = gameState.phaseTimes;
                 GAME_MANAGER.state.enabledRoles = gameState.enabledRoles;
@@ -74,6 +82,8 @@ export function createGameManager(): GameManager {
             if (lobbyState !== null && GAME_MANAGER.state.stateType === "game") {
                 GAME_MANAGER.state.roomCode = lobbyState.roomCode;
                 GAME_MANAGER.state.lobbyName = lobbyState.lobbyName;
+                GAME_MANAGER.state.private = lobbyState.private;
+                GAME_MANAGER.state.hasPassword = lobbyState.hasPassword;
                 GAME_MANAGER.state.roleList = lobbyState.roleList;
                 GAME_MANAGER.state.phaseTimes = lobbyState.phaseTimes;
                 GAME_MANAGER.state.enabledRoles = lobbyState.enabledRoles;
@@ -104,7 +114,7 @@ export function createGameManager(): GameManager {
             GAME_MANAGER.state = {
                 stateType: "outsideLobby",
                 selectedRoomCode: null,
-                lobbies: new Map<number, LobbyPreviewData>()
+                lobbies: new Map<string, LobbyPreviewData>()
             };
 
             return true;
@@ -113,6 +123,7 @@ export function createGameManager(): GameManager {
         state: {
             stateType: "disconnected"
         },
+        lastSequence: 0,
 
         updateChatFilter(filter: PlayerIndex | null) {
             if(GAME_MANAGER.state.stateType === "game" && GAME_MANAGER.state.clientState.type === "player"){
@@ -195,7 +206,7 @@ export function createGameManager(): GameManager {
 
             return promise;
         },
-        sendRejoinPacket(roomCode: number, playerId: number) {
+        sendRejoinPacket(roomCode: string, playerId: number, reconnectToken: string) {
             let completePromise: (success: boolean) => void;
             const promise = new Promise<boolean>((resolver) => {
                 completePromise = resolver;
@@ -214,16 +225,48 @@ export function createGameManager(): GameManager {
             };
             GAME_MANAGER.addStateListener(onJoined);
 
+            // Only a client that still has the room can be sent just what it missed
+            const hasRoom = (GAME_MANAGER.state.stateType === "lobby" || GAME_MANAGER.state.stateType === "game")
+                && GAME_MANAGER.state.roomCode === roomCode
+                && GAME_MANAGER.state.myId === playerId;
+
             this.server.sendPacket({
                 type: "reJoin",
                 roomCode,
-                playerId
+                playerId,
+                reconnectToken,
+                lastSequence: hasRoom ? GAME_MANAGER.lastSequence : undefined
             });
 
 
             return promise;
         },
-        sendJoinPacket(roomCode: number) {
+        async resumeRoom() {
+            const reconnectData = loadReconnectData();
+            if (
+                (GAME_MANAGER.state.stateType !== "lobby" && GAME_MANAGER.state.stateType !== "game")
+                || reconnectData === null
+                || reconnectData.roomCode !== GAME_MANAGER.state.roomCode
+                || reconnectData.playerId !== GAME_MANAGER.state.myId
+            ) {
+                return false;
+            }
+
+            ANCHOR_CONTROLLER?.setCoverCard(<LoadingScreen type="disconnect"/>);
+            for (let attempt = 0; attempt < RESUME_ATTEMPTS; attempt++) {
+                if (await GAME_MANAGER.server.open()) {
+                    // A resumed join clears the cover card
+                    if (await this.sendRejoinPacket(reconnectData.roomCode, reconnectData.playerId, reconnectData.reconnectToken)) {
+                        return true;
+                    }
+                    break;
+                }
+                await new Promise(resolve => setTimeout(resolve, RESUME_RETRY_DELAY_MS));
+            }
+            ANCHOR_CONTROLLER?.clearCoverCard();
+            return false;
+        },
+        sendJoinPacket(roomCode: string, password?: string, inviteCode?: string) {
             let completePromise: (success: boolean) => void;
             const promise = new Promise<boolean>((resolver) => {
                 completePromise = resolver;
@@ -244,7 +287,9 @@ export function createGameManager(): GameManager {
 
             this.server.sendPacket({
                 type: "join",
-                roomCode
+                roomCode,
+                password,
+                inviteCode
             });
 
             return promise;
@@ -266,6 +311,28 @@ export function createGameManager(): GameManager {
                 type: "relinquishHost",
             });
         },
+        sendAddBotPacket() {
+            this.server.sendPacket({
+                type: "hostAddBot",
+            });
+        },
+        sendSetRoomPrivatePacket(isPrivate) {
+            this.server.sendPacket({
+                type: "hostSetRoomPrivate",
+                private: isPrivate
+            });
+        },
+        sendSetRoomPasswordPacket(password) {
+            this.server.sendPacket({
+                type: "hostSetRoomPassword",
+                password: password === "" ? null : password
+            });
+        },
+        sendCreateInviteCodePacket() {
+            this.server.sendPacket({
+                type: "hostCreateInviteCode",
+            });
+        },
 
         sendSetSpectatorPacket(spectator) {
             this.server.sendPacket({
@@ -360,6 +427,17 @@ export function createGameManager(): GameManager {
                 type: "simplifyRoleList"
             });
         },
+        sendAnalyzeRoleListPacket() {
+            this.server.sendPacket({
+                type: "analyzeRoleList"
+            });
+        },
+        sendSetRoleListConstraintsPacket(constraints: RoleListConstraint[]) {
+            this.server.sendPacket({
+                type: "setRoleListConstraints",
+                constraints
+            });
+        },
 
         sendJudgementPacket(judgement: Verdict) {
             this.server.sendPacket({
@@ -516,6 +594,7 @@ function createServer(){
             }
             try {
                 Server.ws = new WebSocket(address);
+                Server.ws.binaryType = "arraybuffer";
             } catch {
                 return Promise.resolve(false);
             }
@@ -533,6 +612,12 @@ function createServer(){
             ]);
 
             Server.ws.onopen = (event: Event)=>{
+                // The server ignores everything else until it has accepted this
+                Server.sendPacket({
+                    type: "hello",
+                    protocolVersion: PROTOCOL_VERSION,
+                    capabilities: CAPABILITIES
+                });
                 completePromise(true);
                 console.log("Connected to server.");
             };
@@ -543,16 +628,26 @@ function createServer(){
                 if (Server.ws === null) return; // We closed it ourselves
                 Server.ws = null;
 
-                ANCHOR_CONTROLLER?.pushErrorCard({
-                    title: translate("notification.connectionFailed"), 
-                    body: ""
+                // The room is kept while trying to rejoin it, so only what was missed has to be sent
+                GAME_MANAGER.resumeRoom().then(resumed => {
+                    if (resumed) return;
+                    ANCHOR_CONTROLLER?.pushErrorCard({
+                        title: translate("notification.connectionFailed"), 
+                        body: ""
+                    });
+                    ANCHOR_CONTROLLER?.setContent(<StartMenu/>);
                 });
-                ANCHOR_CONTROLLER?.setContent(<StartMenu/>);
             };
-            Server.ws.onmessage = (event: MessageEvent<string>)=>{
-                GAME_MANAGER.messageListener(
-                    JSON.parse(event.data) as ToClientPacket
-                );
+            // Binary frames are MessagePack, once the server has agreed to send it
+            Server.ws.onmessage = (event: MessageEvent<string | ArrayBuffer>)=>{
+                const packet = (typeof event.data === "string"
+                    ? JSON.parse(event.data)
+                    : decodeMessagePack(new Uint8Array(event.data))) as ToClientPacket & { sequence?: number };
+                // Packets from a room are numbered
+                if (packet.sequence !== undefined) {
+                    GAME_MANAGER.lastSequence = packet.sequence;
+                }
+                GAME_MANAGER.messageListener(packet);
             };
             Server.ws.onerror = (event: Event) => {
                 Server.close();
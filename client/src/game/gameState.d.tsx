@@ -1,8 +1,8 @@
 import { Grave } from "./graveState";
 import { ChatMessage } from "../components/ChatMessage";
 import { Role, RoleState } from "./roleState.d";
-import { RoleList } from "./roleListState.d";
-import { LobbyPreviewData } from "./packet";
+import { RoleList, RoleListConstraint } from "./roleListState.d";
+import { LobbyPreviewData, RoleListAnalysis } from "./packet";
 import { ChatFilter } from "../menu/game/gameScreenContent/ChatMenu";
 import { ControllerID, SavedController } from "./abilityInput";
 import translate from "./lang";
@@ -18,15 +18,19 @@ export type OutsideLobbyState = {
     stateType: "outsideLobby",
 
     selectedRoomCode: string | null,
-    lobbies: Map<number, LobbyPreviewData>,
+    lobbies: Map<string, LobbyPreviewData>,
 }
 
 
 //Change this to use PlayerID for player map and playerID for who I AM instead of myName and host
 export type LobbyState = {
     stateType: "lobby"
-    roomCode: number,
+    roomCode: string,
     lobbyName: string,
+    private: boolean,
+    hasPassword: boolean,
+    /** The last invite code this client created, if it's the host */
+    inviteCode: string | null,
 
     myId: number | null,
 
@@ -34,6 +38,9 @@ export type LobbyState = {
     phaseTimes: PhaseTimes,
     enabledRoles: Role[],
     enabledModifiers: ModifierType[],
+    /** The last analysis of the role list this client asked for */
+    roleListAnalysis: RoleListAnalysis | null,
+    roleListConstraints: RoleListConstraint[],
 
     players: ListMap<LobbyClientID, LobbyClient>,
     chatMessages: ChatMessage[],
@@ -43,12 +50,19 @@ export type LobbyClient = {
     connection: ClientConnection,
     clientType: LobbyClientType
 }
-export type ClientConnection = "connected" | "disconnected" | "couldReconnect";
+export type ClientConnection = "connected" | "disconnected" | "couldReconnect" | "bot";
 export type GameClient = {
     clientType: GameClientType,
     connection: ClientConnection,
+    /** Null unless the client is connected */
+    connectionQuality: ConnectionQuality | null,
     host: boolean,
 }
+export type ConnectionQuality = {
+    /** Null until the client has answered a ping */
+    roundTripMillis: number | null,
+    rating: "unknown" | "good" | "fair" | "poor" | "lagging"
+}
 export type GameClientType = {
     type: "spectator",
     index: number
@@ -66,8 +80,10 @@ export type PlayerClientType = {
 
 type GameState = {
     stateType: "game",
-    roomCode: number,
+    roomCode: string,
     lobbyName: string,
+    private: boolean,
+    hasPassword: boolean,
     
     initialized: boolean,
 
@@ -19,8 +19,11 @@ export function defaultPhaseTimes(): PhaseTimes {
 export function createLobbyState(): LobbyState {
     return {
         stateType: "lobby",
-        roomCode: 0,
+        roomCode: "",
         lobbyName: "Mafia Lobby",
+        private: false,
+        hasPassword: false,
+        inviteCode: null,
 
         myId: null,
 
@@ -28,6 +31,8 @@ export function createLobbyState(): LobbyState {
         phaseTimes: defaultPhaseTimes(),
         enabledRoles: [],
         enabledModifiers: [],
+        roleListAnalysis: null,
+        roleListConstraints: [],
 
         players: new ListMap<LobbyClientID, LobbyClient>(),
         chatMessages: [],
@@ -37,8 +42,10 @@ export function createLobbyState(): LobbyState {
 export function createGameState(): GameState {
     return {
         stateType: "game",
-        roomCode: 0,
+        roomCode: "",
         lobbyName: "",
+        private: false,
+        hasPassword: false,
 
         initialized: false,
 
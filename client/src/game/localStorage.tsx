@@ -7,12 +7,13 @@ import { ContentMenu } from "../menu/game/GameScreen";
 import { ParseResult, Success } from "../components/gameModeSettings/gameMode/parse";
 
 
-export function saveReconnectData(roomCode: number, playerId: number) {
+export function saveReconnectData(roomCode: string, playerId: number, reconnectToken: string) {
     localStorage.setItem(
         "reconnectData",
         JSON.stringify({
             "roomCode": roomCode,
             "playerId": playerId,
+            "reconnectToken": reconnectToken,
             "lastSaveTime": Date.now()
         })
     );
@@ -21,8 +22,9 @@ export function deleteReconnectData() {
     localStorage.removeItem("reconnectData");
 }
 export function loadReconnectData(): {
-    roomCode: number,
+    roomCode: string,
     playerId: number,
+    reconnectToken: string,
     lastSaveTime: number,
 } | null {
     let dataJSON = localStorage.getItem("reconnectData");
@@ -32,7 +34,8 @@ export function loadReconnectData(): {
     
         // Make sure it isn't expired
         const HOUR_IN_SECONDS = 3_600_000;
-        if (reconnectData.lastSaveTime < Date.now() - HOUR_IN_SECONDS) {
+        // Data saved before reconnect tokens existed can't be used to rejoin
+        if (reconnectData.lastSaveTime < Date.now() - HOUR_IN_SECONDS || typeof reconnectData.reconnectToken !== "string") {
             deleteReconnectData();
             return null
         }
@@ -4,8 +4,9 @@ import { ANCHOR_CONTROLLER, chatMessageToAudio } from "./../menu/Anchor";
 import GAME_MANAGER from "./../index";
 import GameScreen from "./../menu/game/GameScreen";
 import { ToClientPacket } from "./packet";
-import { GameClient, PlayerIndex, Tag } from "./gameState.d";
+import { GameClient, PlayerIndex, Tag, translateConclusion } from "./gameState.d";
 import { Role } from "./roleState.d";
+import { translateRoleGenerationError } from "./roleListState.d";
 import translate from "./lang";
 import { computePlayerKeywordData, computePlayerKeywordDataForLobby } from "../components/StyledText";
 import { deleteReconnectData, loadSettingsParsed, saveReconnectData } from "./localStorage";
@@ -21,7 +22,7 @@ import PlayMenu from "../menu/main/PlayMenu";
 import StartMenu from "../menu/main/StartMenu";
 import { defaultAlibi } from "../menu/game/gameScreenContent/WillMenu";
 import ListMap from "../ListMap";
-import { sortControllerIdCompare } from "./abilityInput";
+import { controllerIdToLink, sortControllerIdCompare } from "./abilityInput";
 
 function sendDefaultName() {
     const defaultName = loadSettingsParsed().defaultName;
@@ -41,6 +42,16 @@ export default function messageListener(packet: ToClientPacket){
                 });
             }
         break;
+        case "hello":
+        break;
+        case "rejectHello":
+            ANCHOR_CONTROLLER?.pushErrorCard({
+                title: translate("notification.rejectHello"),
+                body: translate("notification.rejectHello." + packet.reason)
+            });
+            GAME_MANAGER.setDisconnectedState();
+            ANCHOR_CONTROLLER?.setContent(<StartMenu/>);
+        break;
         case "rateLimitExceeded":
             ANCHOR_CONTROLLER?.pushErrorCard({ title: translate("notification.rateLimitExceeded"), body: "" });
         break;
@@ -52,15 +63,27 @@ export default function messageListener(packet: ToClientPacket){
             GAME_MANAGER.setDisconnectedState();
             ANCHOR_CONTROLLER?.setContent(<StartMenu/>);
         break
+        case "serverAnnouncement":
+            ANCHOR_CONTROLLER?.pushErrorCard({ title: translate("notification.serverAnnouncement"), body: packet.message });
+        break;
+        case "serverRestarting":
+            ANCHOR_CONTROLLER?.pushErrorCard({ title: translate("notification.serverRestarting"), body: translate("notification.serverRestarting.body", packet.inSecs) });
+        break;
         case "lobbyList":
             if(GAME_MANAGER.state.stateType === "outsideLobby"){
                 GAME_MANAGER.state.lobbies = new Map();
 
                 for(let [lobbyId, lobbyData] of Object.entries(packet.lobbies))
-                    GAME_MANAGER.state.lobbies.set(Number.parseInt(lobbyId), lobbyData);
+                    GAME_MANAGER.state.lobbies.set(lobbyId, lobbyData);
             }
         break;
         case "acceptJoin":
+            if(packet.resumed && (GAME_MANAGER.state.stateType === "lobby" || GAME_MANAGER.state.stateType === "game")){
+                // Everything missed is sent next, so the room is kept as it is
+                saveReconnectData(packet.roomCode, packet.playerId, packet.reconnectToken);
+                ANCHOR_CONTROLLER?.clearCoverCard();
+                break;
+            }
             if(packet.inGame && packet.spectator){
                 GAME_MANAGER.setSpectatorGameState();
                 ANCHOR_CONTROLLER?.setContent(<LoadingScreen type="join" />)
@@ -78,7 +101,7 @@ export default function messageListener(packet: ToClientPacket){
                 GAME_MANAGER.state.myId = packet.playerId;
             }
 
-            saveReconnectData(packet.roomCode, packet.playerId);
+            saveReconnectData(packet.roomCode, packet.playerId, packet.reconnectToken);
             sendDefaultName();
             ANCHOR_CONTROLLER?.clearCoverCard();
         break;
@@ -99,12 +122,27 @@ export default function messageListener(packet: ToClientPacket){
                 case "serverBusy":
                     ANCHOR_CONTROLLER?.pushErrorCard({ title: translate("notification.rejectJoin"), body: translate("notification.rejectJoin.serverBusy") });
                 break;
+                case "creatingRoomsTooFast":
+                    ANCHOR_CONTROLLER?.pushErrorCard({ title: translate("notification.rejectJoin"), body: translate("notification.rejectJoin.creatingRoomsTooFast") });
+                break;
                 case "playerTaken":
                     ANCHOR_CONTROLLER?.pushErrorCard({ title: translate("notification.rejectJoin"), body: translate("notification.rejectJoin.playerTaken") });
                 break;
                 case "playerDoesntExist":
                     ANCHOR_CONTROLLER?.pushErrorCard({ title: translate("notification.rejectJoin"), body: translate("notification.rejectJoin.playerDoesntExist") });
                 break;
+                case "passwordRequired":
+                    ANCHOR_CONTROLLER?.pushErrorCard({ title: translate("notification.rejectJoin"), body: translate("notification.rejectJoin.passwordRequired") });
+                break;
+                case "wrongPassword":
+                    ANCHOR_CONTROLLER?.pushErrorCard({ title: translate("notification.rejectJoin"), body: translate("notification.rejectJoin.wrongPassword") });
+                break;
+                case "inviteRequired":
+                    ANCHOR_CONTROLLER?.pushErrorCard({ title: translate("notification.rejectJoin"), body: translate("notification.rejectJoin.inviteRequired") });
+                break;
+                case "invalidInviteCode":
+                    ANCHOR_CONTROLLER?.pushErrorCard({ title: translate("notification.rejectJoin"), body: translate("notification.rejectJoin.invalidInviteCode") });
+                break;
                 default:
                     ANCHOR_CONTROLLER?.pushErrorCard({ title: translate("notification.rejectJoin"), body: `${packet.type} message response not implemented: ${packet.reason}` });
                     console.error(`${packet.type} message response not implemented: ${packet.reason}`);
@@ -115,25 +153,25 @@ export default function messageListener(packet: ToClientPacket){
             
         break;
         case "rejectStart":
-            switch(packet.reason) {
+            switch(packet.reason.type) {
                 case "gameEndsInstantly":
-                    ANCHOR_CONTROLLER?.pushErrorCard({ title: translate("notification.rejectStart"), body: translate("notification.rejectStart.gameEndsInstantly") });
+                    ANCHOR_CONTROLLER?.pushErrorCard({ title: translate("notification.rejectStart"), body: translate("notification.rejectStart.gameEndsInstantly", translateConclusion(packet.reason.conclusion)) });
                 break;
                 case "roleListTooSmall":
                     ANCHOR_CONTROLLER?.pushErrorCard({ title: translate("notification.rejectStart"), body: translate("notification.rejectStart.roleListTooSmall") });
                 break;
                 case "roleListCannotCreateRoles":
-                    ANCHOR_CONTROLLER?.pushErrorCard({ title: translate("notification.rejectStart"), body: translate("notification.rejectStart.roleListCannotCreateRoles") });
+                    ANCHOR_CONTROLLER?.pushErrorCard({ title: translate("notification.rejectStart"), body: translateRoleGenerationError(packet.reason) });
                 break;
                 case "zeroTimeGame":
                     ANCHOR_CONTROLLER?.pushErrorCard({ title: translate("notification.rejectStart"), body: translate("notification.rejectStart.zeroTimeGame") });
                 break;
-                case "tooManyCLients":
+                case "tooManyClients":
                     ANCHOR_CONTROLLER?.pushErrorCard({ title: translate("notification.rejectStart"), body: translate("notification.rejectStart.tooManyClients") });
                 break;
                 default:
                     ANCHOR_CONTROLLER?.pushErrorCard({ title: translate("notification.rejectStart"), body: "" });
-                    console.error(`${packet.type} message response not implemented: ${packet.reason}`);
+                    console.error(`${packet.type} message response not implemented: ${packet.reason.type}`);
                     console.error(packet);
                 break;
             }
@@ -238,6 +276,17 @@ export default function messageListener(packet: ToClientPacket){
                 GAME_MANAGER.state.lobbyName = packet.name;
             }
         break;
+        case "roomAccess":
+            if(GAME_MANAGER.state.stateType === "lobby" || GAME_MANAGER.state.stateType === "game"){
+                GAME_MANAGER.state.private = packet.private;
+                GAME_MANAGER.state.hasPassword = packet.hasPassword;
+            }
+        break;
+        case "inviteCode":
+            if(GAME_MANAGER.state.stateType === "lobby"){
+                GAME_MANAGER.state.inviteCode = packet.code;
+            }
+        break;
         case "startGame": 
             if (GAME_MANAGER.state.stateType === "lobby") {
                 const isSpectator = GAME_MANAGER.state.players.get(GAME_MANAGER.state.myId!)?.clientType.type === "spectator";
@@ -305,6 +354,14 @@ export default function messageListener(packet: ToClientPacket){
                 GAME_MANAGER.state.roleList = [...GAME_MANAGER.state.roleList];
             }
         break;
+        case "roleListAnalysis":
+            if(GAME_MANAGER.state.stateType === "lobby")
+                GAME_MANAGER.state.roleListAnalysis = packet.analysis;
+        break;
+        case "roleListConstraints":
+            if(GAME_MANAGER.state.stateType === "lobby")
+                GAME_MANAGER.state.roleListConstraints = packet.constraints;
+        break;
         case "phaseTime":
             if(GAME_MANAGER.state.stateType === "lobby" || GAME_MANAGER.state.stateType === "game") {
                 GAME_MANAGER.state.phaseTimes[packet.phase.type] = packet.time;
@@ -380,6 +437,22 @@ export default function messageListener(packet: ToClientPacket){
                     packet.save.sort((a, b) => sortControllerIdCompare(a[0],b[0]));
             }
         break;
+        case "yourAllowedControllersUpdate":
+            if(GAME_MANAGER.state.stateType === "game" && GAME_MANAGER.state.clientState.type === "player"){
+                const savedControllers = new ListMap(
+                    GAME_MANAGER.state.clientState.savedControllers,
+                    (k1, k2) => controllerIdToLink(k1) === controllerIdToLink(k2)
+                );
+                for (const [id, controller] of packet.changed) {
+                    savedControllers.insert(id, controller);
+                }
+                for (const id of packet.removed) {
+                    savedControllers.delete(id);
+                }
+                GAME_MANAGER.state.clientState.savedControllers = 
+                    savedControllers.entries().sort((a, b) => sortControllerIdCompare(a[0],b[0]));
+            }
+        break;
         case "yourRoleLabels":
             if(GAME_MANAGER.state.stateType === "game"){
                 for (const player of GAME_MANAGER.state.players) {
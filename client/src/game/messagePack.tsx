@@ -0,0 +1,115 @@
+/**
+ * Decodes the MessagePack the server sends once it has agreed to the `messagePack` capability.
+ * Only handles the types the server writes: nil, booleans, numbers, strings, binary, arrays and maps.
+ * Maps become plain objects, like they would from JSON.
+ */
+export function decodeMessagePack(bytes: Uint8Array): unknown {
+    const reader = new MessagePackReader(bytes);
+    return reader.read();
+}
+
+class MessagePackReader {
+    private readonly view: DataView;
+    private offset = 0;
+    private static readonly TEXT_DECODER = new TextDecoder();
+
+    constructor(private readonly bytes: Uint8Array) {
+        this.view = new DataView(bytes.buffer, bytes.byteOffset, bytes.byteLength);
+    }
+
+    read(): unknown {
+        const type = this.uint(1);
+
+        if (type <= 0x7f) return type;
+        if (type >= 0xe0) return type - 0x100;
+        if ((type & 0xf0) === 0x80) return this.map(type & 0x0f);
+        if ((type & 0xf0) === 0x90) return this.array(type & 0x0f);
+        if ((type & 0xe0) === 0xa0) return this.string(type & 0x1f);
+
+        switch (type) {
+            case 0xc0: return null;
+            case 0xc2: return false;
+            case 0xc3: return true;
+            case 0xc4: return this.binary(this.uint(1));
+            case 0xc5: return this.binary(this.uint(2));
+            case 0xc6: return this.binary(this.uint(4));
+            case 0xca: return this.float(4);
+            case 0xcb: return this.float(8);
+            case 0xcc: return this.uint(1);
+            case 0xcd: return this.uint(2);
+            case 0xce: return this.uint(4);
+            case 0xcf: return this.uint(8);
+            case 0xd0: return this.int(1);
+            case 0xd1: return this.int(2);
+            case 0xd2: return this.int(4);
+            case 0xd3: return this.int(8);
+            case 0xd9: return this.string(this.uint(1));
+            case 0xda: return this.string(this.uint(2));
+            case 0xdb: return this.string(this.uint(4));
+            case 0xdc: return this.array(this.uint(2));
+            case 0xdd: return this.array(this.uint(4));
+            case 0xde: return this.map(this.uint(2));
+            case 0xdf: return this.map(this.uint(4));
+            default: throw new Error(`Unsupported MessagePack type 0x${type.toString(16)}`);
+        }
+    }
+
+    private advance(length: number): number {
+        const start = this.offset;
+        this.offset += length;
+        return start;
+    }
+
+    private uint(length: 1 | 2 | 4 | 8): number {
+        const start = this.advance(length);
+        switch (length) {
+            case 1: return this.view.getUint8(start);
+            case 2: return this.view.getUint16(start);
+            case 4: return this.view.getUint32(start);
+            // 64 bit numbers past 2^53 lose precision, the server doesn't send any
+            case 8: return this.view.getUint32(start) * 2 ** 32 + this.view.getUint32(start + 4);
+        }
+    }
+
+    private int(length: 1 | 2 | 4 | 8): number {
+        const start = this.advance(length);
+        switch (length) {
+            case 1: return this.view.getInt8(start);
+            case 2: return this.view.getInt16(start);
+            case 4: return this.view.getInt32(start);
+            case 8: return this.view.getInt32(start) * 2 ** 32 + this.view.getUint32(start + 4);
+        }
+    }
+
+    private float(length: 4 | 8): number {
+        const start = this.advance(length);
+        return length === 4 ? this.view.getFloat32(start) : this.view.getFloat64(start);
+    }
+
+    private string(length: number): string {
+        const start = this.advance(length);
+        return MessagePackReader.TEXT_DECODER.decode(this.bytes.subarray(start, start + length));
+    }
+
+    private binary(length: number): number[] {
+        const start = this.advance(length);
+        return Array.from(this.bytes.subarray(start, start + length));
+    }
+
+    private array(length: number): unknown[] {
+        const array = [];
+        for (let i = 0; i < length; i++) {
+            array.push(this.read());
+        }
+        return array;
+    }
+
+    private map(length: number): Record<string, unknown> {
+        const map: Record<string, unknown> = {};
+        for (let i = 0; i < length; i++) {
+            const key = this.read();
+            map[String(key)] = this.read();
+        }
+        return map;
+    }
+}
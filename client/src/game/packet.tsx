@@ -1,7 +1,7 @@
-import { PhaseType, PlayerIndex, Verdict, PhaseTimes, Tag, LobbyClientID, ChatGroup, PhaseState, LobbyClient, ModifierType, InsiderGroup, GameClient } from "./gameState.d"
+import { PhaseType, PlayerIndex, Verdict, PhaseTimes, Tag, LobbyClientID, ChatGroup, PhaseState, LobbyClient, ModifierType, InsiderGroup, GameClient, Conclusion } from "./gameState.d"
 import { Grave } from "./graveState"
 import { ChatMessage } from "../components/ChatMessage"
-import { RoleList, RoleOutline } from "./roleListState.d"
+import { RoleGenerationError, RoleList, RoleListConstraint, RoleOutline, RoleSet } from "./roleListState.d"
 import { Role, RoleState } from "./roleState.d"
 import { DoomsayerGuess } from "../menu/game/gameScreenContent/AbilityMenu/RoleSpecificMenus/LargeDoomsayerMenu"
 import { KiraGuess } from "../menu/game/gameScreenContent/AbilityMenu/AbilitySelectionTypes/KiraSelectionMenu"
@@ -25,15 +25,32 @@ export type ToClientPacket = {
     type: "forcedOutsideLobby"
 } | {
     type: "forcedDisconnect"
+} | {
+    type: "serverAnnouncement",
+    message: string
+} | {
+    type: "serverRestarting",
+    inSecs: number
+} | {
+    type: "hello",
+    protocolVersion: number,
+    capabilities: Capability[]
+} | {
+    type: "rejectHello",
+    reason: "helloRequired" | "clientTooOld" | "clientTooNew",
+    protocolVersion: number
 } | {
     type: "lobbyList",
-    lobbies: Record<number, LobbyPreviewData>,
+    lobbies: Record<string, LobbyPreviewData>,
 } | {
     type: "acceptJoin",
-    roomCode: number,
+    roomCode: string,
     inGame: boolean,
     playerId: number,
-    spectator: boolean
+    spectator: boolean,
+    reconnectToken: string,
+    /** The client rejoined and is only sent what it missed, so it keeps what it has */
+    resumed: boolean
 } | {
     type: "rejectJoin",
     reason: string
@@ -48,6 +65,13 @@ export type ToClientPacket = {
 } | {
     type: "lobbyName",
     name: string
+} | {
+    type: "roomAccess",
+    private: boolean,
+    hasPassword: boolean
+} | {
+    type: "inviteCode",
+    code: string
 } | {
     type: "yourPlayerIndex",
     playerIndex: PlayerIndex
@@ -56,7 +80,7 @@ export type ToClientPacket = {
     fellowInsiders: PlayerIndex[]
 } | {
     type: "rejectStart",
-    reason: string
+    reason: RejectStartReason
 } | {
     type: "playersHost",
     hosts: LobbyClientID[],
@@ -82,6 +106,12 @@ export type ToClientPacket = {
     type: "roleOutline",
     index: number,
     roleOutline: RoleOutline
+} | {
+    type: "roleListAnalysis",
+    analysis: RoleListAnalysis
+} | {
+    type: "roleListConstraints",
+    constraints: RoleListConstraint[]
 } | {
     type: "phaseTime",
     phase: Exclude<PhaseState, { type: "recess" }>, 
@@ -119,6 +149,10 @@ export type ToClientPacket = {
 } | {
     type: "yourAllowedControllers",
     save: ListMapData<ControllerID, SavedController>,
+} | {
+    type: "yourAllowedControllersUpdate",
+    changed: ListMapData<ControllerID, SavedController>,
+    removed: ControllerID[],
 } | {
     type: "yourRoleLabels",
     roleLabels: ListMapData<PlayerIndex, Role> 
@@ -163,19 +197,53 @@ export type ToClientPacket = {
     player: PlayerIndex | null
 }
 
+/** Outlines are numbered by their index in the role list, starting from 0 */
+export type RejectStartReason = {
+    type: "tooManyClients" | "roleListTooSmall" | "zeroTimeGame" | "playerDisconnected"
+} | {
+    type: "gameEndsInstantly",
+    conclusion: Conclusion
+} | ({
+    type: "roleListCannotCreateRoles"
+} & RoleGenerationError)
+
+/** Every chance is between 0 and 1 */
+export type RoleListAnalysis = {
+    problem: RoleGenerationError | null,
+    /** For each role set, the chance of each number of its roles, starting from none */
+    roleSetCounts: Partial<Record<RoleSet, number[]>>,
+    /** Roles that were never generated are left out */
+    roles: Partial<Record<Role, number>>,
+    endsInstantly: number
+}
+
+/** Raise this whenever the server's protocol version is raised */
+export const PROTOCOL_VERSION = 3;
+/** Optional packet kinds this client understands */
+export type Capability = "roomAccess" | "messagePack" | "serverNotices" | "roleListAnalysis" | "roleListConstraints" | "allowedControllersUpdate";
+export const CAPABILITIES: Capability[] = ["roomAccess", "messagePack", "serverNotices", "roleListAnalysis", "roleListConstraints", "allowedControllersUpdate"];
+
 export type ToServerPacket = {
     type: "ping",
+} | {
+    type: "hello",
+    protocolVersion: number,
+    capabilities: Capability[]
 } | {
     type: "lobbyListRequest",
 } | {
     type: "hostDataRequest",
 } | {
     type: "reJoin",
-    roomCode: number,
+    roomCode: string,
     playerId: number,
+    reconnectToken: string,
+    lastSequence?: number
 } | {
     type: "join", 
-    roomCode: number
+    roomCode: string,
+    password?: string,
+    inviteCode?: string
 } | {
     type: "host",
 } | {
@@ -186,6 +254,16 @@ export type ToServerPacket = {
     playerId: number
 } | {
     type: "relinquishHost",
+} | {
+    type: "hostAddBot",
+} | {
+    type: "hostSetRoomPrivate",
+    private: boolean
+} | {
+    type: "hostSetRoomPassword",
+    password: string | null
+} | {
+    type: "hostCreateInviteCode",
 }
 // Lobby
 | {
@@ -214,6 +292,11 @@ export type ToServerPacket = {
     roleOutline: RoleOutline
 } | {
     type: "simplifyRoleList"
+} | {
+    type: "analyzeRoleList"
+} | {
+    type: "setRoleListConstraints",
+    constraints: RoleListConstraint[]
 } | {
     type: "setPhaseTime", 
     phase: PhaseType, 
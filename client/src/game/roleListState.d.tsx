@@ -46,13 +46,66 @@ export function getRoleSetsFromRole(role: Role): RoleSet[] {
 
 export type RoleOutline = RoleOutlineOption[];
 
-export type RoleOutlineOption = ({
+export type RoleOutlineOptionRoles = {
     roleSet: RoleSet
 } | {
     role: Role
-}) & {
+};
+
+export type RoleOutlineOption = RoleOutlineOptionRoles & {
     winIfAny?: Conclusion[],
-    insiderGroups?: InsiderGroup[]
+    insiderGroups?: InsiderGroup[],
+    /** How likely this option is compared to the outline's others, from 0 to 65535. Without one, each of the option's roles counts once,
+     * so mixing weighted and unweighted options compares weights to numbers of roles */
+    weight?: number
+}
+
+/** Why a role list can't be generated. Outlines are numbered from 0 */
+export type RoleGenerationError = {
+    problem: "noEnabledRoles" | "allOptionsDisabled" | "zeroWeights",
+    outline: number
+} | {
+    problem: "maximumCountExhausted",
+    outlines: number[],
+    roles: Role[]
+} | {
+    problem: "constraintsCantBeMet",
+    constraints: number[]
+} | {
+    problem: "constraintsTooComplex"
+}
+
+export function translateRoleGenerationError(error: RoleGenerationError): string {
+    // Outlines are numbered from 1 in the outline list
+    if (error.problem === "maximumCountExhausted") {
+        return translate("notification.rejectStart.roleListCannotCreateRoles.maximumCountExhausted",
+            error.outlines.map(outline => outline + 1).join(", "),
+            error.roles.map(role => translate("role."+role+".name")).join(", ")
+        );
+    } else if (error.problem === "constraintsCantBeMet") {
+        return translate("notification.rejectStart.roleListCannotCreateRoles.constraintsCantBeMet",
+            error.constraints.map(constraint => constraint + 1).join(", ")
+        );
+    } else if (error.problem === "constraintsTooComplex") {
+        return translate("notification.rejectStart.roleListCannotCreateRoles.constraintsTooComplex");
+    } else {
+        return translate("notification.rejectStart.roleListCannotCreateRoles."+error.problem, error.outline + 1);
+    }
+}
+
+/** A count of roles. `perPlayers` is one for every that many players, rounded down */
+export type ConstraintCount = { fixed: number } | { perPlayers: number };
+
+/** A rule over the whole generated role list. Constraints are numbered from 0 */
+export type RoleListConstraint = {
+    type: "count",
+    roles: RoleOutlineOptionRoles,
+    min?: ConstraintCount,
+    max?: ConstraintCount
+} | {
+    type: "exclusive",
+    first: RoleOutlineOptionRoles,
+    second: RoleOutlineOptionRoles
 }
 
 export type RoleOrRoleSet = ({
@@ -117,6 +170,9 @@ export function getRolesFromRoleOrRoleSet(roleOrRoleSet: RoleOrRoleSet): Role[]
 }
 
 export function simplifyRoleOutline(roleOutline: RoleOutline): RoleOutline {
+    // Merging options would change how likely weighted ones are
+    if (roleOutline.some(option => option.weight !== undefined)) return roleOutline;
+
     let newOptions = [...roleOutline];
 
     newOptions = newOptions.filter((item, index, self) => {
@@ -101,16 +101,13 @@ export default function GlobalMenu(): ReactElement {
 }
 
 export function RoomLinkButton(): JSX.Element {
-    const code = useLobbyOrGameState(
-        state => {
-            const code = new URL(window.location.href);
-            code.pathname = "/connect"
-            code.searchParams.set("code", state.roomCode.toString(18))
-            return code;
-        }, ["acceptJoin", "backToLobby"]
-    )!;
+    const roomCode = useLobbyOrGameState(state => state.roomCode, ["acceptJoin", "backToLobby"])!;
+    const code = new URL(window.location.href);
+    code.pathname = "/connect"
+    code.searchParams.set("code", roomCode)
     
+    // The code is shown so it can be read out to other players
     return <CopyButton text={code.toString()}>
-        <Icon>link</Icon> {translate("menu.play.field.roomCode")}
+        <Icon>link</Icon> {translate("menu.play.field.roomCode")} <code>{roomCode}</code>
     </CopyButton>
 }
\ No newline at end of file
@@ -88,7 +88,7 @@ function showThisController(id: ControllerID): boolean {
 export default function GenericAbilityMenu(): ReactElement {
     const savedAbilities = usePlayerState(
         playerState => playerState.savedControllers,
-        ["yourAllowedControllers"]
+        ["yourAllowedControllers", "yourAllowedControllersUpdate"]
     )!;
 
     let controllerGroupsMap: ControllerGroupsMap = new ListMap();
@@ -0,0 +1,51 @@
+import React, { ReactElement, useState } from "react";
+import GAME_MANAGER from "../..";
+import translate from "../../game/lang";
+import Icon from "../../components/Icon";
+import { useLobbyState } from "../../components/useHooks";
+import { Button } from "../../components/Button";
+import { CopyButton } from "../../components/ClipboardButtons";
+
+/** The host's controls for who can join the lobby */
+export default function LobbyAccessPane(): ReactElement {
+    const isPrivate = useLobbyState(state => state.private, ["roomAccess"])!;
+    const hasPassword = useLobbyState(state => state.hasPassword, ["roomAccess"])!;
+    const inviteLink = useLobbyState(
+        state => {
+            if (state.inviteCode === null) return null;
+            const link = new URL(window.location.href);
+            link.pathname = "/connect";
+            link.searchParams.set("code", state.roomCode);
+            link.searchParams.set("invite", state.inviteCode);
+            return link.toString();
+        },
+        ["inviteCode"]
+    );
+    const [password, setPassword] = useState<string>("");
+
+    return <div className="lobby-access-pane">
+        <label>
+            <input type="checkbox" checked={isPrivate}
+                onChange={e => GAME_MANAGER.sendSetRoomPrivatePacket(e.target.checked)}
+            />
+            {translate("menu.lobby.access.private")}
+        </label>
+        <label>
+            {hasPassword ? <Icon>lock</Icon> : <Icon>lock_open</Icon>}
+            <input type="password" value={password}
+                placeholder={translate("menu.lobby.access.password")}
+                onChange={e => setPassword(e.target.value)}
+                onKeyUp={e => {
+                    if (e.key === 'Enter') GAME_MANAGER.sendSetRoomPasswordPacket(password);
+                }}
+                onBlur={() => GAME_MANAGER.sendSetRoomPasswordPacket(password)}
+            />
+        </label>
+        <Button onClick={() => GAME_MANAGER.sendCreateInviteCodePacket()}>
+            <Icon>add_link</Icon> {translate("menu.lobby.access.createInvite")}
+        </Button>
+        {inviteLink && <CopyButton text={inviteLink}>
+            <Icon>link</Icon> {translate("menu.lobby.access.copyInvite")}
+        </CopyButton>}
+    </div>
+}
@@ -20,6 +20,9 @@ import { useLobbyState } from "../../components/useHooks";
 import { Button } from "../../components/Button";
 import { EnabledModifiersSelector } from "../../components/gameModeSettings/EnabledModifiersSelector";
 import LobbyNamePane from "./LobbyNamePane";
+import LobbyAccessPane from "./LobbyAccessPane";
+import RoleListAnalysisPane from "./RoleListAnalysisPane";
+import RoleListConstraintsPane from "./RoleListConstraintsPane";
 
 export default function LobbyMenu(): ReactElement {
     const isSpectator = useLobbyState(
@@ -154,6 +157,8 @@ function LobbyMenuSettings(props: Readonly<{
             onRemoveOutline={undefined}
             setRoleList={sendRoleList}
         />
+        <RoleListConstraintsPane disabled={!props.isHost}/>
+        <RoleListAnalysisPane/>
         <EnabledRoleSelector
             onEnableRoles={roles => GAME_MANAGER.sendEnabledRolesPacket([...enabledRoles, ...roles])}
             onDisableRoles={roles => GAME_MANAGER.sendEnabledRolesPacket(enabledRoles.filter(role => !roles.includes(role)))}
@@ -227,6 +232,7 @@ function LobbyMenuHeader(props: Readonly<{
             /> : 
             <h3>{lobbyName}</h3>
         }
+        {props.isHost && <LobbyAccessPane/>}
         
     </header>
 }
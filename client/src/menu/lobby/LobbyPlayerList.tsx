@@ -2,7 +2,7 @@ import React, { ReactElement, useRef, useState } from "react";
 import translate from "../../game/lang";
 import GAME_MANAGER from "../../index";
 import "./lobbyMenu.css";
-import { ClientConnection } from "../../game/gameState.d";
+import { ClientConnection, ConnectionQuality } from "../../game/gameState.d";
 import Icon from "../../components/Icon";
 import { useLobbyOrGameState } from "../../components/useHooks";
 import { Button, RawButton } from "../../components/Button";
@@ -14,6 +14,8 @@ type PlayerDisplayData = {
     id: number,
     clientType: "player" | "spectator",
     connection: ClientConnection,
+    /** Only hosts in a game know this */
+    connectionQuality: ConnectionQuality | null,
     ready: boolean | null,
     host: boolean,
     name: string | null,
@@ -31,6 +33,7 @@ export default function LobbyPlayerList(): ReactElement {
                         clientType: player.clientType.type,
                         ready: player.ready === "ready",
                         connection: player.connection,
+                        connectionQuality: null,
                         host: player.ready === "host",
                         name,
                         displayName: name ?? "Spectator",
@@ -42,6 +45,7 @@ export default function LobbyPlayerList(): ReactElement {
                         id,
                         clientType: player.clientType.type,
                         connection: player.connection,
+                        connectionQuality: player.connectionQuality,
                         ready: null,
                         host: player.host,
                         name: player.clientType.type === "player"
@@ -77,6 +81,9 @@ export default function LobbyPlayerList(): ReactElement {
                 }
             </ol>
         </div>
+        {host && <Button onClick={() => GAME_MANAGER.sendAddBotPacket()}>
+            <Icon>smart_toy</Icon> {translate("menu.hostSettings.addBot")}
+        </Button>}
         {host && <>
             <h2>{translate("menu.hostSettings.spectators")}</h2>
             <div className="lobby-player-list">
@@ -111,16 +118,21 @@ function LobbyPlayerListPlayer(props: Readonly<{ player: PlayerDisplayData }>):
     const [renameOpen, setRenameOpen] = useState(false);
     const renameButtonRef = useRef<HTMLButtonElement>(null);
 
-    return <li key={props.player.id} className={props.player.connection==="connected" ? "" : "keyword-dead"}>
+    return <li key={props.player.id} className={props.player.connection==="connected" || props.player.connection==="bot" ? "" : "keyword-dead"}>
         <div>
+            {props.player.connection === "bot" && <Icon>smart_toy</Icon>}
             {props.player.connection === "couldReconnect" && <Icon>signal_cellular_connected_no_internet_4_bar</Icon>}
             {props.player.connection === "disconnected" && <Icon>sentiment_very_dissatisfied</Icon>}
             {props.player.host && <Icon>shield</Icon>}
             {props.player.ready && <Icon>check</Icon>}
+            {props.player.connectionQuality?.rating === "lagging" && <Icon>network_ping</Icon>}
             <StyledText>{props.player.displayName}</StyledText>
+            {props.player.connectionQuality?.roundTripMillis != null && 
+                <span>{translate("menu.hostSettings.roundTrip", props.player.connectionQuality.roundTripMillis)}</span>
+            }
         </div>
         <div>
-            {host && !props.player.host && <button
+            {host && !props.player.host && props.player.connection !== "bot" && <button
                 onClick={() => GAME_MANAGER.sendSetPlayerHostPacket(props.player.id)}
             ><Icon>add_moderator</Icon></button>}
             {host && props.player.connection !== "disconnected" && <button 
@@ -0,0 +1,47 @@
+import React, { ReactElement, useEffect } from "react";
+import GAME_MANAGER from "../..";
+import translate from "../../game/lang";
+import { useLobbyState } from "../../components/useHooks";
+import { translateRoleGenerationError } from "../../game/roleListState.d";
+
+const FACTIONS = ["town", "mafia", "cult", "fiends", "neutral", "minions"] as const;
+
+/** A preview of what the role list generates, kept up to date as it's edited */
+export default function RoleListAnalysisPane(): ReactElement {
+    const roleList = useLobbyState(state => state.roleList, ["roleList", "roleOutline"])!;
+    const enabledRoles = useLobbyState(state => state.enabledRoles, ["enabledRoles"])!;
+    const constraints = useLobbyState(state => state.roleListConstraints, ["roleListConstraints"])!;
+    const analysis = useLobbyState(state => state.roleListAnalysis, ["roleListAnalysis"]) ?? null;
+
+    useEffect(() => {
+        // Wait for edits to settle, since the server generates the list many times for each analysis
+        const timeout = setTimeout(() => GAME_MANAGER.sendAnalyzeRoleListPacket(), 500);
+        return () => clearTimeout(timeout);
+    }, [roleList, enabledRoles, constraints]);
+
+    if (analysis === null) {
+        return <div className="role-list-analysis-pane"/>
+    }
+
+    return <div className="role-list-analysis-pane">
+        <h2>{translate("menu.lobby.roleListAnalysis")}</h2>
+        {analysis.problem !== null
+            ? <p>{translateRoleGenerationError(analysis.problem)}</p>
+            : <>
+                <ul>
+                    {FACTIONS.map(faction => <li key={faction}>
+                        {translate("menu.lobby.roleListAnalysis.faction", translate(faction), averageCount(analysis.roleSetCounts[faction] ?? []).toFixed(1))}
+                    </li>)}
+                </ul>
+                {analysis.endsInstantly > 0 && <p>
+                    {translate("menu.lobby.roleListAnalysis.endsInstantly", Math.round(analysis.endsInstantly * 100))}
+                </p>}
+            </>
+        }
+    </div>
+}
+
+/** `chances` is the chance of each count, starting from none */
+function averageCount(chances: number[]): number {
+    return chances.reduce((total, chance, count) => total + chance * count, 0);
+}
@@ -0,0 +1,130 @@
+import React, { ReactElement } from "react";
+import GAME_MANAGER from "../..";
+import translate from "../../game/lang";
+import Icon from "../../components/Icon";
+import { useLobbyState } from "../../components/useHooks";
+import { Button } from "../../components/Button";
+import { RoleOrRoleSetSelector } from "../../components/gameModeSettings/OutlineSelector";
+import { ConstraintCount, RoleListConstraint, RoleOrRoleSet, RoleOutlineOptionRoles } from "../../game/roleListState.d";
+
+/** Matches the server's limit */
+const MAX_ROLE_LIST_CONSTRAINTS = 32;
+
+/** Rules the host puts on the whole generated role list */
+export default function RoleListConstraintsPane(props: Readonly<{ disabled: boolean }>): ReactElement {
+    const constraints = useLobbyState(state => state.roleListConstraints, ["roleListConstraints"])!;
+
+    const setConstraint = (index: number, constraint: RoleListConstraint) => {
+        const newConstraints = [...constraints];
+        newConstraints[index] = constraint;
+        GAME_MANAGER.sendSetRoleListConstraintsPacket(newConstraints);
+    }
+
+    return <div className="role-list-constraints-pane">
+        <h2>{translate("menu.lobby.roleListConstraints")}</h2>
+        {constraints.map((constraint, index) => <div key={index} className="role-list-constraint">
+            <span>{index + 1}.</span>
+            <select
+                disabled={props.disabled}
+                value={constraint.type}
+                onChange={e => setConstraint(index, newConstraint(e.target.value as RoleListConstraint["type"]))}
+            >
+                <option value="count">{translate("menu.lobby.roleListConstraints.count")}</option>
+                <option value="exclusive">{translate("menu.lobby.roleListConstraints.exclusive")}</option>
+            </select>
+            {constraint.type === "count" ? <>
+                <RoleOrRoleSetSelector
+                    disabled={props.disabled}
+                    roleOrRoleSet={toRoleOrRoleSet(constraint.roles)}
+                    onChange={value => setConstraint(index, { ...constraint, roles: fromRoleOrRoleSet(value) })}
+                />
+                <ConstraintCountSelector
+                    disabled={props.disabled}
+                    label={translate("menu.lobby.roleListConstraints.min")}
+                    count={constraint.min}
+                    onChange={min => setConstraint(index, { ...constraint, min })}
+                />
+                <ConstraintCountSelector
+                    disabled={props.disabled}
+                    label={translate("menu.lobby.roleListConstraints.max")}
+                    count={constraint.max}
+                    onChange={max => setConstraint(index, { ...constraint, max })}
+                />
+            </> : <>
+                <RoleOrRoleSetSelector
+                    disabled={props.disabled}
+                    roleOrRoleSet={toRoleOrRoleSet(constraint.first)}
+                    onChange={value => setConstraint(index, { ...constraint, first: fromRoleOrRoleSet(value) })}
+                />
+                <RoleOrRoleSetSelector
+                    disabled={props.disabled}
+                    roleOrRoleSet={toRoleOrRoleSet(constraint.second)}
+                    onChange={value => setConstraint(index, { ...constraint, second: fromRoleOrRoleSet(value) })}
+                />
+            </>}
+            {!props.disabled && <Button
+                onClick={() => GAME_MANAGER.sendSetRoleListConstraintsPacket(constraints.filter((_, i) => i !== index))}
+            >
+                <Icon>delete</Icon>
+            </Button>}
+        </div>)}
+        {!props.disabled && constraints.length < MAX_ROLE_LIST_CONSTRAINTS && <Button
+            onClick={() => GAME_MANAGER.sendSetRoleListConstraintsPacket([...constraints, newConstraint("count")])}
+        >
+            <Icon>add</Icon> {translate("menu.lobby.roleListConstraints.add")}
+        </Button>}
+    </div>
+}
+
+function ConstraintCountSelector(props: Readonly<{
+    disabled: boolean,
+    label: string,
+    count: ConstraintCount | undefined,
+    onChange: (count: ConstraintCount | undefined) => void
+}>): ReactElement {
+    const kind = props.count === undefined ? "none" : "fixed" in props.count ? "fixed" : "perPlayers";
+    const value = props.count === undefined ? 0 : "fixed" in props.count ? props.count.fixed : props.count.perPlayers;
+
+    const setCount = (kind: string, value: number) => {
+        value = Math.max(0, Math.min(255, Math.floor(value)));
+        switch (kind) {
+            case "fixed": props.onChange({ fixed: value }); break;
+            case "perPlayers": props.onChange({ perPlayers: value }); break;
+            default: props.onChange(undefined);
+        }
+    }
+
+    return <label>
+        {props.label}
+        <select disabled={props.disabled} value={kind} onChange={e => setCount(e.target.value, value || 1)}>
+            <option value="none">{translate("menu.lobby.roleListConstraints.none")}</option>
+            <option value="fixed">{translate("menu.lobby.roleListConstraints.fixed")}</option>
+            <option value="perPlayers">{translate("menu.lobby.roleListConstraints.perPlayers")}</option>
+        </select>
+        {props.count !== undefined && <input
+            type="number"
+            min={0}
+            max={255}
+            disabled={props.disabled}
+            value={value}
+            onChange={e => setCount(kind, Number(e.target.value))}
+        />}
+    </label>
+}
+
+function newConstraint(type: RoleListConstraint["type"]): RoleListConstraint {
+    switch (type) {
+        case "count":
+            return { type, roles: { roleSet: "any" }, max: { fixed: 1 } };
+        case "exclusive":
+            return { type, first: { roleSet: "any" }, second: { roleSet: "any" } };
+    }
+}
+
+function toRoleOrRoleSet(roles: RoleOutlineOptionRoles): RoleOrRoleSet {
+    return "role" in roles ? { type: "role", role: roles.role } : { type: "roleSet", roleSet: roles.roleSet };
+}
+
+function fromRoleOrRoleSet(value: RoleOrRoleSet): RoleOutlineOptionRoles {
+    return value.type === "role" ? { role: value.role } : { roleSet: value.roleSet };
+}
@@ -8,6 +8,7 @@ import { StateListener } from "../../game/gameManager.d";
 import { LobbyPreviewData } from "../../game/packet";
 import LobbyMenu from "../lobby/LobbyMenu";
 import PlayMenuJoinPopup from "./PlayMenuJoinPopup";
+import { loadReconnectData } from "../../game/localStorage";
 
 export default function PlayMenu(): ReactElement {
     const { setContent: setAnchorContent } = useContext(AnchorControllerContext)!;
@@ -20,16 +21,21 @@ export default function PlayMenu(): ReactElement {
     })
 
     const joinGame = useCallback(
-        async (roomCode?: number, playerId?: number): Promise<boolean> => {
+        async (roomCode?: string, playerId?: number, password?: string): Promise<boolean> => {
             if (roomCode === undefined) return false;
         
             setAnchorContent(<LoadingScreen type="join"/>);
         
             let success: boolean;
             if (playerId === undefined) {
-                success = await GAME_MANAGER.sendJoinPacket(roomCode);
+                success = await GAME_MANAGER.sendJoinPacket(roomCode, password === "" ? undefined : password);
             } else {
-                success = await GAME_MANAGER.sendRejoinPacket(roomCode, playerId);
+                // Only this player's own client has the token to rejoin as them
+                const reconnectData = loadReconnectData();
+                const reconnectToken = reconnectData?.roomCode === roomCode && reconnectData.playerId === playerId
+                    ? reconnectData.reconnectToken
+                    : "";
+                success = await GAME_MANAGER.sendRejoinPacket(roomCode, playerId, reconnectToken);
             }
         
             if (!success) {
@@ -73,30 +79,27 @@ export default function PlayMenu(): ReactElement {
 }
 
 function PlayMenuFooter(props: Readonly<{
-    joinGame: (roomCode?: number, playerId?: number) => Promise<boolean>
+    joinGame: (roomCode?: string, playerId?: number, password?: string) => Promise<boolean>
 }>): ReactElement {
     const [roomCode, setRoomCode] = useState<number | undefined>(undefined);
     const [playerID, setPlayerID] = useState<number | undefined>(undefined);
+    const [password, setPassword] = useState<string>("");
 
     return <footer>
         <div>
             <label>{translate("menu.play.field.roomCode")}</label>
-            <input type="text" value={roomCode?.toString(18)} 
+            <input type="text" value={roomCode ?? ""} 
                 onChange={(e)=>{
-                    const value = e.target.value;
+                    // Codes are case-insensitive, and only have letters, or digits if they're old
+                    const value = e.target.value.toUpperCase().replace(/[^A-Z0-9]/g, "");
                     if (value === "") {
                         setRoomCode(undefined);
                     } else {
-                        try {
-                            const code = parseInt(value, 18);
-                            if (!isNaN(code)) {
-                                setRoomCode(code)
-                            }
-                        } catch (_) {}
+                        setRoomCode(value);
                     }}}
                 onKeyUp={(e)=>{
                     if(e.key === 'Enter') {
-                        props.joinGame(roomCode);
+                        props.joinGame(roomCode, undefined, password);
                     }
                 }}
             />
@@ -119,23 +122,34 @@ function PlayMenuFooter(props: Readonly<{
                 }}
                 onKeyUp={(e)=>{
                     if(e.key === 'Enter') {
-                        props.joinGame(roomCode, playerID);
+                        props.joinGame(roomCode, playerID, password);
+                    }
+                }}
+            />
+        </div>
+        <div>
+            <label>{translate("menu.play.field.password")}</label>
+            <input type="password" value={password}
+                onChange={(e)=>setPassword(e.target.value)}
+                onKeyUp={(e)=>{
+                    if(e.key === 'Enter') {
+                        props.joinGame(roomCode, playerID, password);
                     }
                 }}
             />
         </div>
         <button onClick={()=>{
-            props.joinGame(roomCode, playerID)
+            props.joinGame(roomCode, playerID, password)
         }}>
             {translate("menu.play.button.join")}
         </button>
     </footer>
 }
 
-type LobbyMap = Map<number, LobbyPreviewData>;
+type LobbyMap = Map<string, LobbyPreviewData>;
 
 function PlayMenuTable(props: Readonly<{
-    joinGame: (roomCode?: number, playerId?: number) => Promise<boolean>
+    joinGame: (roomCode?: string, playerId?: number) => Promise<boolean>
 }>): ReactElement {
     const [lobbies, setLobbies] = useState<LobbyMap>(new Map());
     const { setCoverCard } = useContext(AnchorControllerContext)!;
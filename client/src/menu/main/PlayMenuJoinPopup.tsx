@@ -4,9 +4,9 @@ import translate from "../../game/lang";
 import "./playMenuJoinPopup.css";
 
 export default function PlayMenuJoinPopup(props: Readonly<{
-    roomCode: number,
+    roomCode: string,
     lobbyData: LobbyPreviewData,
-    joinGame: (roomCode?: number, playerId?: number) => void
+    joinGame: (roomCode?: string, playerId?: number) => void
 }>): ReactElement {
 
     return <div className="play-menu-join-popup">
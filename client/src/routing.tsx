@@ -29,7 +29,7 @@ async function routeWiki(anchorController: AnchorController, page: string) {
     }
 }
 
-async function routeLobby(anchorController: AnchorController, roomCode: string) {
+async function routeLobby(anchorController: AnchorController, roomCode: string, inviteCode: string | null) {
     const reconnectData = loadReconnectData();
 
     if (!await GAME_MANAGER.setOutsideLobbyState()) {
@@ -41,17 +41,17 @@ async function routeLobby(anchorController: AnchorController, roomCode: string)
 
     let success: boolean;
     try {
-        const code = parseInt(roomCode, 18)
+        const code = roomCode.toUpperCase();
         if (reconnectData) {
-            success = await GAME_MANAGER.sendRejoinPacket(code, reconnectData.playerId);
+            success = await GAME_MANAGER.sendRejoinPacket(code, reconnectData.playerId, reconnectData.reconnectToken);
             
 
             if(!success) {
                 deleteReconnectData();
-                success = await GAME_MANAGER.sendJoinPacket(code);
+                success = await GAME_MANAGER.sendJoinPacket(code, undefined, inviteCode ?? undefined);
             }
         }else{
-            success = await GAME_MANAGER.sendJoinPacket(code);
+            success = await GAME_MANAGER.sendJoinPacket(code, undefined, inviteCode ?? undefined);
         }
     } catch {
         success = false;
@@ -110,7 +110,7 @@ async function routeMainButFirstTryUsingReconnectData(anchorController: AnchorCo
         return;
     }
 
-    if (!await GAME_MANAGER.sendRejoinPacket(reconnectData.roomCode, reconnectData.playerId)) {
+    if (!await GAME_MANAGER.sendRejoinPacket(reconnectData.roomCode, reconnectData.playerId, reconnectData.reconnectToken)) {
         anchorController.setContent(<StartMenu/>);
         deleteReconnectData();
         return;
@@ -124,9 +124,10 @@ export default async function route(anchorController: AnchorController, url: Loc
     if (url.pathname.startsWith("/wiki")) {
         return await routeWiki(anchorController, url.pathname.substring(5));
     } else if (url.pathname.startsWith("/connect")) {
-        const roomCode = new URLSearchParams(url.search).get("code");
+        const params = new URLSearchParams(url.search);
+        const roomCode = params.get("code");
         if (roomCode !== null) {
-            return await routeLobby(anchorController, roomCode);
+            return await routeLobby(anchorController, roomCode, params.get("invite"));
         }
     } else if (url.pathname.startsWith("/gameMode")) {
         const gameMode = new URLSearchParams(url.search).get("mode");
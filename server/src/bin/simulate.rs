@@ -0,0 +1,263 @@
+//! Plays many games between bots and prints how they ended, to check whether a game mode is balanced.
+//!
+//! ```text
+//! cargo run --release --bin simulate -- <settings file> [--games N] [--seed N] [--mode NAME] [--players N]
+//! ```
+//!
+//! The settings file is either a single settings object, or a game modes file like `defaultGameModes.json`,
+//! in which case `--mode` and `--players` pick which settings to use.
+
+use std::{collections::{HashMap, VecDeque}, panic::{self, AssertUnwindSafe}, time::Duration};
+
+use mafia_server::{
+    client_connection::ClientConnection,
+    game::{
+        bot::Bot, game_client::{GameClient, GameClientLocation}, game_conclusion::GameConclusion,
+        grave::{GraveDeathCause, GraveInformation}, player::{PlayerInitializeParameters, PlayerReference},
+        rng::GameSeed, settings::Settings, Game, RejectStartReason
+    },
+    room::{RoomClientID, RoomState}, vec_map::VecMap, websocket_connections::connection::ClientSender
+};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+struct Arguments {
+    settings_path: String,
+    games: u32,
+    seed: GameSeed,
+    mode: Option<String>,
+    players: Option<u8>,
+}
+
+struct GameResult {
+    /// `None` if the game reached the last day without ending
+    conclusion: Option<GameConclusion>,
+    days: u8,
+    death_causes: Vec<String>,
+}
+
+#[derive(Default)]
+struct Stats {
+    games: u32,
+    conclusions: HashMap<GameConclusion, u32>,
+    /// Games that reached the last day without ending, which aren't counted as draws
+    unfinished: u32,
+    /// Summed over finished games only
+    total_days: u64,
+    death_causes: HashMap<String, u32>,
+    rejected: HashMap<String, u32>,
+    crashed_seeds: Vec<GameSeed>,
+}
+
+fn main() {
+    let arguments = match parse_arguments(std::env::args().skip(1)) {
+        Ok(arguments) => arguments,
+        Err(message) => {
+            eprintln!("{message}");
+            eprintln!("Usage: simulate <settings file> [--games N] [--seed N] [--mode NAME] [--players N]");
+            std::process::exit(1);
+        }
+    };
+
+    let settings = match load_settings(&arguments) {
+        Ok(settings) => settings,
+        Err(message) => {
+            eprintln!("{message}");
+            std::process::exit(1);
+        }
+    };
+    let players = arguments.players.unwrap_or(settings.role_list.0.len().try_into().unwrap_or(u8::MAX));
+
+    // Panics are counted as crashes, the default hook would print every one of them
+    panic::set_hook(Box::new(|_| {}));
+
+    let mut stats = Stats::default();
+    for game_index in 0..arguments.games {
+        let seed = arguments.seed.wrapping_add(game_index.into());
+        stats.games = stats.games.saturating_add(1);
+
+        match panic::catch_unwind(AssertUnwindSafe(|| simulate_game(&settings, players, seed))) {
+            Ok(Ok(result)) => stats.add(result),
+            Ok(Err(reason)) => *stats.rejected.entry(format!("{reason:?}")).or_default() += 1,
+            Err(_) => stats.crashed_seeds.push(seed),
+        }
+    }
+
+    stats.print(players, arguments.seed);
+}
+
+fn parse_arguments(mut arguments: impl Iterator<Item = String>) -> Result<Arguments, String> {
+    let mut parsed = Arguments {
+        settings_path: String::new(),
+        games: 1000,
+        seed: rand::random(),
+        mode: None,
+        players: None,
+    };
+
+    while let Some(argument) = arguments.next() {
+        let mut value = || arguments.next().ok_or(format!("Missing value for {argument}"));
+
+        match argument.as_str() {
+            "--games" => parsed.games = value()?.parse().map_err(|_| "--games must be a number")?,
+            "--seed" => parsed.seed = value()?.parse().map_err(|_| "--seed must be a number")?,
+            "--mode" => parsed.mode = Some(value()?),
+            "--players" => parsed.players = Some(value()?.parse().map_err(|_| "--players must be between 0 and 255")?),
+            _ if parsed.settings_path.is_empty() => parsed.settings_path = argument,
+            _ => return Err(format!("Unexpected argument {argument}")),
+        }
+    }
+
+    if parsed.settings_path.is_empty() {
+        return Err("Missing settings file".to_string());
+    }
+
+    Ok(parsed)
+}
+
+fn load_settings(arguments: &Arguments) -> Result<Settings, String> {
+    let file = std::fs::read_to_string(&arguments.settings_path)
+        .map_err(|err| format!("Couldn't read {}: {err}", arguments.settings_path))?;
+    let json: Value = serde_json::from_str(&file).map_err(|err| format!("Invalid JSON: {err}"))?;
+
+    let settings = if let Some(game_modes) = json.get("gameModes").and_then(Value::as_array) {
+        let (Some(mode), Some(players)) = (&arguments.mode, arguments.players) else {
+            return Err("A game modes file needs --mode and --players".to_string());
+        };
+        let game_mode = game_modes.iter()
+            .find(|game_mode| game_mode.get("name").and_then(Value::as_str) == Some(mode.as_str()))
+            .ok_or(format!("No game mode named {mode}"))?;
+
+        game_mode.get("data")
+            .and_then(|data| data.get(players.to_string()))
+            .ok_or(format!("{mode} has no settings for {players} players"))?
+            .clone()
+    } else {
+        json
+    };
+
+    serde_json::from_value(settings).map_err(|err| format!("Invalid settings: {err}"))
+}
+
+fn simulate_game(settings: &Settings, players: u8, seed: GameSeed) -> Result<GameResult, RejectStartReason> {
+    // Nobody listens to what the game sends
+    let sender = ClientSender::new(mpsc::unbounded_channel().0);
+
+    let mut clients = VecMap::new();
+    let mut player_parameters = Vec::new();
+    for index in 0..players {
+        let player = unsafe { PlayerReference::new_unchecked(index) };
+        clients.insert(RoomClientID::from(index), GameClient {
+            client_location: GameClientLocation::Player(player),
+            host: index == 0,
+            last_message_times: VecDeque::new(),
+        });
+        player_parameters.push(PlayerInitializeParameters {
+            connection: ClientConnection::Connected(sender.clone()),
+            name: format!("Bot {}", index.saturating_add(1)),
+            host: index == 0,
+        });
+    }
+
+    let mut game = Game::new("Simulation".to_string(), settings.clone(), clients, player_parameters, Vec::new(), seed)?;
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    while game.ticking {
+        for player in PlayerReference::all_players(&game).collect::<Vec<_>>() {
+            for packet in Bot::act(&game, player, &mut rng) {
+                let _ = game.on_client_message(&sender, RoomClientID::from(player.index()), packet);
+            }
+        }
+
+        // Skip straight to the end of the phase, then into the next one
+        let time_remaining = game.phase_time_remaining().unwrap_or(Duration::from_secs(1));
+        let _ = game.tick(time_remaining);
+        let _ = game.tick(Duration::ZERO);
+    }
+
+    Ok(GameResult {
+        conclusion: GameConclusion::game_is_over(&game),
+        days: game.day_number(),
+        death_causes: game.graves.iter()
+            .flat_map(|grave| match &grave.information {
+                GraveInformation::Obscured => vec!["Obscured".to_string()],
+                GraveInformation::Normal { death_cause: GraveDeathCause::Killers(killers), .. } =>
+                    killers.iter().map(|killer| format!("{killer:?}")).collect(),
+                GraveInformation::Normal { death_cause, .. } => vec![format!("{death_cause:?}")],
+            })
+            .collect(),
+    })
+}
+
+impl Stats {
+    fn add(&mut self, result: GameResult) {
+        match result.conclusion {
+            Some(conclusion) => {
+                *self.conclusions.entry(conclusion).or_default() += 1;
+                self.total_days = self.total_days.saturating_add(result.days.into());
+            }
+            None => self.unfinished = self.unfinished.saturating_add(1),
+        }
+        for death_cause in result.death_causes {
+            *self.death_causes.entry(death_cause).or_default() += 1;
+        }
+    }
+
+    fn print(&self, players: u8, seed: GameSeed) {
+        let finished: u32 = self.conclusions.values().sum();
+
+        println!("Simulated {} games with {players} players, starting from seed {seed}", self.games);
+        println!();
+
+        println!("Conclusions ({finished} finished games):");
+        for (conclusion, count) in sorted_by_count(&self.conclusions) {
+            println!("  {:<16} {count:>8} {:>7.2}%", format!("{conclusion:?}"), percentage(count, finished));
+        }
+        println!();
+
+        if self.unfinished > 0 {
+            println!("Unfinished games, still going on the last day: {}", self.unfinished);
+            println!();
+        }
+
+        if finished > 0 {
+            println!("Average game length: {:.2} days", self.total_days as f64 / f64::from(finished));
+            println!();
+        }
+
+        let deaths: u32 = self.death_causes.values().sum();
+        println!("Death causes ({deaths} deaths):");
+        for (death_cause, count) in sorted_by_count(&self.death_causes) {
+            println!("  {:<32} {count:>8} {:>7.2}%", death_cause, percentage(count, deaths));
+        }
+
+        if !self.rejected.is_empty() {
+            println!();
+            println!("Games that couldn't start:");
+            for (reason, count) in sorted_by_count(&self.rejected) {
+                println!("  {reason:<32} {count:>8}");
+            }
+        }
+
+        if !self.crashed_seeds.is_empty() {
+            println!();
+            println!("{} games crashed, with seeds: {:?}", self.crashed_seeds.len(), self.crashed_seeds);
+        }
+    }
+}
+
+fn sorted_by_count<K: Clone + Ord>(counts: &HashMap<K, u32>) -> Vec<(K, u32)> {
+    let mut sorted: Vec<(K, u32)> = counts.iter().map(|(key, count)| (key.clone(), *count)).collect();
+    sorted.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    sorted
+}
+
+fn percentage(count: u32, total: u32) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        f64::from(count) * 100.0 / f64::from(total)
+    }
+}
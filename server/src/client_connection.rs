@@ -2,23 +2,56 @@ use std::time::Duration;
 
 use serde::Serialize;
 
-use crate::{packet::ToClientPacket, websocket_connections::connection::ClientSender};
+use crate::{packet::ToClientPacket, protocol::Capability, websocket_connections::{connection::ClientSender, heartbeat::ConnectionQuality}};
 
 #[derive(Clone, Debug)]
 pub enum ClientConnection {
     Connected(ClientSender),
-    CouldReconnect { disconnect_timer: Duration },
-    Disconnected
+    /// `sender` is the one the client had, if any, which keeps numbering what the client misses so it can be replayed
+    CouldReconnect { disconnect_timer: Duration, sender: Option<ClientSender> },
+    Disconnected,
+    /// A bot added by the host, it has no websocket so packets sent to it are dropped
+    Bot
 }
 impl ClientConnection {
     pub fn send_packet(&self, packet: ToClientPacket)->bool {
-        if let ClientConnection::Connected(ref sender) = self {
-            sender.send(packet);
-            true
-        }else{
-            false
+        match self {
+            ClientConnection::Connected(sender) => {
+                sender.send(packet);
+                true
+            },
+            ClientConnection::CouldReconnect { sender: Some(sender), .. } => {
+                sender.record(packet);
+                false
+            },
+            _ => false
+        }
+    }
+    /// Whether the client agreed to `capability`, see [`ClientSender::supports`].
+    /// Packets to clients without a sender are dropped anyway
+    pub fn supports(&self, capability: Capability) -> bool {
+        match self {
+            ClientConnection::Connected(sender) |
+            ClientConnection::CouldReconnect { sender: Some(sender), .. } => sender.supports(capability),
+            _ => true,
         }
     }
+    /// How well the client's websocket is doing, if it's connected to one
+    pub fn quality(&self) -> Option<ConnectionQuality> {
+        match self {
+            ClientConnection::Connected(sender) => sender.connection_quality(),
+            _ => None,
+        }
+    }
+    /// What this connection becomes when the client loses it
+    pub fn lost(&self, disconnect_timer: Duration) -> Self {
+        let sender = match self {
+            ClientConnection::Connected(sender) => Some(sender.clone()),
+            ClientConnection::CouldReconnect { sender, .. } => sender.clone(),
+            _ => None,
+        };
+        ClientConnection::CouldReconnect { disconnect_timer, sender }
+    }
 }
 impl Serialize for ClientConnection{
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -28,6 +61,7 @@ impl Serialize for ClientConnection{
             ClientConnection::Connected(_) => serializer.serialize_str("connected"),
             ClientConnection::CouldReconnect { .. } => {serializer.serialize_str("couldReconnect")}
             ClientConnection::Disconnected => serializer.serialize_str("disconnected"),
+            ClientConnection::Bot => serializer.serialize_str("bot"),
         }
     }
 }
\ No newline at end of file
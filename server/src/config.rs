@@ -0,0 +1,305 @@
+//! Operator settings for the server.
+//!
+//! They are read from a TOML file (`config.toml`, or whatever `CONFIG_FILE` points to),
+//! then any environment variable named after a setting in upper case overrides it, e.g. `WS_ADDRESS` or `MAX_ROOMS`.
+//! Every setting but `ws_address` has a default, so it's the only one that has to be set,
+//! so the server never opens a port nobody asked for.
+//! See `config.example.toml` for every setting.
+
+use std::{fmt::Display, net::ToSocketAddrs, path::PathBuf, str::FromStr, sync::OnceLock, time::Duration};
+
+use serde::Deserialize;
+
+use crate::log::{LogFormat, LogLevel};
+
+const DEFAULT_CONFIG_FILE: &str = "config.toml";
+
+static CONFIG: OnceLock<ServerConfig> = OnceLock::new();
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    /// The address the websocket server listens on. Required, there's no default
+    pub ws_address: String,
+    /// Where to save the replay of every game that ends, replays aren't saved if this isn't set
+    pub replay_directory: Option<PathBuf>,
+    /// Where to snapshot rooms so they survive a restart, rooms aren't kept if this isn't set
+    pub data_directory: Option<PathBuf>,
+    /// Where to put the operator console's Unix socket, there's no console if this isn't set.
+    /// See [`crate::websocket_connections::admin`]
+    pub admin_socket: Option<PathBuf>,
+
+    /// How long the server waits between ticking every room
+    pub tick_interval_millis: u64,
+    pub snapshot_interval_secs: u64,
+
+    /// How long a player who lost connection during a game has to rejoin
+    pub game_disconnect_timer_secs: u64,
+    /// How long a player who lost connection in a lobby has to rejoin
+    pub lobby_disconnect_timer_secs: u64,
+    /// How long the clients of a lobby restored from a snapshot have to rejoin, since reloading after a restart takes a while.
+    /// Players in a restored game have `game_disconnect_timer_secs`
+    pub restored_lobby_disconnect_timer_secs: u64,
+    /// How many of the latest packets sent to each client are kept, to replay to a client that rejoins.
+    /// A client that missed more than this is sent everything again
+    pub client_outbox_size: usize,
+
+    /// How often the server pings every connection
+    pub heartbeat_interval_secs: u64,
+    /// How long a connection can go without answering before it's closed, and its player loses connection
+    pub heartbeat_timeout_secs: u64,
+
+    /// How many chat messages, whispers and verdicts a client can send per second, on average
+    pub message_rate_limit: u16,
+    /// How long the message rate limit is averaged over
+    pub message_rate_limit_window_secs: u64,
+
+    pub max_name_length: usize,
+
+    /// How many letters new room codes have, from 1 to 7
+    pub room_code_length: u32,
+
+    pub max_rooms: usize,
+    pub max_clients_per_room: usize,
+    /// How many websocket connections the server accepts at once
+    pub max_connections: usize,
+    /// How many websocket connections the server accepts at once from one IP address
+    pub max_connections_per_ip: usize,
+    /// How many rooms one IP address can create per room creation window
+    pub room_creation_limit: u16,
+    pub room_creation_window_secs: u64,
+    /// How many packets one IP address can send per second, on average, before it's banned
+    pub packet_rate_limit: u16,
+    /// How long the packet rate limit is averaged over
+    pub packet_rate_limit_window_secs: u64,
+    /// How long an IP address that sent too many packets is banned for
+    pub ip_ban_duration_secs: u64,
+
+    /// Lines less important than this aren't logged: `info`, `important`, `error` or `fatal`
+    pub log_level: LogLevel,
+    /// `pretty` for colored text, or `json` for one JSON object per line
+    pub log_format: LogFormat,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Read(PathBuf, std::io::Error),
+    Parse(PathBuf, toml::de::Error),
+    EnvironmentVariable { name: &'static str, value: String },
+    Invalid(&'static str),
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            ws_address: String::new(),
+            replay_directory: None,
+            data_directory: None,
+            admin_socket: None,
+
+            tick_interval_millis: 1000,
+            snapshot_interval_secs: 30,
+
+            game_disconnect_timer_secs: 60 * 2,
+            lobby_disconnect_timer_secs: 5,
+            restored_lobby_disconnect_timer_secs: 60 * 2,
+            client_outbox_size: 512,
+
+            heartbeat_interval_secs: 5,
+            heartbeat_timeout_secs: 15,
+
+            message_rate_limit: 1,
+            message_rate_limit_window_secs: 10,
+
+            max_name_length: 20,
+
+            room_code_length: 4,
+
+            max_rooms: 10_000,
+            max_clients_per_room: 64,
+            max_connections: 10_000,
+            max_connections_per_ip: 16,
+            room_creation_limit: 5,
+            room_creation_window_secs: 60,
+            packet_rate_limit: 30,
+            packet_rate_limit_window_secs: 10,
+            ip_ban_duration_secs: 60 * 5,
+
+            log_level: LogLevel::Info,
+            log_format: LogFormat::Pretty,
+        }
+    }
+}
+
+impl ServerConfig {
+    /// The config the server was started with.
+    /// Defaults are used if it was never set, like in tests and the simulator.
+    pub fn get() -> &'static ServerConfig {
+        CONFIG.get_or_init(ServerConfig::default)
+    }
+
+    /// Sets the config returned by [`ServerConfig::get`].
+    /// Does nothing if it was already set or read, so call this before starting the server.
+    pub fn set(config: ServerConfig) {
+        let _ = CONFIG.set(config);
+    }
+
+    /// Reads the config file and environment variables, then validates the result
+    pub fn load() -> Result<Self, ConfigError> {
+        let explicit_path = std::env::var("CONFIG_FILE").ok().map(PathBuf::from);
+        let path = explicit_path.clone().unwrap_or(PathBuf::from(DEFAULT_CONFIG_FILE));
+
+        let mut config = match std::fs::read_to_string(&path) {
+            Ok(file) => Self::from_toml(&file).map_err(|err| ConfigError::Parse(path, err))?,
+            // The default config file is optional, but one that was asked for isn't
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound && explicit_path.is_none() => Self::default(),
+            Err(err) => return Err(ConfigError::Read(path, err)),
+        };
+
+        config.override_with(|name| std::env::var(name).ok())?;
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    pub fn from_toml(file: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(file)
+    }
+
+    /// Overrides every setting that `variable` returns a value for, given the setting's name in upper case
+    pub fn override_with(&mut self, variable: impl Fn(&str) -> Option<String>) -> Result<(), ConfigError> {
+        fn parse<T: FromStr>(variable: &impl Fn(&str) -> Option<String>, name: &'static str, setting: &mut T) -> Result<(), ConfigError> {
+            if let Some(value) = variable(name) {
+                *setting = value.trim().parse().map_err(|_| ConfigError::EnvironmentVariable { name, value })?;
+            }
+            Ok(())
+        }
+        fn parse_optional<T: FromStr>(variable: &impl Fn(&str) -> Option<String>, name: &'static str, setting: &mut Option<T>) -> Result<(), ConfigError> {
+            if let Some(value) = variable(name) {
+                *setting = Some(value.trim().parse().map_err(|_| ConfigError::EnvironmentVariable { name, value })?);
+            }
+            Ok(())
+        }
+
+        parse(&variable, "WS_ADDRESS", &mut self.ws_address)?;
+        parse_optional(&variable, "REPLAY_DIRECTORY", &mut self.replay_directory)?;
+        parse_optional(&variable, "DATA_DIRECTORY", &mut self.data_directory)?;
+        parse_optional(&variable, "ADMIN_SOCKET", &mut self.admin_socket)?;
+        parse(&variable, "TICK_INTERVAL_MILLIS", &mut self.tick_interval_millis)?;
+        parse(&variable, "SNAPSHOT_INTERVAL_SECS", &mut self.snapshot_interval_secs)?;
+        parse(&variable, "GAME_DISCONNECT_TIMER_SECS", &mut self.game_disconnect_timer_secs)?;
+        parse(&variable, "LOBBY_DISCONNECT_TIMER_SECS", &mut self.lobby_disconnect_timer_secs)?;
+        parse(&variable, "RESTORED_LOBBY_DISCONNECT_TIMER_SECS", &mut self.restored_lobby_disconnect_timer_secs)?;
+        parse(&variable, "CLIENT_OUTBOX_SIZE", &mut self.client_outbox_size)?;
+        parse(&variable, "HEARTBEAT_INTERVAL_SECS", &mut self.heartbeat_interval_secs)?;
+        parse(&variable, "HEARTBEAT_TIMEOUT_SECS", &mut self.heartbeat_timeout_secs)?;
+        parse(&variable, "MESSAGE_RATE_LIMIT", &mut self.message_rate_limit)?;
+        parse(&variable, "MESSAGE_RATE_LIMIT_WINDOW_SECS", &mut self.message_rate_limit_window_secs)?;
+        parse(&variable, "MAX_NAME_LENGTH", &mut self.max_name_length)?;
+        parse(&variable, "ROOM_CODE_LENGTH", &mut self.room_code_length)?;
+        parse(&variable, "MAX_ROOMS", &mut self.max_rooms)?;
+        parse(&variable, "MAX_CLIENTS_PER_ROOM", &mut self.max_clients_per_room)?;
+        parse(&variable, "MAX_CONNECTIONS", &mut self.max_connections)?;
+        parse(&variable, "MAX_CONNECTIONS_PER_IP", &mut self.max_connections_per_ip)?;
+        parse(&variable, "ROOM_CREATION_LIMIT", &mut self.room_creation_limit)?;
+        parse(&variable, "ROOM_CREATION_WINDOW_SECS", &mut self.room_creation_window_secs)?;
+        parse(&variable, "PACKET_RATE_LIMIT", &mut self.packet_rate_limit)?;
+        parse(&variable, "PACKET_RATE_LIMIT_WINDOW_SECS", &mut self.packet_rate_limit_window_secs)?;
+        parse(&variable, "IP_BAN_DURATION_SECS", &mut self.ip_ban_duration_secs)?;
+        parse(&variable, "LOG_LEVEL", &mut self.log_level)?;
+        parse(&variable, "LOG_FORMAT", &mut self.log_format)?;
+
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.ws_address.is_empty() {
+            return Err(ConfigError::Invalid("ws_address must be set, in the config file or with WS_ADDRESS"));
+        }
+        if self.ws_address.to_socket_addrs().is_err() {
+            return Err(ConfigError::Invalid("ws_address must be a socket address, like 0.0.0.0:80"));
+        }
+        if self.tick_interval_millis == 0 {
+            return Err(ConfigError::Invalid("tick_interval_millis must be more than 0"));
+        }
+        if self.snapshot_interval_secs == 0 {
+            return Err(ConfigError::Invalid("snapshot_interval_secs must be more than 0"));
+        }
+        if self.game_disconnect_timer_secs == 0 || self.lobby_disconnect_timer_secs == 0 || self.restored_lobby_disconnect_timer_secs == 0 {
+            return Err(ConfigError::Invalid("disconnect timers must be more than 0 seconds"));
+        }
+        if self.heartbeat_interval_secs == 0 || self.heartbeat_timeout_secs <= self.heartbeat_interval_secs {
+            return Err(ConfigError::Invalid("heartbeat_interval_secs must be more than 0, and less than heartbeat_timeout_secs"));
+        }
+        if self.message_rate_limit == 0 || self.message_rate_limit_window_secs == 0 {
+            return Err(ConfigError::Invalid("message_rate_limit and message_rate_limit_window_secs must be more than 0"));
+        }
+        if self.max_name_length == 0 {
+            return Err(ConfigError::Invalid("max_name_length must be more than 0"));
+        }
+        if !(1..=7).contains(&self.room_code_length) {
+            return Err(ConfigError::Invalid("room_code_length must be from 1 to 7"));
+        }
+        if self.max_rooms == 0 || self.max_clients_per_room == 0 || self.max_connections == 0 {
+            return Err(ConfigError::Invalid("max_rooms, max_clients_per_room and max_connections must be more than 0"));
+        }
+        if self.max_connections_per_ip == 0 {
+            return Err(ConfigError::Invalid("max_connections_per_ip must be more than 0"));
+        }
+        if self.room_creation_limit == 0 || self.room_creation_window_secs == 0 {
+            return Err(ConfigError::Invalid("room_creation_limit and room_creation_window_secs must be more than 0"));
+        }
+        if self.packet_rate_limit == 0 || self.packet_rate_limit_window_secs == 0 {
+            return Err(ConfigError::Invalid("packet_rate_limit and packet_rate_limit_window_secs must be more than 0"));
+        }
+        if self.ip_ban_duration_secs == 0 {
+            return Err(ConfigError::Invalid("ip_ban_duration_secs must be more than 0"));
+        }
+        Ok(())
+    }
+
+    pub fn tick_interval(&self) -> Duration {
+        Duration::from_millis(self.tick_interval_millis)
+    }
+    pub fn snapshot_interval(&self) -> Duration {
+        Duration::from_secs(self.snapshot_interval_secs)
+    }
+    pub fn game_disconnect_timer(&self) -> Duration {
+        Duration::from_secs(self.game_disconnect_timer_secs)
+    }
+    pub fn lobby_disconnect_timer(&self) -> Duration {
+        Duration::from_secs(self.lobby_disconnect_timer_secs)
+    }
+    pub fn restored_lobby_disconnect_timer(&self) -> Duration {
+        Duration::from_secs(self.restored_lobby_disconnect_timer_secs)
+    }
+    pub fn heartbeat_interval(&self) -> Duration {
+        Duration::from_secs(self.heartbeat_interval_secs)
+    }
+    pub fn heartbeat_timeout(&self) -> Duration {
+        Duration::from_secs(self.heartbeat_timeout_secs)
+    }
+    pub fn message_rate_limit_window(&self) -> Duration {
+        Duration::from_secs(self.message_rate_limit_window_secs)
+    }
+    pub fn room_creation_window(&self) -> Duration {
+        Duration::from_secs(self.room_creation_window_secs)
+    }
+    pub fn packet_rate_limit_window(&self) -> Duration {
+        Duration::from_secs(self.packet_rate_limit_window_secs)
+    }
+    pub fn ip_ban_duration(&self) -> Duration {
+        Duration::from_secs(self.ip_ban_duration_secs)
+    }
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::Read(path, err) => write!(f, "Couldn't read config file {}: {err}", path.display()),
+            ConfigError::Parse(path, err) => write!(f, "Invalid config file {}: {err}", path.display()),
+            ConfigError::EnvironmentVariable { name, value } => write!(f, "Invalid value for environment variable {name}: {value}"),
+            ConfigError::Invalid(reason) => write!(f, "Invalid config: {reason}"),
+        }
+    }
+}
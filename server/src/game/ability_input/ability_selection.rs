@@ -1,3 +1,4 @@
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 
 use super::{
@@ -56,6 +57,11 @@ macro_rules! selection_kinds {
                     }),*
                 }
             }
+            pub fn random_selection<R: Rng + ?Sized>(&self, game: &Game, rng: &mut R)->AbilitySelection {
+                match self {
+                    $(Self::$name(available) => available.random_selection(game, rng).into()),*
+                }
+            }
         }
     }
 }
@@ -4,6 +4,7 @@ pub mod saved_controllers_map; pub use saved_controllers_map::*;
 pub mod controller_id; pub use controller_id::*;
 pub mod controller_parameters; pub use controller_parameters::*;
 
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 
 use super::{
@@ -44,6 +45,10 @@ pub trait AvailableSelectionKind: Into<AvailableAbilitySelection>{
     type Selection: Into<AbilitySelection>;
     fn validate_selection(&self, game: &Game, selection: &Self::Selection)->bool;
     fn default_selection(&self, game: &Game) -> Self::Selection;
+    /// Used by bots. This doesn't have to be valid, callers should validate it
+    fn random_selection<R: Rng + ?Sized>(&self, game: &Game, _rng: &mut R) -> Self::Selection {
+        self.default_selection(game)
+    }
 }
 
 impl AbilityInput{
@@ -13,7 +13,7 @@ use crate::{
             on_validated_ability_input_received::OnValidatedAbilityInputReceived
         }, 
         phase::PhaseType, player::PlayerReference, Game
-    }, packet::ToClientPacket, vec_map::VecMap, vec_set::VecSet
+    }, packet::ToClientPacket, protocol::Capability, vec_map::VecMap, vec_set::VecSet
 };
 
 use super::*;
@@ -21,11 +21,24 @@ use super::*;
 #[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct SavedControllersMap{
     pub(super) saved_controllers: VecMap<ControllerID, SavedController>,
+    /// The controllers each player's client was last sent, so only what changed since is sent next.
+    /// Not saved, so clients get everything again after a restart
+    #[serde(skip)]
+    sent_to_players: VecMap<PlayerReference, VecMap<ControllerID, SavedController>>,
+    /// Whether anything controllers are worked out from may have changed since the last tick,
+    /// so ticks where nothing happened don't work them all out again
+    #[serde(skip)]
+    outdated: bool,
 }
 
 impl SavedControllersMap{
     pub fn new(saved_controllers: VecMap<ControllerID, SavedController>)->Self{
-        Self{saved_controllers}
+        Self{saved_controllers, sent_to_players: VecMap::new(), outdated: false}
+    }
+
+    /// Call when the game changes outside of a phase starting, like when a player sends a packet or disconnects
+    pub fn mark_outdated(&mut self){
+        self.outdated = true;
     }
 
     //event listeners
@@ -65,9 +78,13 @@ impl SavedControllersMap{
             saved_controller.reset_on_phase_start(phase);
         }
         Self::send_saved_controllers_to_clients(game);
+        // The rest of the phase start can still change what they're worked out from
+        game.saved_controllers.mark_outdated();
     }
 
     pub fn on_tick(game: &mut Game){
+        if !game.saved_controllers.outdated {return}
+        game.saved_controllers.outdated = false;
         Self::update_controllers_from_parameters(game);
     }
 
@@ -245,13 +262,40 @@ impl SavedControllersMap{
     }
     // game stuff
     
-    pub fn send_saved_controllers_to_clients(game: &Game){
+    /// Sends each player only the controllers that were added, changed or removed since they were last sent theirs,
+    /// or all of them if their client can't take just the changes
+    pub fn send_saved_controllers_to_clients(game: &mut Game){
         for player in PlayerReference::all_players(game){
-            player.send_packet(game, ToClientPacket::YourAllowedControllers { 
-                save: game.saved_controllers.controllers_allowed_to_player(player).saved_controllers
-            });
+            let current = game.saved_controllers.controllers_allowed_to_player(player).saved_controllers;
+            let sent = game.saved_controllers.sent_to_players.get(&player);
+
+            let changed: VecMap<ControllerID, SavedController> = current.iter()
+                .filter(|(id, controller)| sent.and_then(|sent| sent.get(id)) != Some(controller))
+                .map(|(id, controller)| (id.clone(), controller.clone()))
+                .collect();
+            let removed: Vec<ControllerID> = sent.into_iter()
+                .flat_map(|sent| sent.keys())
+                .filter(|id| !current.contains_key(id))
+                .cloned()
+                .collect();
+
+            if changed.is_empty() && removed.is_empty() {continue}
+
+            if player.connection(game).supports(Capability::AllowedControllersUpdate) {
+                player.send_packet(game, ToClientPacket::YourAllowedControllersUpdate { changed, removed });
+            } else {
+                player.send_packet(game, ToClientPacket::YourAllowedControllers { save: current.clone() });
+            }
+            game.saved_controllers.sent_to_players.insert(player, current);
         }
     }
+    /// Sends the player every controller they're allowed, replacing whatever their client had.
+    /// Used when they join or reconnect
+    pub fn send_all_saved_controllers_to_client(game: &mut Game, player: PlayerReference){
+        let save = game.saved_controllers.controllers_allowed_to_player(player).saved_controllers;
+        player.send_packet(game, ToClientPacket::YourAllowedControllers { save: save.clone() });
+        game.saved_controllers.sent_to_players.insert(player, save);
+    }
 }
 
 
@@ -1,3 +1,4 @@
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 
 use crate::game::{
@@ -26,6 +27,13 @@ impl AvailableSelectionKind for AvailableIntegerSelection{
     fn default_selection(&self, _: &Game) -> Self::Selection {
         IntegerSelection(0)
     }
+
+    fn random_selection<R: Rng + ?Sized>(&self, game: &Game, rng: &mut R) -> Self::Selection {
+        if self.min > self.max {
+            return self.default_selection(game);
+        }
+        IntegerSelection(rng.random_range(self.min..=self.max))
+    }
 }
 
 
@@ -7,6 +7,7 @@ pub mod player_list_selection; pub use player_list_selection::*;
 pub mod integer_selection; pub use integer_selection::*;
 pub mod chat_message_selection; pub use chat_message_selection::*;
 
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 
 use super::{ability_selection::AbilitySelection, AbilityInput, AvailableSelectionKind, ControllerID};
@@ -52,6 +53,10 @@ impl AvailableSelectionKind for AvailableBooleanSelection {
     fn default_selection(&self, _game: &crate::game::Game) -> Self::Selection {
         BooleanSelection(false)
     }
+
+    fn random_selection<R: Rng + ?Sized>(&self, _game: &crate::game::Game, rng: &mut R) -> Self::Selection {
+        BooleanSelection(rng.random())
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, Default, PartialEq, Eq, PartialOrd, Ord)]
@@ -1,3 +1,4 @@
+use rand::{seq::IteratorRandom, Rng};
 use serde::{Deserialize, Serialize};
 
 use crate::{game::{ability_input::{ability_selection::AbilitySelection, AbilityInput, ControllerID, AvailableSelectionKind}, player::PlayerReference, Game}, vec_set::VecSet};
@@ -31,6 +32,15 @@ impl AvailableSelectionKind for AvailablePlayerListSelection{
     fn default_selection(&self, _: &Game) -> Self::Selection {
         PlayerListSelection(Vec::new())
     }
+
+    fn random_selection<R: Rng + ?Sized>(&self, _: &Game, rng: &mut R) -> Self::Selection {
+        let max_players = self.max_players
+            .map_or(self.available_players.count(), usize::from)
+            .min(self.available_players.count());
+        let count = rng.random_range(0..=max_players);
+
+        PlayerListSelection(self.available_players.iter().copied().choose_multiple(rng, count))
+    }
 }
 
 
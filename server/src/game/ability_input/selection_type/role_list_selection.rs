@@ -1,3 +1,4 @@
+use rand::{seq::IteratorRandom, Rng};
 use serde::{Deserialize, Serialize};
 
 use crate::{game::{ability_input::{ability_selection::AbilitySelection, AbilityInput, ControllerID, AvailableSelectionKind}, role::Role, Game}, vec_set::VecSet};
@@ -25,6 +26,15 @@ impl AvailableSelectionKind for AvailableRoleListSelection{
     fn default_selection(&self, _: &Game) -> Self::Selection {
         RoleListSelection(vec![])
     }
+
+    fn random_selection<R: Rng + ?Sized>(&self, _: &Game, rng: &mut R) -> Self::Selection {
+        let max_roles = self.max_roles
+            .map_or(self.available_roles.count(), usize::from)
+            .min(self.available_roles.count());
+        let count = rng.random_range(0..=max_roles);
+
+        RoleListSelection(self.available_roles.iter().copied().choose_multiple(rng, count))
+    }
 }
 
 
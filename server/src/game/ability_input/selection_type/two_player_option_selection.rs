@@ -1,5 +1,6 @@
 use std::cmp::Ordering;
 
+use rand::{seq::IteratorRandom, Rng};
 use serde::{Deserialize, Serialize};
 
 use crate::{game::{ability_input::{AbilitySelection, AvailableSelectionKind, ControllerID}, player::PlayerReference, Game}, vec_set::VecSet};
@@ -87,6 +88,24 @@ impl AvailableSelectionKind for AvailableTwoPlayerOptionSelection{
     fn default_selection(&self, _: &Game) -> Self::Selection {
         TwoPlayerOptionSelection(None)
     }
+
+    fn random_selection<R: Rng + ?Sized>(&self, _: &Game, rng: &mut R) -> Self::Selection {
+        if self.can_choose_none && rng.random_bool(0.5) {
+            return TwoPlayerOptionSelection(None);
+        }
+
+        let Some(first) = self.available_first_players.iter().copied().choose(rng) else {
+            return TwoPlayerOptionSelection(None);
+        };
+        let Some(second) = self.available_second_players.iter()
+            .copied()
+            .filter(|second| self.can_choose_duplicates || *second != first)
+            .choose(rng) else {
+            return TwoPlayerOptionSelection(None);
+        };
+
+        TwoPlayerOptionSelection(Some((first, second)))
+    }
 }
 
 impl ControllerID{
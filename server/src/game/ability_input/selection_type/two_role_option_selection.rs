@@ -1,5 +1,6 @@
 use std::cmp::Ordering;
 
+use rand::{seq::IteratorRandom, Rng};
 use serde::{Deserialize, Serialize};
 
 use crate::{
@@ -48,6 +49,13 @@ impl AvailableSelectionKind for AvailableTwoRoleOptionSelection{
     fn default_selection(&self, _: &Game) -> Self::Selection {
         TwoRoleOptionSelection(None, None)
     }
+
+    fn random_selection<R: Rng + ?Sized>(&self, _: &Game, rng: &mut R) -> Self::Selection {
+        TwoRoleOptionSelection(
+            self.available_roles.iter().copied().choose(rng).flatten(),
+            self.available_roles.iter().copied().choose(rng).flatten()
+        )
+    }
 }
 impl PartialOrd for AvailableTwoRoleOptionSelection{
     fn partial_cmp(&self, other: &Self)->Option<std::cmp::Ordering>{
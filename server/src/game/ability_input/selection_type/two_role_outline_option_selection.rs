@@ -1,3 +1,4 @@
+use rand::{seq::IteratorRandom, Rng};
 use serde::{Deserialize, Serialize};
 
 use crate::{game::{ability_input::{ability_selection::AbilitySelection, AbilityInput, ControllerID, AvailableSelectionKind}, role_outline_reference::RoleOutlineReference, Game}, vec_set::VecSet};
@@ -21,6 +22,13 @@ impl AvailableSelectionKind for AvailableTwoRoleOutlineOptionSelection{
     fn default_selection(&self, _: &Game) -> Self::Selection {
         TwoRoleOutlineOptionSelection(None, None)
     }
+
+    fn random_selection<R: Rng + ?Sized>(&self, _: &Game, rng: &mut R) -> Self::Selection {
+        TwoRoleOutlineOptionSelection(
+            self.0.iter().copied().choose(rng).flatten(),
+            self.0.iter().copied().choose(rng).flatten()
+        )
+    }
 }
 
 impl PartialOrd for AvailableTwoRoleOutlineOptionSelection{
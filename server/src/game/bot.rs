@@ -0,0 +1,105 @@
+//! A simple bot that plays by picking random valid selections for its controllers and voting randomly.
+//! Bots act by sending the same [`ToServerPacket`]s a real client would.
+
+use rand::{seq::IndexedRandom, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{packet::ToServerPacket, room::RoomClientID};
+
+use super::{
+    ability_input::{AbilityInput, AbilitySelection, ControllerID, ControllerParameters, PlayerListSelection},
+    game_client::GameClientLocation, game_conclusion::GameConclusion, phase::{PhaseState, PhaseType},
+    player::PlayerReference, role::Role, role_list::RoleSet, verdict::Verdict, Game
+};
+
+pub struct Bot;
+
+/// Plays for every player added to the lobby as a bot.
+/// Each bot is seeded from the game's rng, so a replay makes them act the same way again.
+#[derive(Default)]
+pub struct Bots {
+    last_acted: Option<(u8, PhaseType)>,
+}
+
+impl Bots {
+    pub fn on_tick(game: &mut Game) {
+        if !game.ticking {return}
+
+        let phase = (game.day_number(), game.current_phase().phase());
+        if game.bots.last_acted == Some(phase) {return}
+        game.bots.last_acted = Some(phase);
+
+        let bots: Vec<(RoomClientID, PlayerReference)> = game.clients.iter()
+            .filter_map(|(id, client)| match client.client_location {
+                GameClientLocation::Player(player) if player.is_bot(game) => Some((*id, player)),
+                _ => None,
+            })
+            .collect();
+
+        for (room_client_id, player) in bots {
+            // The game's rng can't stay borrowed while the bot looks at the game, which might draw from it too
+            let seed = game.rng().random();
+            let packets = Bot::act(game, player, &mut ChaCha8Rng::seed_from_u64(seed));
+            for packet in packets {
+                game.on_player_message(room_client_id, player, packet);
+            }
+        }
+    }
+}
+
+impl Bot {
+    /// The packets a bot playing as `player` sends at the start of the current phase
+    pub fn act<R: Rng + ?Sized>(game: &Game, player: PlayerReference, rng: &mut R) -> Vec<ToServerPacket> {
+        let mut packets = Vec::new();
+
+        if player.alive(game) && player.will(game).is_empty() {
+            packets.push(ToServerPacket::SaveWill { will: format!("I am the {:?}", Self::claimed_role(game, player, rng)) });
+        }
+
+        let controllers = game.saved_controllers.controller_parameters_allowed_to_player(player);
+        for (id, parameters) in controllers.controller_parameters().iter() {
+            if parameters.grayed_out() {continue}
+
+            let selection = match id {
+                ControllerID::Nominate { .. } => Self::nomination(game, parameters, rng),
+                _ => parameters.available().random_selection(game, rng),
+            };
+            if parameters.validate_selection(game, &selection) {
+                packets.push(ToServerPacket::AbilityInput { ability_input: AbilityInput::new(id.clone(), selection) });
+            }
+        }
+
+        if let PhaseState::Judgement { player_on_trial, .. } = game.current_phase() {
+            if player.alive(game) && *player_on_trial != player {
+                let verdict = [Verdict::Guilty, Verdict::Innocent, Verdict::Abstain].choose(rng).copied().unwrap_or_default();
+                packets.push(ToServerPacket::Judgement { verdict });
+            }
+        }
+
+        packets
+    }
+
+    /// Town bots claim their real role, everyone else claims a town role that's enabled, so their will doesn't give them away
+    fn claimed_role<R: Rng + ?Sized>(game: &Game, player: PlayerReference, rng: &mut R) -> Role {
+        if player.win_condition(game).is_loyalist_for(GameConclusion::Town) {
+            return player.role(game);
+        }
+
+        let town_roles: Vec<Role> = RoleSet::Town.get_roles().into_iter()
+            .filter(|role| game.settings.enabled_roles.contains(role))
+            .collect();
+        town_roles.choose(rng).copied().unwrap_or(Role::Villager)
+    }
+
+    /// Nominating at random almost never reaches a majority, so bots often join whoever has the most votes
+    fn nomination<R: Rng + ?Sized>(game: &Game, parameters: &ControllerParameters, rng: &mut R) -> AbilitySelection {
+        let leading = game.create_voted_player_map().iter()
+            .max_by_key(|(_, votes)| **votes)
+            .map(|(player, _)| *player);
+
+        match leading {
+            Some(leading) if rng.random_bool(0.5) => PlayerListSelection(vec![leading]).into(),
+            _ => parameters.available().random_selection(game, rng),
+        }
+    }
+}
@@ -1,11 +1,11 @@
-use std::collections::HashSet;
+use crate::vec_set::VecSet;
 
 use crate::game::{player::PlayerReference, Game};
 
 
 #[derive(Default)]
 pub struct Confused{
-    players: HashSet<PlayerReference>
+    players: VecSet<PlayerReference>
 }
 
 impl Game {
@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use crate::vec_set::VecSet;
 
 use crate::game::{chat::ChatMessageVariant, event::on_midnight::{MidnightVariables, OnMidnight, OnMidnightPriority}, phase::PhaseType, player::PlayerReference, Game};
 
@@ -7,7 +7,7 @@ use super::insider_group::InsiderGroupID;
 #[derive(Default)]
 pub struct Detained{
     //resets every obituary
-    players: HashSet<PlayerReference>,
+    players: VecSet<PlayerReference>,
 }
 impl Detained{
     pub fn on_phase_start(game: &mut Game, phase: PhaseType){
@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use crate::vec_set::VecSet;
 
 use crate::game::{player::PlayerReference, Game};
 
@@ -6,7 +6,7 @@ use super::confused::Confused;
 
 #[derive(Default, Clone)]
 pub struct DrunkAura {
-    pub players: HashSet<PlayerReference>,
+    pub players: VecSet<PlayerReference>,
 }
 
 impl Game {
@@ -1,4 +1,3 @@
-use std::collections::HashSet;
 
 use crate::{game::{
     attack_power::AttackPower, chat::ChatMessageVariant,
@@ -22,14 +21,14 @@ impl Game{
 
 #[derive(Default, Clone)]
 pub struct MafiaRecruits{
-    recruits: HashSet<PlayerReference>,
+    recruits: VecSet<PlayerReference>,
 }
 impl MafiaRecruits{
     pub fn recruit(game: &mut Game, midnight_variables: &mut MidnightVariables, player: PlayerReference)->bool{
         let mut recruiter_recruits = game.mafia_recruits().clone();
 
         if InsiderGroupID::Mafia.contains_player(game, player) {return false;}
-        if !recruiter_recruits.recruits.insert(player){return false;}
+        if recruiter_recruits.recruits.insert(player).is_some(){return false;}
         Tags::add_tag(game, super::tags::TagSetID::SyndicateRecruit, player);
 
         game.set_recruiter_recruits(recruiter_recruits);
@@ -69,19 +68,19 @@ impl MafiaRecruits{
     pub fn is_recruited(game: &Game, player: PlayerReference)->bool{
         game.mafia_recruits().recruits.contains(&player)
     }
-    pub fn recruits(game: &Game)->HashSet<PlayerReference>{
+    pub fn recruits(game: &Game)->VecSet<PlayerReference>{
         PlayerReference::all_players(game)
             .filter(|p|
                 game.mafia_recruits().recruits.contains(p)
             )
             .collect()
     }
-    pub fn mafia_members(game: &Game)->HashSet<PlayerReference>{
+    pub fn mafia_members(game: &Game)->VecSet<PlayerReference>{
         PlayerReference::all_players(game)
             .filter(|p|InsiderGroupID::Mafia.contains_player(game, *p))
             .collect()
     }
-    pub fn mafia_and_recruits(game: &Game)->HashSet<PlayerReference>{
+    pub fn mafia_and_recruits(game: &Game)->VecSet<PlayerReference>{
         let mut mafia_and_recruits = MafiaRecruits::recruits(game);
         mafia_and_recruits.extend(MafiaRecruits::mafia_members(game));
         mafia_and_recruits
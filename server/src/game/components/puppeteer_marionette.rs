@@ -1,4 +1,3 @@
-use std::collections::HashSet;
 
 use crate::{game::{
     attack_power::AttackPower, chat::ChatMessageVariant,
@@ -19,14 +18,14 @@ impl Game{
 
 #[derive(Default, Clone)]
 pub struct PuppeteerMarionette{
-    marionettes: HashSet<PlayerReference>,
+    marionettes: VecSet<PlayerReference>,
 }
 impl PuppeteerMarionette{
     pub fn string(game: &mut Game, midnight_variables: &mut MidnightVariables, player: PlayerReference)->bool{
         let mut puppeteer_marionette = game.puppeteer_marionette().clone();
 
         if player.role(game) == Role::Puppeteer {return false;}
-        if !puppeteer_marionette.marionettes.insert(player){return false;}
+        if puppeteer_marionette.marionettes.insert(player).is_some(){return false;}
         Tags::add_tag(game, super::tags::TagSetID::PuppeteerMarionette, player);
 
         game.set_puppeteer_marionette(puppeteer_marionette);
@@ -65,19 +64,19 @@ impl PuppeteerMarionette{
     pub fn is_marionette(game: &Game, player: PlayerReference)->bool{
         game.puppeteer_marionette().marionettes.contains(&player)
     }
-    pub fn marionettes(game: &Game)->HashSet<PlayerReference>{
+    pub fn marionettes(game: &Game)->VecSet<PlayerReference>{
         PlayerReference::all_players(game)
             .filter(|p|
                 game.puppeteer_marionette().marionettes.contains(p)
             )
             .collect()
     }
-    pub fn puppeteers(game: &Game)->HashSet<PlayerReference>{
+    pub fn puppeteers(game: &Game)->VecSet<PlayerReference>{
         PlayerReference::all_players(game)
             .filter(|p|p.role(game)==Role::Puppeteer)
             .collect()
     }
-    pub fn marionettes_and_puppeteer(game: &Game)->HashSet<PlayerReference>{
+    pub fn marionettes_and_puppeteer(game: &Game)->VecSet<PlayerReference>{
         let mut marionettes_and_puppeteer = PuppeteerMarionette::marionettes(game);
         marionettes_and_puppeteer.extend(PuppeteerMarionette::puppeteers(game));
         marionettes_and_puppeteer
@@ -1,10 +1,10 @@
-use std::collections::HashSet;
+use crate::vec_set::VecSet;
 
 use crate::game::{phase::PhaseType, player::PlayerReference, verdict::Verdict, Game};
 
 #[derive(Default, Clone)]
 pub struct VerdictsToday{
-    guilties: HashSet<PlayerReference>,
+    guilties: VecSet<PlayerReference>,
 }
 
 impl Game{
@@ -19,13 +19,13 @@ impl Game{
 impl VerdictsToday{
     pub fn new()->Self{
         Self{
-            guilties: HashSet::new(),
+            guilties: VecSet::new(),
         }
     }
     pub fn player_guiltied_today(game: &Game, player: &PlayerReference)->bool{
         game.verdicts_today().guilties.contains(player)
     }
-    pub fn guilties(game: &Game)->&HashSet<PlayerReference>{
+    pub fn guilties(game: &Game)->&VecSet<PlayerReference>{
         &game.verdicts_today().guilties
     }
     pub fn on_phase_start(game: &mut Game, phase: PhaseType){
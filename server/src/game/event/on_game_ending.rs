@@ -19,5 +19,6 @@ impl OnGameEnding{
         }
 
         game.on_game_ending(self.conclusion.clone());
+        game.saved_controllers.mark_outdated();
     }
 }
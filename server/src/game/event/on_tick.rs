@@ -1,5 +1,5 @@
 use crate::game::{
-    ability_input::saved_controllers_map::SavedControllersMap, 
+    ability_input::saved_controllers_map::SavedControllersMap, bot::Bots,
     Game
 };
 
@@ -11,5 +11,6 @@ impl OnTick{
     }
     pub fn invoke(&self, game: &mut Game){
         SavedControllersMap::on_tick(game);
+        Bots::on_tick(game);
     }
 }
\ No newline at end of file
@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 
-use super::{components::win_condition::WinCondition, player::PlayerReference, role::Role, role_list::RoleSet, Game};
+use crate::vec_set::VecSet;
+
+use super::{components::{insider_group::InsiderGroupID, win_condition::WinCondition}, player::PlayerReference, role::Role, role_list::RoleSet, Game};
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
 #[serde(rename_all = "camelCase")]
@@ -37,32 +39,33 @@ impl GameConclusion {
     }
     ///either return Some(EndGameCondition) or None (if the game is not over yet)
     pub fn game_is_over(game: &Game)->Option<GameConclusion> {
+        GameConclusion::reached_by(
+            &PlayerReference::all_players(game)
+                .filter(|player| player.alive(game))
+                .map(|player| (player.role(game), player.keeps_game_running(game), player.win_condition(game).clone()))
+                .collect::<Vec<_>>()
+        )
+    }
+    /// Same as [`GameConclusion::game_is_over`], given the role of every living player,
+    /// whether they keep the game running, and their win condition
+    pub fn reached_by(living_players: &[(Role, bool, WinCondition)])->Option<GameConclusion> {
 
         //Special wildcard case
-        let living_roles = PlayerReference::all_players(game).filter_map(|player|{
-            if player.alive(game){
-                Some(player.role(game))
-            }else{
-                None
-            }
-        }).collect::<Vec<_>>();
-
-        if living_roles.iter().all(|role|matches!(role, Role::Wildcard|Role::TrueWildcard)) && living_roles.len() > 1 {
+        if living_players.iter().all(|(role, _, _)|matches!(role, Role::Wildcard|Role::TrueWildcard)) && living_players.len() > 1 {
             return None;
         }
         
         //if nobody is left to hold game hostage
-        if !PlayerReference::all_players(game).any(|player| player.alive(game) && player.keeps_game_running(game)){
+        if !living_players.iter().any(|(_, keeps_game_running, _)| *keeps_game_running){
             return Some(GameConclusion::Draw);
         }
 
         //find one end game condition that everyone agrees on
         GameConclusion::all().into_iter().find(|resolution| 
-            PlayerReference::all_players(game)
-                .filter(|p|p.alive(game))
-                .filter(|p|p.keeps_game_running(game))
-                .all(|p|
-                    match p.win_condition(game){
+            living_players.iter()
+                .filter(|(_, keeps_game_running, _)| *keeps_game_running)
+                .all(|(_, _, win_condition)|
+                    match win_condition{
                         WinCondition::GameConclusionReached{win_if_any} => win_if_any.contains(resolution),
                         WinCondition::RoleStateWon => true,
                     }
@@ -90,6 +93,16 @@ impl GameConclusion {
             matches!(role, Role::Apostle | Role::Zealot | Role::Krampus)
         }
     }
+    /// If they can consistently kill then they keep the game running
+    /// Town kills by voting
+    /// Mafia kills with MK or gun
+    /// Cult kills / converts
+    pub fn player_keeps_game_running(role: Role, insider_groups: &VecSet<InsiderGroupID>, win_condition: &WinCondition)->bool{
+        insider_groups.contains(&InsiderGroupID::Mafia) ||
+        insider_groups.contains(&InsiderGroupID::Cult) ||
+        win_condition.is_loyalist_for(GameConclusion::Town) ||
+        GameConclusion::keeps_game_running(role)
+    }
 }
 
 
@@ -1,6 +1,5 @@
 use std::vec;
 
-use rand::rng;
 use rand::seq::SliceRandom;
 use serde::{Serialize, Deserialize};
 
@@ -85,7 +84,7 @@ impl Grave{
 
     pub fn from_player_night(game: &Game, midnight_variables: &MidnightVariables, player_ref: PlayerReference) -> Grave {
         let mut killers = player_ref.night_grave_killers(midnight_variables).clone();
-        killers.shuffle(&mut rng());
+        killers.shuffle(&mut *game.rng());
         Grave {
             player: player_ref,
             died_phase: GravePhase::Night,
@@ -9,6 +9,9 @@ pub mod role;
 pub mod visit;
 pub mod verdict;
 pub mod role_list;
+pub mod role_generation;
+pub mod role_list_analysis;
+pub mod role_list_constraints;
 pub mod settings;
 pub mod game_conclusion;
 pub mod components;
@@ -20,7 +23,12 @@ pub mod attack_power;
 pub mod modifiers;
 pub mod role_outline_reference;
 pub mod ability_input;
+pub mod rng;
+pub mod replay;
+pub mod bot;
 
+use std::cell::RefMut;
+use std::collections::BTreeMap;
 use std::collections::VecDeque;
 use std::time::Duration;
 use std::time::Instant;
@@ -51,11 +59,18 @@ use modifiers::ModifierType;
 use modifiers::Modifiers;
 use event::before_initial_role_creation::BeforeInitialRoleCreation;
 use rand::seq::SliceRandom;
+use rand::Rng;
+use rand_chacha::ChaCha8Rng;
+use rng::GameRng;
+use rng::GameSeed;
+use replay::{Replay, ReplayEvent};
+use role_generation::{Generator, RoleGenerationError};
 use role_list::RoleAssignment;
 use role_outline_reference::RoleOutlineReference;
 use serde::Serialize;
 
 use crate::client_connection::ClientConnection;
+use crate::config::ServerConfig;
 use crate::game::event::on_game_start::OnGameStart;
 use crate::game::player::PlayerIndex;
 use game_client::GameClient;
@@ -97,6 +112,7 @@ use self::spectator::{
     SpectatorInitializeParameters
 };
 use self::verdict::Verdict;
+use self::bot::Bots;
 
 
 pub struct Game {
@@ -115,6 +131,10 @@ pub struct Game {
 
     phase_machine : PhaseStateMachine,
 
+    rng: GameRng,
+
+    /// Everything that happened to this game, so it can be played back later
+    replay: Replay,
     
     /// Whether the game is still updating phase times
     pub ticking: bool,
@@ -139,16 +159,18 @@ pub struct Game {
     pub tags: Tags,
     pub silenced: Silenced,
     pub fragile_vests: PlayerComponent<FragileVests>,
-    pub win_condition: PlayerComponent<WinCondition>
+    pub win_condition: PlayerComponent<WinCondition>,
+    pub bots: Bots
 }
 
-#[derive(Serialize, Debug, Clone, Copy)]
-#[serde(rename_all = "camelCase")]
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "camelCase")]
 pub enum RejectStartReason {
     TooManyClients,
-    GameEndsInstantly,
+    /// Every role list tried would end the game straight away, most of them with `conclusion`
+    GameEndsInstantly { conclusion: GameConclusion },
     RoleListTooSmall,
-    RoleListCannotCreateRoles,
+    RoleListCannotCreateRoles(RoleGenerationError),
     ZeroTimeGame,
     PlayerDisconnected
 }
@@ -163,129 +185,118 @@ pub enum GameOverReason {
 
 type Assignments = VecMap<PlayerReference, (RoleOutlineReference, RoleAssignment)>;
 
-impl Game {
-    pub const DISCONNECT_TIMER_SECS: u16 = 60 * 2;
+/// How many role lists a game generates looking for one that doesn't end it as soon as it starts
+const MAX_ROLE_LIST_TRIES: u8 = 20;
 
+impl Game {
     /// `players` must have length 255 or lower.
     pub fn new(
         room_name: String,
         settings: Settings,
         clients: VecMap<RoomClientID, GameClient>,
         players: Vec<PlayerInitializeParameters>,
-        spectators: Vec<SpectatorInitializeParameters>
+        spectators: Vec<SpectatorInitializeParameters>,
+        seed: GameSeed
     ) -> Result<Self, RejectStartReason>{
         //check settings are not completly off the rails
         if settings.phase_times.game_ends_instantly() {
             return Err(RejectStartReason::ZeroTimeGame);
         }
         
+        let rng = GameRng::new(seed);
+        let replay = Replay::new(&room_name, &settings, seed, &clients, &players, &spectators);
 
-        let mut role_generation_tries = 0u8;
-        const MAX_ROLE_GENERATION_TRIES: u8 = 250;
-        let mut game = loop {
+        for player in players.iter() {
+            let (ClientConnection::Connected(_) | ClientConnection::Bot) = player.connection else {
+                return Err(RejectStartReason::PlayerDisconnected)
+            };
+        }
+        if settings.role_list.0.len() < players.len() {
+            return Err(RejectStartReason::RoleListTooSmall);
+        }
 
-            if role_generation_tries >= MAX_ROLE_GENERATION_TRIES {
-                return Err(RejectStartReason::RoleListCannotCreateRoles);
-            }
+        let mut generator = Generator::new(&settings.role_list, &settings.enabled_roles, &settings.role_list_constraints)
+            .map_err(RejectStartReason::RoleListCannotCreateRoles)?;
+        let random_outline_assignments = Self::generate_role_list_that_keeps_going(&mut generator, &mut *rng.get())?;
+        let assignments = Self::assign_players_to_assignments(random_outline_assignments, &mut *rng.get());
 
-            let settings = settings.clone();
-            let role_list = settings.role_list.clone();
+        // Create list of players
+        let mut new_players = Vec::new();
+        for (player_index, player) in players.iter().enumerate() {
+            let Ok(player_index) = player_index.try_into() else {return Err(RejectStartReason::TooManyClients)};
+            let player_ref = unsafe{PlayerReference::new_unchecked(player_index)};
 
-            let random_outline_assignments = match role_list.create_random_role_assignments(&settings.enabled_roles){
-                Some(roles) => {roles},
-                None => {
-                    role_generation_tries = role_generation_tries.saturating_add(1);
-                    continue;
-                }
+            let Some((_, assignment)) = assignments.get(&player_ref) else {
+                return Err(RejectStartReason::RoleListTooSmall)
             };
 
-            let assignments = Self::assign_players_to_assignments(random_outline_assignments);            
+            let new_player = Player::new(
+                player.name.clone(),
+                player.connection.clone(),
+                assignment.role()
+            );
+            
+            new_players.push(new_player);
+        }
 
+        #[expect(clippy::cast_possible_truncation, reason = "Explained in doc comment")]
+        let num_players = new_players.len() as u8;
 
-            // Create list of players
-            let mut new_players = Vec::new();
-            for (player_index, player) in players.iter().enumerate() {
-                let Ok(player_index) = player_index.try_into() else {return Err(RejectStartReason::TooManyClients)};
-                let player_ref = unsafe{PlayerReference::new_unchecked(player_index)};
+        let mut game = Self{
+            room_name,
+            clients,
+            pitchfork: Pitchfork::new(num_players),
 
-                let ClientConnection::Connected(ref sender) = player.connection else {
-                    return Err(RejectStartReason::PlayerDisconnected)
-                };
-                let Some((_, assignment)) = assignments.get(&player_ref) else {
-                    return Err(RejectStartReason::RoleListTooSmall)
-                };
+            assignments: assignments.clone(),
+            ticking: true,
+            spectators: spectators.into_iter().map(Spectator::new).collect(),
+            spectator_chat_messages: Vec::new(),
+            players: new_players.into_boxed_slice(),
+            graves: Vec::new(),
+            phase_machine: PhaseStateMachine::new(settings.phase_times.clone()),
+            rng,
+            replay,
+            modifiers: Modifiers::default_from_settings(settings.enabled_modifiers.clone()),
+            settings,
 
-                let new_player = Player::new(
-                    player.name.clone(),
-                    sender.clone(),
-                    assignment.role()
-                );
-                
-                new_players.push(new_player);
-            }
+            saved_controllers: SavedControllersMap::default(),
+            syndicate_gun_item: SyndicateGunItem::default(),
+            cult: Cult::default(),
+            mafia: Mafia,
+            puppeteer_marionette: PuppeteerMarionette::default(),
+            mafia_recruits: MafiaRecruits::default(),
+            verdicts_today: VerdictsToday::default(),
+            poison: Poison::default(),
 
-            #[expect(clippy::cast_possible_truncation, reason = "Explained in doc comment")]
-            let num_players = new_players.len() as u8;
-
-            let mut game = Self{
-                room_name: room_name.clone(),
-                clients: clients.clone(),
-                pitchfork: Pitchfork::new(num_players),
-
-                assignments: assignments.clone(),
-                ticking: true,
-                spectators: spectators.clone().into_iter().map(Spectator::new).collect(),
-                spectator_chat_messages: Vec::new(),
-                players: new_players.into_boxed_slice(),
-                graves: Vec::new(),
-                phase_machine: PhaseStateMachine::new(settings.phase_times.clone()),
-                modifiers: Modifiers::default_from_settings(settings.enabled_modifiers.clone()),
-                settings,
-
-                saved_controllers: SavedControllersMap::default(),
-                syndicate_gun_item: SyndicateGunItem::default(),
-                cult: Cult::default(),
-                mafia: Mafia,
-                puppeteer_marionette: PuppeteerMarionette::default(),
-                mafia_recruits: MafiaRecruits::default(),
-                verdicts_today: VerdictsToday::default(),
-                poison: Poison::default(),
-
-                insider_groups: unsafe{InsiderGroups::new(num_players, &assignments)},
-                detained: Detained::default(),
-                confused: Confused::default(),
-                drunk_aura: DrunkAura::default(),
-                synopsis_tracker: SynopsisTracker::new(num_players),
-                tags: Tags::default(),
-                silenced: Silenced::default(),
-                fragile_vests: unsafe{PlayerComponent::<FragileVests>::new(num_players)},
-                win_condition: unsafe{PlayerComponent::<WinCondition>::new(num_players, &assignments)}
-            };
+            insider_groups: unsafe{InsiderGroups::new(num_players, &assignments)},
+            detained: Detained::default(),
+            confused: Confused::default(),
+            drunk_aura: DrunkAura::default(),
+            synopsis_tracker: SynopsisTracker::new(num_players),
+            tags: Tags::default(),
+            silenced: Silenced::default(),
+            fragile_vests: unsafe{PlayerComponent::<FragileVests>::new(num_players)},
+            win_condition: unsafe{PlayerComponent::<WinCondition>::new(num_players, &assignments)},
+            bots: Bots::default()
+        };
 
-            // Just distribute insider groups, this is for game over checking (Keeps game running syndicate gun)
-            for player in PlayerReference::all_players(&game){
-                let Some((_, assignment)) = assignments.get(&player) else {
-                    return Err(RejectStartReason::RoleListTooSmall)
-                };
-                
-                let insider_groups = assignment.insider_groups();
-                
-                for group in insider_groups{
-                    unsafe {
-                        group.add_player_to_revealed_group_unchecked(&mut game, player);
-                    }
+        // Just distribute insider groups, this is for game over checking (Keeps game running syndicate gun)
+        for player in PlayerReference::all_players(&game){
+            let Some((_, assignment)) = assignments.get(&player) else {
+                return Err(RejectStartReason::RoleListTooSmall)
+            };
+            
+            let insider_groups = assignment.insider_groups();
+            
+            for group in insider_groups{
+                unsafe {
+                    group.add_player_to_revealed_group_unchecked(&mut game, player);
                 }
             }
+        }
 
-
-            if !game.game_is_over() {
-                break game;
-            }
-            role_generation_tries = role_generation_tries.saturating_add(1);
-        };
-
-        if game.game_is_over() {
-            return Err(RejectStartReason::RoleListCannotCreateRoles);
+        if let Some(conclusion) = GameConclusion::game_is_over(&game) {
+            return Err(RejectStartReason::GameEndsInstantly { conclusion });
         }
         
         game.send_packet_to_all(ToClientPacket::StartGame);
@@ -324,11 +335,35 @@ impl Game {
         Ok(game)
     }
     
+    /// A role list from `generator` that doesn't end the game as soon as it starts.
+    /// Lists are checked without building the game, and after [`MAX_ROLE_LIST_TRIES`] that all would,
+    /// the game can't start with the conclusion they reached most often
+    fn generate_role_list_that_keeps_going<R: Rng + ?Sized>(generator: &mut Generator, rng: &mut R) -> Result<Vec<RoleAssignment>, RejectStartReason> {
+        let mut conclusions: BTreeMap<GameConclusion, u8> = BTreeMap::new();
+        for _ in 0..MAX_ROLE_LIST_TRIES {
+            let role_list = generator.generate(rng);
+            let players: Vec<_> = role_list.iter()
+                .map(|assignment| (assignment.role(), assignment.keeps_game_running(), assignment.win_condition()))
+                .collect();
+
+            let Some(conclusion) = GameConclusion::reached_by(&players) else {
+                return Ok(role_list);
+            };
+            let times = conclusions.entry(conclusion).or_default();
+            *times = times.saturating_add(1);
+        }
+
+        let conclusion = conclusions.into_iter()
+            .max_by_key(|(_, times)| *times)
+            .map_or(GameConclusion::Draw, |(conclusion, _)| conclusion);
+        Err(RejectStartReason::GameEndsInstantly { conclusion })
+    }
+
     /// `initialization_data` must have length 255 or lower
     #[expect(clippy::cast_possible_truncation, reason = "See doc comment")]
-    fn assign_players_to_assignments(initialization_data: Vec<RoleAssignment>)->Assignments{
+    fn assign_players_to_assignments<R: Rng + ?Sized>(initialization_data: Vec<RoleAssignment>, rng: &mut R)->Assignments{
         let mut player_indices: Vec<PlayerIndex> = (0..initialization_data.len() as PlayerIndex).collect();
-        player_indices.shuffle(&mut rand::rng());
+        player_indices.shuffle(rng);
 
         initialization_data
             .into_iter()
@@ -474,6 +509,24 @@ impl Game {
         self.phase_machine.day_number
     }
 
+    pub fn phase_time_remaining(&self) -> Option<Duration> {
+        self.phase_machine.get_time_remaining()
+    }
+
+    /// All randomness in the game should come from here. See [`GameRng`]
+    pub fn rng(&self) -> RefMut<'_, ChaCha8Rng> {
+        self.rng.get()
+    }
+
+    /// The seed this game was started with. Starting a game with the same seed and inputs reproduces it.
+    pub fn seed(&self) -> GameSeed {
+        self.rng.seed()
+    }
+
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
     pub fn add_grave(&mut self, grave: Grave) {
         if let Ok(grave_index) = self.graves.len().try_into() {
             self.graves.push(grave.clone());
@@ -541,7 +594,7 @@ impl Game {
                     !matches!(spectator.connection(game), ClientConnection::Disconnected)
                 },
                 GameClientLocation::Player(player) => {
-                    !matches!(player.connection(game), ClientConnection::Disconnected)
+                    !matches!(player.connection(game), ClientConnection::Disconnected | ClientConnection::Bot)
                 }
             }
         }
@@ -581,12 +634,14 @@ impl Game {
     fn resend_host_data(&self, connection: &ClientConnection) {
         connection.send_packet(ToClientPacket::HostData { clients: self.clients.iter()
             .map(|(id, client)| {
+                let connection = match client.client_location {
+                    GameClientLocation::Player(player) => player.connection(self).clone(),
+                    GameClientLocation::Spectator(spectator) => spectator.connection(self)
+                };
                 (*id, HostDataPacketGameClient {
                     client_type: client.client_location.clone(),
-                    connection: match client.client_location {
-                        GameClientLocation::Player(player) => player.connection(self).clone(),
-                        GameClientLocation::Spectator(spectator) => spectator.connection(self)
-                    },
+                    connection_quality: connection.quality(),
+                    connection,
                     host: client.host
                 })
             }).collect()
@@ -613,7 +668,7 @@ impl Game {
 
         other_players.remove(player_ref.index() as usize);
         
-        let new_name: String = name_validation::sanitize_name(name, &other_players);
+        let new_name: String = name_validation::sanitize_name(name, &other_players, &mut *self.rng());
 
         player_ref.set_name(self, new_name);
     }
@@ -629,6 +684,42 @@ impl Game {
             None
         }
     }
+
+    /// Removes a client without recording it to the replay,
+    /// for when the packet that caused it was already recorded
+    fn remove_client_unrecorded(&mut self, room_client_id: RoomClientID) -> RemoveRoomClientResult {
+        let Some(game_player) = self.clients.get_mut(&room_client_id) else {
+            return RemoveRoomClientResult::ClientNotInRoom;
+        };
+
+        match game_player.client_location {
+            GameClientLocation::Player(player) => player.quit(self),
+            GameClientLocation::Spectator(spectator) => {
+                self.clients.remove(&room_client_id);
+
+                // Shift every other spectator down one index
+                for client in self.clients.iter_mut() {
+                    if let GameClientLocation::Spectator(ref mut other) = &mut client.1.client_location {
+                        if other.index() > spectator.index() {
+                            *other = SpectatorPointer::new(other.index().saturating_sub(1));
+                        }
+                    }
+                }
+
+                self.remove_spectator(spectator.index());
+            }
+        }
+
+        self.ensure_host_exists(None);
+
+        self.resend_host_data_to_all_hosts();
+
+        if !self.is_any_client_connected() {
+            RemoveRoomClientResult::RoomShouldClose
+        } else {
+            RemoveRoomClientResult::Success
+        }
+    }
 }
 
 impl RoomState for Game {
@@ -637,6 +728,8 @@ impl RoomState for Game {
             return RoomTickResult { close_room: false }
         }
 
+        self.replay.record(ReplayEvent::Tick { time_passed });
+
         if let Some(conclusion) = GameConclusion::game_is_over(self) {
             OnGameEnding::new(conclusion).invoke(self);
         }
@@ -676,6 +769,10 @@ impl RoomState for Game {
     
     fn join_client(&mut self, send: &ClientSender) -> Result<JoinRoomClientResult, RejectJoinReason> {
         let is_host = !self.clients.iter().any(|p|p.1.host);
+
+        if self.clients.len() >= ServerConfig::get().max_clients_per_room {
+            return Err(RejectJoinReason::RoomFull);
+        }
                 
         let Some(room_client_id) = 
             (self.clients
@@ -697,15 +794,18 @@ impl RoomState for Game {
         self.clients.insert(room_client_id, new_client);
 
         self.resend_host_data_to_all_hosts();
+
+        self.replay.record(ReplayEvent::Join { room_client_id });
         Ok(JoinRoomClientResult { id: room_client_id, in_game: true, spectator: true })
     }
 
     fn initialize_client(&mut self, room_client_id: RoomClientID, send: &ClientSender) {
+        self.replay.record(ReplayEvent::Initialize { room_client_id });
+
         if let Some(client) = self.clients.get(&room_client_id) {
             match client.client_location {
                 GameClientLocation::Player(player) => {
                     player.connect(self, send.clone());
-                    player.send_join_game_data(self);
                 },
                 GameClientLocation::Spectator(spectator) => {
                     spectator.send_join_game_data(self);
@@ -725,45 +825,18 @@ impl RoomState for Game {
     }
     
     fn remove_client(&mut self, room_client_id: u32) -> RemoveRoomClientResult {
-        let Some(game_player) = self.clients.get_mut(&room_client_id) else {
-            return RemoveRoomClientResult::ClientNotInRoom;
-        };
-
-        match game_player.client_location {
-            GameClientLocation::Player(player) => player.quit(self),
-            GameClientLocation::Spectator(spectator) => {
-                self.clients.remove(&room_client_id);
-
-                // Shift every other spectator down one index
-                for client in self.clients.iter_mut() {
-                    if let GameClientLocation::Spectator(ref mut other) = &mut client.1.client_location {
-                        if other.index() > spectator.index() {
-                            *other = SpectatorPointer::new(other.index().saturating_sub(1));
-                        }
-                    }
-                }
-
-                self.remove_spectator(spectator.index());
-            }
-        }
-
-        self.ensure_host_exists(None);
-
-        self.resend_host_data_to_all_hosts();
-
-        if !self.is_any_client_connected() {
-            RemoveRoomClientResult::RoomShouldClose
-        } else {
-            RemoveRoomClientResult::Success
-        }
+        self.replay.record(ReplayEvent::Leave { room_client_id });
+        self.remove_client_unrecorded(room_client_id)
     }
     
     fn remove_client_rejoinable(&mut self, id: u32) -> RemoveRoomClientResult {
         let Some(game_player) = self.clients.get_mut(&id) else { return RemoveRoomClientResult::ClientNotInRoom };
 
+        self.replay.record(ReplayEvent::LoseConnection { room_client_id: id });
+
         match game_player.client_location {
             GameClientLocation::Player(player) => {
-                if !player.is_disconnected(self) {
+                if !player.is_disconnected(self) && !player.is_bot(self) {
                     player.lose_connection(self);
     
                     self.ensure_host_exists(None);
@@ -789,7 +862,7 @@ impl RoomState for Game {
         RemoveRoomClientResult::Success
     }
     
-    fn rejoin_client(&mut self, _: &ClientSender, room_client_id: u32) -> Result<JoinRoomClientResult, RejectJoinReason> {
+    fn rejoin_client(&mut self, send: &ClientSender, room_client_id: u32) -> Result<JoinRoomClientResult, RejectJoinReason> {
         let Some(client) = self.clients.get_mut(&room_client_id) else {
             return Err(RejectJoinReason::PlayerDoesntExist)
         };
@@ -799,8 +872,10 @@ impl RoomState for Game {
                 return Err(RejectJoinReason::PlayerTaken)
             };
 
+            player.reconnect(self, send.clone());
             self.resend_host_data_to_all_hosts();
 
+            self.replay.record(ReplayEvent::Rejoin { room_client_id });
             Ok(JoinRoomClientResult { id: room_client_id, in_game: true, spectator: false })
         }else{
             Err(RejectJoinReason::PlayerDoesntExist)
@@ -845,10 +920,10 @@ pub mod test {
             synopsis::SynopsisTracker, tags::Tags, verdicts_today::VerdictsToday, win_condition::WinCondition
         }, event::{before_initial_role_creation::BeforeInitialRoleCreation, on_game_start::OnGameStart},
         phase::PhaseStateMachine, player::{test::mock_player, PlayerReference},
-        settings::Settings, Assignments, Game, RejectStartReason
+        replay::Replay, rng::{GameRng, GameSeed}, settings::Settings, Assignments, Game, RejectStartReason
     };
     
-    pub fn mock_game(settings: Settings, num_players: u8) -> Result<(Game, Assignments), RejectStartReason> {
+    pub fn mock_game(settings: Settings, num_players: u8, seed: GameSeed) -> Result<(Game, Assignments), RejectStartReason> {
 
         //check settings are not completly off the rails
         if settings.phase_times.game_ends_instantly() {
@@ -857,13 +932,14 @@ pub mod test {
 
         let settings = settings.clone();
         let role_list = settings.role_list.clone();
+        let rng = GameRng::new(seed);
         
-        let random_outline_assignments = match role_list.create_random_role_assignments(&settings.enabled_roles){
-            Some(roles) => {roles},
-            None => {return Err(RejectStartReason::RoleListCannotCreateRoles);}
+        let random_outline_assignments = match role_list.create_random_role_assignments(&settings.enabled_roles, &settings.role_list_constraints, &mut *rng.get()){
+            Ok(roles) => roles,
+            Err(error) => return Err(RejectStartReason::RoleListCannotCreateRoles(error)),
         };
 
-        let assignments = Game::assign_players_to_assignments(random_outline_assignments);
+        let assignments = Game::assign_players_to_assignments(random_outline_assignments, &mut *rng.get());
 
         let mut players = Vec::new();
         for player in unsafe{PlayerReference::all_players_from_count(num_players)} {
@@ -889,6 +965,8 @@ pub mod test {
             players: players.into_boxed_slice(),
             graves: Vec::new(),
             phase_machine: PhaseStateMachine::new(settings.phase_times.clone()),
+            rng,
+            replay: Replay::new("Test", &settings, seed, &VecMap::new(), &[], &[]),
             settings,
 
             saved_controllers: SavedControllersMap::default(),
@@ -908,7 +986,8 @@ pub mod test {
             tags: Tags::default(),
             silenced: Silenced::default(),
             fragile_vests: unsafe{PlayerComponent::<FragileVests>::new(num_players)},
-            win_condition: unsafe{PlayerComponent::<WinCondition>::new(num_players, &assignments)}
+            win_condition: unsafe{PlayerComponent::<WinCondition>::new(num_players, &assignments)},
+            bots: Default::default()
         };
 
 
@@ -1,7 +1,7 @@
-use crate::{lobby::{lobby_client::LobbyClient, Lobby}, log, packet::{ToClientPacket, ToServerPacket}, room::{RemoveRoomClientResult, RoomClientID, RoomState}, strings::TidyableString, vec_map::VecMap, websocket_connections::connection::ClientSender};
+use crate::{lobby::{lobby_client::LobbyClient, Lobby}, log, packet::{ToClientPacket, ToServerPacket}, room::{RemoveRoomClientResult, RoomClientID}, strings::TidyableString, vec_map::VecMap, websocket_connections::connection::ClientSender};
 
 use super::{
-    chat::{ChatGroup, ChatMessageVariant, MessageSender}, event::{on_fast_forward::OnFastForward, on_game_ending::OnGameEnding, on_whisper::OnWhisper, Event}, game_client::GameClientLocation, game_conclusion::GameConclusion, phase::PhaseType, player::PlayerReference, role::{
+    chat::{ChatGroup, ChatMessageVariant, MessageSender}, event::{on_fast_forward::OnFastForward, on_game_ending::OnGameEnding, on_whisper::OnWhisper, Event}, game_client::GameClientLocation, game_conclusion::GameConclusion, phase::PhaseType, player::PlayerReference, replay::ReplayEvent, role::{
         Role, RoleState
     }, spectator::spectator_pointer::SpectatorPointer, Game
 };
@@ -23,6 +23,8 @@ impl Game {
     }
     
     pub fn on_client_message(&mut self, _: &ClientSender, room_client_id: RoomClientID, incoming_packet: ToServerPacket) -> GameClientMessageResult {
+        self.replay.record(ReplayEvent::Packet { room_client_id, packet: incoming_packet.clone() });
+
         if let Some(client) = self.clients.get(&room_client_id) {
             match client.client_location {
                 GameClientLocation::Player(player) => {
@@ -40,12 +42,14 @@ impl Game {
     }
 
     pub fn on_player_message(&mut self, room_client_id: RoomClientID, sender_player_ref: PlayerReference, incoming_packet: ToServerPacket) -> GameClientMessageResult {
+        self.saved_controllers.mark_outdated();
+
         'packet_match: {match incoming_packet {
             ToServerPacket::SetName{ name } => {
                 self.set_player_name(sender_player_ref, name);
             },
             ToServerPacket::Leave => {
-                if let RemoveRoomClientResult::RoomShouldClose = self.remove_client(room_client_id) {
+                if let RemoveRoomClientResult::RoomShouldClose = self.remove_client_unrecorded(room_client_id) {
                     return GameClientMessageResult::Close;
                 }
             },
@@ -107,6 +111,9 @@ impl Game {
                 if let Some(player) = self.clients.get(&room_client_id){
                     if !player.host {break 'packet_match}
                 }
+                if let Some(GameClientLocation::Player(player)) = self.clients.get(&player_id).map(|c| c.client_location.clone()) {
+                    if player.is_bot(self) {break 'packet_match}
+                }
                 if let Some(player) = self.clients.get_mut(&player_id) {
                     player.set_host();
                 }
@@ -2,7 +2,7 @@ use std::{ops::Div, time::Duration};
 
 use serde::{Serialize, Deserialize};
 
-use crate::{game::modifiers::{ModifierType, Modifiers}, packet::ToClientPacket};
+use crate::{game::modifiers::{ModifierType, Modifiers}, log::LogContext, packet::ToClientPacket};
 
 use super::{
     chat::{ChatGroup, ChatMessageVariant},
@@ -97,6 +97,8 @@ impl PhaseStateMachine {
         game.phase_machine.current_state = new_phase;
         game.phase_machine.time_remaining = PhaseStateMachine::get_phase_time_length(game, game.current_phase().phase());
 
+        LogContext::set_game_time(game.day_number(), game.current_phase().phase());
+
         PhaseState::start(game);
         OnPhaseStart::new(game.current_phase().clone()).invoke(game);
     }
@@ -15,7 +15,6 @@ use crate::{
         role::{Role, RoleState}, 
         verdict::Verdict,
     },
-    websocket_connections::connection::ClientSender,
 };
 use super::chat::ChatMessage;
 
@@ -48,9 +47,9 @@ struct PlayerVotingVariables{
     verdict:        Verdict,
 }
 impl Player {
-    pub fn new(name: String, sender: ClientSender, role: Role) -> Self {
+    pub fn new(name: String, connection: ClientConnection, role: Role) -> Self {
         Self {
-            connection: ClientConnection::Connected(sender),
+            connection,
 
             name,
             role_state: role.default_state(),
@@ -84,7 +83,7 @@ pub mod test {
     pub fn mock_player(name: String, role: Role) -> Player {
         Player {
             // Since `tick` is never called in tests, this will never decrement.
-            connection: ClientConnection::CouldReconnect { disconnect_timer: Duration::from_secs(1) },
+            connection: ClientConnection::CouldReconnect { disconnect_timer: Duration::from_secs(1), sender: None },
 
             name,
             role_state: role.default_state(),
@@ -1,4 +1,3 @@
-use std::collections::HashSet;
 use rand::seq::SliceRandom;
 
 use crate::{
@@ -306,7 +305,7 @@ impl PlayerReference{
 
     pub fn push_night_messages_to_player(&self, game: &mut Game, midnight_variables: &mut MidnightVariables){
         let mut messages = self.night_messages(midnight_variables).to_vec();
-        messages.shuffle(&mut rand::rng());
+        messages.shuffle(&mut *game.rng());
         messages.sort();
         self.send_packet(game, ToClientPacket::NightMessages { chat_messages: 
             messages.iter().map(|msg|ChatMessage::new_private(msg.clone())).collect()
@@ -384,16 +383,13 @@ impl PlayerReference{
             },
         }
     }
-    /// If they can consistently kill then they keep the game running
-    /// Town kills by voting
-    /// Mafia kills with MK or gun
-    /// Cult kills / converts
+    /// See [`GameConclusion::player_keeps_game_running`]
     pub fn keeps_game_running(&self, game: &Game) -> bool {
-        if InsiderGroupID::Mafia.contains_player(game, *self) {return true;}
-        if InsiderGroupID::Cult.contains_player(game, *self) {return true;}
-        if self.win_condition(game).is_loyalist_for(GameConclusion::Town) {return true;}
-        
-        GameConclusion::keeps_game_running(self.role(game))
+        GameConclusion::player_keeps_game_running(
+            self.role(game),
+            &InsiderGroupID::all_groups_with_player(game, *self),
+            self.win_condition(game)
+        )
     }
 
     /*
@@ -417,7 +413,7 @@ impl PlayerReference{
     pub fn on_role_creation(&self, game: &mut Game) {
         self.role_state(game).clone().on_role_creation(game, *self)
     }
-    pub fn get_current_send_chat_groups(&self, game: &Game) -> HashSet<ChatGroup> {
+    pub fn get_current_send_chat_groups(&self, game: &Game) -> VecSet<ChatGroup> {
         if Modifiers::is_enabled(game, ModifierType::NoChat)
             || (
                 Modifiers::is_enabled(game, ModifierType::NoNightChat) 
@@ -425,11 +421,11 @@ impl PlayerReference{
                 && matches!(game.current_phase().phase(), PhaseType::Night | PhaseType::Obituary)
             )
         {
-            return HashSet::new()
+            return VecSet::new()
         }
         self.role_state(game).clone().get_current_send_chat_groups(game, *self)
     }
-    pub fn get_current_receive_chat_groups(&self, game: &Game) -> HashSet<ChatGroup> {
+    pub fn get_current_receive_chat_groups(&self, game: &Game) -> VecSet<ChatGroup> {
         self.role_state(game).clone().get_current_receive_chat_groups(game, *self)
     }
     pub fn convert_selection_to_visits(&self, game: &Game) -> Vec<Visit> {
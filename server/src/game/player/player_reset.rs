@@ -7,13 +7,15 @@ impl PlayerReference{
     pub fn tick(&self, game: &mut Game, time_passed: Duration){
         match &self.deref(game).connection {
             ClientConnection::Connected(_) => self.send_repeating_data(game),
-            ClientConnection::CouldReconnect { disconnect_timer } => {
+            ClientConnection::CouldReconnect { disconnect_timer, .. } => {
                 match disconnect_timer.saturating_sub(time_passed) {
                     Duration::ZERO => {
                         self.quit(game);
                     },
                     time_remaining => {
-                        self.deref_mut(game).connection = ClientConnection::CouldReconnect { disconnect_timer: time_remaining }
+                        if let ClientConnection::CouldReconnect { disconnect_timer, .. } = &mut self.deref_mut(game).connection {
+                            *disconnect_timer = time_remaining;
+                        }
                     }
                 }
             },
@@ -1,9 +1,7 @@
-use std::time::Duration;
-
 use crate::{
-    client_connection::ClientConnection, 
+    client_connection::ClientConnection, config::ServerConfig,
     game::{
-        chat::ChatMessageVariant, components::{insider_group::InsiderGroups, tags::Tags},
+        ability_input::saved_controllers_map::SavedControllersMap, chat::ChatMessageVariant, components::{insider_group::InsiderGroups, tags::Tags},
         Game, GameOverReason
     },
     packet::ToClientPacket, websocket_connections::connection::ClientSender
@@ -13,14 +11,22 @@ use super::PlayerReference;
 
 impl PlayerReference{
     pub fn connect(&self, game: &mut Game, sender: ClientSender){
-        self.deref_mut(game).connection = ClientConnection::Connected(sender);
+        self.reconnect(game, sender);
         self.send_join_game_data(game);
     }
+    /// Gives the player its client's new connection without sending it anything,
+    /// for a client that's being sent only what it missed
+    pub fn reconnect(&self, game: &mut Game, sender: ClientSender){
+        self.deref_mut(game).connection = ClientConnection::Connected(sender);
+        game.saved_controllers.mark_outdated();
+    }
     pub fn lose_connection(&self, game: &mut Game){
-        self.deref_mut(game).connection = ClientConnection::CouldReconnect { disconnect_timer: Duration::from_secs(Game::DISCONNECT_TIMER_SECS as u64) };
+        self.deref_mut(game).connection = self.deref(game).connection.lost(ServerConfig::get().game_disconnect_timer());
+        game.saved_controllers.mark_outdated();
     }
     pub fn quit(&self, game: &mut Game) {
         self.deref_mut(game).connection = ClientConnection::Disconnected;
+        game.saved_controllers.mark_outdated();
         if self.alive(game) {
             game.add_message_to_chat_group(
                 crate::game::chat::ChatGroup::All, 
@@ -41,6 +47,9 @@ impl PlayerReference{
     pub fn is_disconnected(&self, game: &Game) -> bool {
         matches!(self.deref(game).connection, ClientConnection::Disconnected)
     }
+    pub fn is_bot(&self, game: &Game) -> bool {
+        matches!(self.deref(game).connection, ClientConnection::Bot)
+    }
 
     pub fn send_packet(&self, game: &Game, packet: ToClientPacket){
         self.deref(game).connection.send_packet(packet);
@@ -102,9 +111,11 @@ impl PlayerReference{
             ToClientPacket::YourJudgement{
                 verdict: self.verdict(game)
             },
-            ToClientPacket::YourAllowedControllers { 
-                save: game.saved_controllers.controllers_allowed_to_player(*self).all_controllers().clone(),
-            },
+        ]);
+
+        SavedControllersMap::send_all_saved_controllers_to_client(game, *self);
+
+        self.send_packets(game, vec![
             ToClientPacket::YourWill{
                 will: self.will(game).clone()
             },
@@ -0,0 +1,142 @@
+//! A replay is an append-only log of everything that happened to a game:
+//! the parameters it was started with, every packet it processed, clients joining and leaving, and every tick.
+//!
+//! Since all randomness in a game comes from its seed (see [`GameRng`](super::rng::GameRng)),
+//! a [`ReplayPlayback`](playback::ReplayPlayback) can rebuild the exact same game from a replay,
+//! including every packet the game sent to its clients.
+
+pub mod playback;
+
+use std::{fs::File, io::{self, BufReader, BufWriter}, path::{Path, PathBuf}, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{client_connection::ClientConnection, packet::ToServerPacket, room::RoomClientID, vec_map::VecMap};
+
+use super::{
+    game_client::{GameClient, GameClientLocation}, player::{PlayerIndex, PlayerInitializeParameters},
+    rng::GameSeed, settings::Settings,
+    spectator::{spectator_pointer::SpectatorIndex, SpectatorInitializeParameters}
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Replay {
+    pub room_name: String,
+    pub settings: Settings,
+    pub seed: GameSeed,
+    pub clients: VecMap<RoomClientID, ReplayClient>,
+    /// In player index order
+    pub players: Vec<ReplayPlayer>,
+    /// In spectator index order
+    pub spectators: Vec<ReplaySpectator>,
+    pub events: Vec<ReplayEvent>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayClient {
+    pub location: ReplayClientLocation,
+    pub host: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(tag = "type", content = "index", rename_all = "camelCase")]
+pub enum ReplayClientLocation {
+    Player(PlayerIndex),
+    Spectator(SpectatorIndex),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayPlayer {
+    pub name: String,
+    pub host: bool,
+    #[serde(default)]
+    pub bot: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplaySpectator {
+    pub host: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ReplayEvent {
+    #[serde(rename_all = "camelCase")]
+    Packet { room_client_id: RoomClientID, packet: ToServerPacket },
+    #[serde(rename_all = "camelCase")]
+    Join { room_client_id: RoomClientID },
+    #[serde(rename_all = "camelCase")]
+    Rejoin { room_client_id: RoomClientID },
+    #[serde(rename_all = "camelCase")]
+    Initialize { room_client_id: RoomClientID },
+    #[serde(rename_all = "camelCase")]
+    Leave { room_client_id: RoomClientID },
+    #[serde(rename_all = "camelCase")]
+    LoseConnection { room_client_id: RoomClientID },
+    #[serde(rename_all = "camelCase")]
+    Tick { time_passed: Duration },
+}
+
+impl Replay {
+    pub fn new(
+        room_name: &str,
+        settings: &Settings,
+        seed: GameSeed,
+        clients: &VecMap<RoomClientID, GameClient>,
+        players: &[PlayerInitializeParameters],
+        spectators: &[SpectatorInitializeParameters]
+    ) -> Self {
+        Self {
+            room_name: room_name.to_string(),
+            settings: settings.clone(),
+            seed,
+            clients: clients.iter()
+                .map(|(id, client)| (*id, ReplayClient {
+                    location: match client.client_location {
+                        GameClientLocation::Player(player) => ReplayClientLocation::Player(player.index()),
+                        GameClientLocation::Spectator(spectator) => ReplayClientLocation::Spectator(spectator.index()),
+                    },
+                    host: client.host
+                }))
+                .collect(),
+            players: players.iter()
+                .map(|player| ReplayPlayer {
+                    name: player.name.clone(),
+                    host: player.host,
+                    bot: matches!(player.connection, ClientConnection::Bot)
+                })
+                .collect(),
+            spectators: spectators.iter()
+                .map(|spectator| ReplaySpectator { host: spectator.host })
+                .collect(),
+            events: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, event: ReplayEvent) {
+        self.events.push(event);
+    }
+
+    /// Writes this replay to a new file in `directory`, returning the path of the file
+    pub fn save(&self, directory: &Path) -> io::Result<PathBuf> {
+        std::fs::create_dir_all(directory)?;
+
+        let path = directory.join(format!(
+            "replay-{}-{}.json",
+            chrono::Local::now().format("%Y%m%d-%H%M%S"),
+            self.seed
+        ));
+
+        serde_json::to_writer(BufWriter::new(File::create(&path)?), self)?;
+
+        Ok(path)
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Ok(serde_json::from_reader(BufReader::new(File::open(path)?))?)
+    }
+}
@@ -0,0 +1,238 @@
+use std::collections::VecDeque;
+
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+
+use crate::{
+    client_connection::ClientConnection, game::{
+        game_client::{GameClient, GameClientLocation}, on_client_message::GameClientMessageResult,
+        player::{PlayerInitializeParameters, PlayerReference},
+        spectator::{spectator_pointer::SpectatorPointer, SpectatorInitializeParameters},
+        Game, RejectStartReason
+    },
+    packet::ToClientPacket, room::{RemoveRoomClientResult, RoomClientID, RoomState},
+    vec_map::VecMap, websocket_connections::connection::{ClientSender, SequencedPacket}
+};
+
+use super::{Replay, ReplayClientLocation, ReplayEvent};
+
+/// Rebuilds a game from a [`Replay`] and steps through its events one at a time,
+/// collecting every packet the game sends to each client.
+pub struct ReplayPlayback {
+    game: Game,
+    events: VecDeque<ReplayEvent>,
+    senders: VecMap<RoomClientID, ClientSender>,
+    receivers: Vec<(RoomClientID, UnboundedReceiver<SequencedPacket>)>,
+    packets: VecMap<RoomClientID, Vec<ToClientPacket>>,
+    finished: bool,
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    CouldntStart(RejectStartReason),
+    /// The clients, players and spectators of the replay don't line up
+    InvalidClients,
+    UnknownClient(RoomClientID),
+    /// The game did something different from what happened when it was recorded
+    Diverged(ReplayEvent),
+}
+
+impl ReplayPlayback {
+    pub fn new(replay: Replay) -> Result<Self, ReplayError> {
+        let mut senders = VecMap::new();
+        let mut receivers = Vec::new();
+        let mut game_clients = VecMap::new();
+
+        for (room_client_id, client) in replay.clients.iter() {
+            let (sender, receiver) = mpsc::unbounded_channel();
+            senders.insert(*room_client_id, ClientSender::new(sender));
+            receivers.push((*room_client_id, receiver));
+
+            let client_location = match client.location {
+                ReplayClientLocation::Player(index) => {
+                    if index as usize >= replay.players.len() {
+                        return Err(ReplayError::InvalidClients);
+                    }
+                    // We just checked the index is in bounds of the player list
+                    GameClientLocation::Player(unsafe { PlayerReference::new_unchecked(index) })
+                },
+                ReplayClientLocation::Spectator(index) => {
+                    if index as usize >= replay.spectators.len() {
+                        return Err(ReplayError::InvalidClients);
+                    }
+                    GameClientLocation::Spectator(SpectatorPointer::new(index))
+                },
+            };
+
+            game_clients.insert(*room_client_id, GameClient {
+                client_location,
+                host: client.host,
+                last_message_times: VecDeque::new(),
+            });
+        }
+
+        let find_sender = |location: &dyn Fn(ReplayClientLocation) -> bool| -> Result<ClientSender, ReplayError> {
+            replay.clients.iter()
+                .find(|(_, client)| location(client.location))
+                .and_then(|(id, _)| senders.get(id).cloned())
+                .ok_or(ReplayError::InvalidClients)
+        };
+
+        let mut players = Vec::new();
+        for (index, player) in replay.players.iter().enumerate() {
+            let sender = find_sender(&|location| matches!(location, ReplayClientLocation::Player(i) if i as usize == index))?;
+            players.push(PlayerInitializeParameters {
+                connection: if player.bot { ClientConnection::Bot } else { ClientConnection::Connected(sender) },
+                name: player.name.clone(),
+                host: player.host,
+            });
+        }
+
+        let mut spectators = Vec::new();
+        for (index, spectator) in replay.spectators.iter().enumerate() {
+            let sender = find_sender(&|location| matches!(location, ReplayClientLocation::Spectator(i) if i as usize == index))?;
+            spectators.push(SpectatorInitializeParameters {
+                connection: ClientConnection::Connected(sender),
+                host: spectator.host,
+            });
+        }
+
+        let game = Game::new(replay.room_name, replay.settings, game_clients, players, spectators, replay.seed)
+            .map_err(ReplayError::CouldntStart)?;
+
+        let mut playback = Self {
+            game,
+            events: replay.events.into(),
+            senders,
+            receivers,
+            packets: VecMap::new(),
+            finished: false,
+        };
+        playback.collect_packets();
+
+        Ok(playback)
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// Every packet the game has sent so far, by the client it was sent to
+    pub fn packets(&self) -> &VecMap<RoomClientID, Vec<ToClientPacket>> {
+        &self.packets
+    }
+
+    /// Whether the game closed or went back to lobby, or there are no events left
+    pub fn is_finished(&self) -> bool {
+        self.finished || self.events.is_empty()
+    }
+
+    /// Applies the next event to the game, returning it.
+    /// Returns `None` once the playback is finished.
+    pub fn step(&mut self) -> Result<Option<ReplayEvent>, ReplayError> {
+        if self.finished { return Ok(None) }
+        let Some(event) = self.events.pop_front() else { return Ok(None) };
+
+        match event.clone() {
+            ReplayEvent::Packet { room_client_id, packet } => {
+                let sender = self.sender(room_client_id)?;
+
+                match self.game.on_client_message(&sender, room_client_id, packet) {
+                    GameClientMessageResult::BackToLobby(_) |
+                    GameClientMessageResult::Close => self.finished = true,
+                    GameClientMessageResult::None => {}
+                }
+            },
+            ReplayEvent::Join { room_client_id } => {
+                let sender = self.new_sender(room_client_id);
+
+                match self.game.join_client(&sender) {
+                    Ok(result) if result.id == room_client_id => {},
+                    _ => return Err(ReplayError::Diverged(event)),
+                }
+            },
+            ReplayEvent::Rejoin { room_client_id } => {
+                let sender = self.new_sender(room_client_id);
+
+                if self.game.rejoin_client(&sender, room_client_id).is_err() {
+                    return Err(ReplayError::Diverged(event));
+                }
+            },
+            ReplayEvent::Initialize { room_client_id } => {
+                let sender = self.sender(room_client_id)?;
+                self.game.initialize_client(room_client_id, &sender);
+            },
+            ReplayEvent::Leave { room_client_id } => {
+                if let RemoveRoomClientResult::RoomShouldClose = self.game.remove_client(room_client_id) {
+                    self.finished = true;
+                }
+            },
+            ReplayEvent::LoseConnection { room_client_id } => {
+                if let RemoveRoomClientResult::RoomShouldClose = self.game.remove_client_rejoinable(room_client_id) {
+                    self.finished = true;
+                }
+            },
+            ReplayEvent::Tick { time_passed } => {
+                if self.game.tick(time_passed).close_room {
+                    self.finished = true;
+                }
+            },
+        }
+
+        self.collect_packets();
+
+        Ok(Some(event))
+    }
+
+    pub fn play_to_end(&mut self) -> Result<(), ReplayError> {
+        while self.step()?.is_some() {}
+        Ok(())
+    }
+
+    pub fn into_game(self) -> Game {
+        self.game
+    }
+
+    fn sender(&self, room_client_id: RoomClientID) -> Result<ClientSender, ReplayError> {
+        self.senders.get(&room_client_id).cloned().ok_or(ReplayError::UnknownClient(room_client_id))
+    }
+
+    fn new_sender(&mut self, room_client_id: RoomClientID) -> ClientSender {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let sender = ClientSender::new(sender);
+
+        self.senders.insert(room_client_id, sender.clone());
+        self.receivers.push((room_client_id, receiver));
+
+        sender
+    }
+
+    fn collect_packets(&mut self) {
+        for (room_client_id, receiver) in self.receivers.iter_mut() {
+            while let Ok(SequencedPacket { packet, .. }) = receiver.try_recv() {
+                if let Some(packets) = self.packets.get_mut(room_client_id) {
+                    packets.push(packet);
+                } else {
+                    self.packets.insert(*room_client_id, vec![packet]);
+                }
+            }
+        }
+    }
+}
+
+impl Game {
+    /// Rebuilds a game by playing its replay to the end.
+    /// Every client is then treated as having lost connection, so players can rejoin it.
+    pub fn from_replay(replay: Replay) -> Result<Self, ReplayError> {
+        let mut playback = ReplayPlayback::new(replay)?;
+        playback.play_to_end()?;
+
+        let mut game = playback.into_game();
+
+        let room_client_ids: Vec<RoomClientID> = game.clients.keys().copied().collect();
+        for room_client_id in room_client_ids {
+            let _ = game.remove_client_rejoinable(room_client_id);
+        }
+
+        Ok(game)
+    }
+}
@@ -0,0 +1,36 @@
+use std::cell::{RefCell, RefMut};
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+pub type GameSeed = u64;
+
+/// The source of all randomness in a game.
+/// Anything random that happens in a game (role generation, player order, role results, grave order)
+/// should draw from this, so the same seed and the same inputs always produce the same game.
+///
+/// It lives in a `RefCell` so it can be drawn from wherever only `&Game` is available,
+/// like while building controller parameters.
+pub struct GameRng {
+    seed: GameSeed,
+    rng: RefCell<ChaCha8Rng>,
+}
+impl GameRng {
+    pub fn new(seed: GameSeed) -> Self {
+        Self {
+            seed,
+            rng: RefCell::new(ChaCha8Rng::seed_from_u64(seed)),
+        }
+    }
+    pub fn new_random() -> Self {
+        Self::new(rand::random())
+    }
+    /// The seed this game was started with
+    pub fn seed(&self) -> GameSeed {
+        self.seed
+    }
+    /// Don't hold on to this, drawing from the rng again while it is borrowed will panic
+    pub fn get(&self) -> RefMut<'_, ChaCha8Rng> {
+        self.rng.borrow_mut()
+    }
+}
@@ -40,18 +40,19 @@ impl RoleStateImpl for Ambusher {
                 let Some(ambush_visit) = actor_visits.first() else {return};
                 let target_ref = ambush_visit.target;
 
-                let player_to_attacks_visit = 
-                if let Some(priority_visitor) = NightVisits::all_visits(midnight_variables).into_iter()
+                let priority_visitor = NightVisits::all_visits(midnight_variables).into_iter()
                     .filter(|visit|
                         ambush_visit != *visit &&
                         visit.target == target_ref &&
                         visit.visitor.alive(game) &&
                         visit.visitor.win_condition(game).is_loyalist_for(GameConclusion::Town)
                     ).collect::<Vec<&Visit>>()
-                    .choose(&mut rand::rng())
-                    .copied()
-                {
-                    Some(priority_visitor.visitor)
+                    .choose(&mut *game.rng())
+                    .map(|v|v.visitor);
+
+                let player_to_attacks_visit = 
+                if let Some(priority_visitor) = priority_visitor {
+                    Some(priority_visitor)
                 } else {
                     NightVisits::all_visits(midnight_variables).into_iter()
                         .filter(|visit|
@@ -59,7 +60,7 @@ impl RoleStateImpl for Ambusher {
                             visit.target == target_ref &&
                             visit.visitor.alive(game)
                         ).collect::<Vec<&Visit>>()
-                        .choose(&mut rand::rng())
+                        .choose(&mut *game.rng())
                         .copied()
                         .map(|v|v.visitor)
                 };
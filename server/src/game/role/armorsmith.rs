@@ -61,8 +61,11 @@ impl RoleStateImpl for Armorsmith {
 
                         if visitors.contains(&visit.target){
                             PlayerComponent::<FragileVests>::add_defense_item_midnight(game, midnight_variables, visit.target, DefensePower::Protected, vec_set![actor_ref]);
-                        }else if let Some(random_visitor) = visitors.choose(&mut rand::rng()) {
-                            PlayerComponent::<FragileVests>::add_defense_item_midnight(game, midnight_variables, *random_visitor, DefensePower::Protected, vec_set![actor_ref]);
+                        }else{
+                            let random_visitor = visitors.choose(&mut *game.rng()).copied();
+                            if let Some(random_visitor) = random_visitor {
+                                PlayerComponent::<FragileVests>::add_defense_item_midnight(game, midnight_variables, random_visitor, DefensePower::Protected, vec_set![actor_ref]);
+                            }
                         }
                     }
                 };
@@ -93,7 +93,7 @@ impl Auditor{
             .map(|data| data.role())
             .filter(|x|game.settings.enabled_roles.contains(x))
             .collect::<Vec<Role>>();
-        all_possible_fake_roles.shuffle(&mut rand::rng());
+        all_possible_fake_roles.shuffle(&mut *game.rng());
 
         let role = chosen_outline.deref_as_role_and_player_originally_generated(game).0;
         let mut out = VecSet::new();
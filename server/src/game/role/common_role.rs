@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use crate::vec_set::VecSet;
 
 use crate::game::{
     ability_input::*,
@@ -103,7 +103,7 @@ pub(super) fn convert_controller_selection_to_visits_possession(game: &Game, act
 
 
 
-pub(super) fn get_current_send_chat_groups(game: &Game, actor_ref: PlayerReference, mut night_chat_groups: Vec<ChatGroup>) -> HashSet<ChatGroup> {
+pub(super) fn get_current_send_chat_groups(game: &Game, actor_ref: PlayerReference, mut night_chat_groups: Vec<ChatGroup>) -> VecSet<ChatGroup> {
     if game.current_phase().phase() == PhaseType::Recess {
         return vec![ChatGroup::All].into_iter().collect()
     }
@@ -117,13 +117,13 @@ pub(super) fn get_current_send_chat_groups(game: &Game, actor_ref: PlayerReferen
         return vec![ChatGroup::Dead].into_iter().collect();
     }
     if Silenced::silenced(game, actor_ref) {
-        return HashSet::new();
+        return VecSet::new();
     }
 
     match game.current_phase() {
-        PhaseState::Briefing => HashSet::new(),
+        PhaseState::Briefing => VecSet::new(),
         PhaseState::Obituary { .. } => {
-            let mut out = HashSet::new();
+            let mut out = VecSet::new();
 
             //evil chat groups
             if InsiderGroupID::Puppeteer.contains_player(game, actor_ref) {
@@ -162,7 +162,7 @@ pub(super) fn get_current_send_chat_groups(game: &Game, actor_ref: PlayerReferen
             if player_on_trial == actor_ref {
                 vec![ChatGroup::All].into_iter().collect()
             } else {
-                HashSet::new()
+                VecSet::new()
             }
         },
         PhaseState::Night => {
@@ -245,7 +245,7 @@ pub(super) fn get_current_send_chat_groups(game: &Game, actor_ref: PlayerReferen
         },
     }
 }
-pub(super) fn get_current_receive_chat_groups(game: &Game, actor_ref: PlayerReference) -> HashSet<ChatGroup> {
+pub(super) fn get_current_receive_chat_groups(game: &Game, actor_ref: PlayerReference) -> VecSet<ChatGroup> {
     let mut out = Vec::new();
 
     out.push(ChatGroup::All);
@@ -1,4 +1,3 @@
-use std::collections::HashSet;
 
 use serde::Serialize;
 
@@ -47,7 +46,7 @@ impl RoleStateImpl for Coxswain {
             .allow_players([actor_ref])
             .build_map()
     }
-    fn get_current_send_chat_groups(self, game: &Game, actor_ref: PlayerReference) -> HashSet<ChatGroup> {
+    fn get_current_send_chat_groups(self, game: &Game, actor_ref: PlayerReference) -> VecSet<ChatGroup> {
         let mut out = crate::game::role::common_role::get_current_send_chat_groups(game, actor_ref, vec![ChatGroup::Dead]);
 
         if 
@@ -58,7 +57,7 @@ impl RoleStateImpl for Coxswain {
         }
         out
     }
-    fn get_current_receive_chat_groups(self, game: &Game, actor_ref: PlayerReference) -> HashSet<ChatGroup> {
+    fn get_current_receive_chat_groups(self, game: &Game, actor_ref: PlayerReference) -> VecSet<ChatGroup> {
         let mut out = crate::game::role::common_role::get_current_receive_chat_groups(game, actor_ref);
 
         if
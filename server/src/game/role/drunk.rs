@@ -26,7 +26,8 @@ impl RoleStateImpl for Drunk {
         //special case here. I don't want to use set_role because it alerts the player their role changed
         //NOTE: It will still send a packet to the player that their role state updated,
         //so it might be deducible that the player is a drunk
-        if let Some(random_town_role) = possible_roles.choose(&mut rand::rng()) {
+        let random_town_role = possible_roles.choose(&mut *game.rng()).copied();
+        if let Some(random_town_role) = random_town_role {
             actor_ref.set_role_state(game, random_town_role.new_state(game));
         }
 
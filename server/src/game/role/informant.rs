@@ -28,10 +28,10 @@ impl RoleStateImpl for Informant {
             let target_ref = visit.target;
 
             let mut visited_by: Vec<PlayerReference> =  visit.target.all_appeared_visitors(game, midnight_variables).into_iter().filter(|p|actor_ref!=*p).collect();
-            visited_by.shuffle(&mut rand::rng());
+            visited_by.shuffle(&mut *game.rng());
 
             let mut visited: Vec<PlayerReference> = target_ref.tracker_seen_visits(game, midnight_variables).iter().map(|v|v.target).collect();
-            visited.shuffle(&mut rand::rng());
+            visited.shuffle(&mut *game.rng());
 
             let message = ChatMessageVariant::InformantResult{
                 player: target_ref,
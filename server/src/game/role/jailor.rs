@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use crate::vec_set::VecSet;
 
 use serde::Serialize;
 
@@ -97,7 +97,7 @@ impl RoleStateImpl for Jailor {
                 .build_map()
         ])
     }
-    fn get_current_send_chat_groups(self, game: &Game, actor_ref: PlayerReference) -> HashSet<ChatGroup> {
+    fn get_current_send_chat_groups(self, game: &Game, actor_ref: PlayerReference) -> VecSet<ChatGroup> {
         crate::game::role::common_role::get_current_send_chat_groups(game, actor_ref, 
             if PlayerReference::all_players(game).any(|p|Detained::is_detained(game, p)) {
                 vec![ChatGroup::Jail].into_iter().collect()
@@ -106,7 +106,7 @@ impl RoleStateImpl for Jailor {
             }
         )
     }
-    fn get_current_receive_chat_groups(self, game: &Game, actor_ref: PlayerReference) -> HashSet<ChatGroup> {
+    fn get_current_receive_chat_groups(self, game: &Game, actor_ref: PlayerReference) -> VecSet<ChatGroup> {
         let mut out = crate::game::role::common_role::get_current_receive_chat_groups(game, actor_ref);
         if 
             game.current_phase().is_night() &&
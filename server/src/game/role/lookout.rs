@@ -25,7 +25,7 @@ impl RoleStateImpl for Lookout {
         if let Some(visit) = actor_visits.first(){
             
             let mut seen_players: Vec<PlayerReference> = visit.target.all_appeared_visitors(game, midnight_variables).into_iter().filter(|p|actor_ref!=*p).collect();
-            seen_players.shuffle(&mut rand::rng());
+            seen_players.shuffle(&mut *game.rng());
 
             let message = ChatMessageVariant::LookoutResult { players:
                 PlayerReference::ref_vec_to_index(seen_players.as_slice())
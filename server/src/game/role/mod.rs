@@ -1,6 +1,5 @@
 #![allow(clippy::single_match, reason = "May add more cases for more priorities later")]
 
-use std::collections::HashSet;
 use crate::vec_set::{vec_set, VecSet};
 
 use crate::game::player::PlayerReference;
@@ -44,10 +43,10 @@ pub trait RoleStateImpl: Clone + std::fmt::Debug + Default + GetClientRoleState<
         vec![]
     }
 
-    fn get_current_send_chat_groups(self, game: &Game, actor_ref: PlayerReference) -> HashSet<ChatGroup> {
+    fn get_current_send_chat_groups(self, game: &Game, actor_ref: PlayerReference) -> VecSet<ChatGroup> {
         crate::game::role::common_role::get_current_send_chat_groups(game, actor_ref, vec![])
     }
-    fn get_current_receive_chat_groups(self, game: &Game, actor_ref: PlayerReference) -> HashSet<ChatGroup> {
+    fn get_current_receive_chat_groups(self, game: &Game, actor_ref: PlayerReference) -> VecSet<ChatGroup> {
         crate::game::role::common_role::get_current_receive_chat_groups(game, actor_ref)
     }
     fn new_state(_game: &Game) -> Self {
@@ -286,12 +285,12 @@ mod macros {
                         $(Self::$name(role_struct) => role_struct.convert_selection_to_visits(game, actor_ref)),*
                     }
                 }
-                pub fn get_current_send_chat_groups(self, game: &Game, actor_ref: PlayerReference) -> HashSet<ChatGroup>{
+                pub fn get_current_send_chat_groups(self, game: &Game, actor_ref: PlayerReference) -> VecSet<ChatGroup>{
                     match self {
                         $(Self::$name(role_struct) => role_struct.get_current_send_chat_groups(game, actor_ref)),*
                     }
                 }
-                pub fn get_current_receive_chat_groups(self, game: &Game, actor_ref: PlayerReference) -> HashSet<ChatGroup>{
+                pub fn get_current_receive_chat_groups(self, game: &Game, actor_ref: PlayerReference) -> VecSet<ChatGroup>{
                     match self {
                         $(Self::$name(role_struct) => role_struct.get_current_receive_chat_groups(game, actor_ref)),*
                     }
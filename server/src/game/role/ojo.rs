@@ -47,7 +47,7 @@ impl RoleStateImpl for Ojo {
                     .for_each(|player_ref|{
 
                     let mut players: Vec<PlayerIndex> = player_ref.all_night_visits_cloned(midnight_variables).into_iter().map(|p|p.target.index()).collect();
-                    players.shuffle(&mut rand::rng());
+                    players.shuffle(&mut *game.rng());
 
                     actor_ref.push_night_message(midnight_variables, 
                         ChatMessageVariant::WerewolfTrackingResult{
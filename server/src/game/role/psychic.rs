@@ -62,7 +62,7 @@ impl Psychic {
             .filter(|p|!p.has_innocent_aura(game))
             .collect();
 
-        valid_players.shuffle(&mut rand::rng());
+        valid_players.shuffle(&mut *game.rng());
 
         #[expect(clippy::indexing_slicing, reason = "We're iterating over indexes, so it's safe")]
         for i in 0..valid_players.len(){
@@ -82,7 +82,7 @@ impl Psychic {
             .filter(|p|!p.has_suspicious_aura(game, midnight_variables))
             .collect();
 
-        valid_players.shuffle(&mut rand::rng());
+        valid_players.shuffle(&mut *game.rng());
 
         for player in valid_players{
             if confused || Self::contains_good(game, target, player){
@@ -121,17 +121,19 @@ impl RoleStateImpl for Recruiter {
         let random_mafia_player = PlayerReference::all_players(game)
             .filter(|p|RoleSet::Mafia.get_roles().contains(&p.role(game)))
             .filter(|p|*p!=actor_ref)
-            .choose(&mut rand::rng());
+            .choose(&mut *game.rng());
 
         if let Some(random_mafia_player) = random_mafia_player {
 
             let random_town_role = RoleOutline {options: vec1![RoleOutlineOption {
                 win_condition: Default::default(), 
                 insider_groups: Default::default(), 
-                roles: RoleOutlineOptionRoles::RoleSet{ role_set: RoleSet::TownCommon } 
+                roles: RoleOutlineOptionRoles::RoleSet{ role_set: RoleSet::TownCommon },
+                weight: None
             }]}.get_random_role_assignments(
                 &game.settings.enabled_roles,
-                PlayerReference::all_players(game).map(|p|p.role(game)).collect::<Vec<_>>().as_slice()
+                PlayerReference::all_players(game).map(|p|p.role(game)).collect::<Vec<_>>().as_slice(),
+                &mut *game.rng()
             ).map(|assignment| assignment.role());
 
             if let Some(random_town_role) = random_town_role {
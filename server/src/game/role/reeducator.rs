@@ -166,6 +166,6 @@ impl Reeducator {
         RoleSet::MafiaSupport.get_roles().into_iter()
             .filter(|p|game.settings.enabled_roles.contains(p))
             .filter(|p|*p!=Role::Reeducator)
-            .choose(&mut rand::rng()).unwrap_or(Role::Reeducator)
+            .choose(&mut *game.rng()).unwrap_or(Role::Reeducator)
     }
 }
\ No newline at end of file
@@ -71,7 +71,7 @@ impl RoleStateImpl for Revolutionary {
 
 
 
-        if let Some(target) = PlayerReference::all_players(game)
+        let target = PlayerReference::all_players(game)
             .filter(|p|
                 RoleSet::Town.get_roles().contains(&p.role(game)) &&
                 
@@ -84,11 +84,13 @@ impl RoleStateImpl for Revolutionary {
                 p.role(game) != Role::Mayor &&
                 p.role(game) != Role::Reporter
             ).collect::<Vec<PlayerReference>>()
-            .choose(&mut rand::rng())
-        {
-            Tags::add_tag(game, TagSetID::RevolutionaryTarget(actor_ref), *target);
-            actor_ref.set_role_state(game, RoleState::Revolutionary(Revolutionary{target: RevolutionaryTarget::Target(*target)}));
-            actor_ref.reveal_players_role(game, *target);
+            .choose(&mut *game.rng())
+            .copied();
+
+        if let Some(target) = target {
+            Tags::add_tag(game, TagSetID::RevolutionaryTarget(actor_ref), target);
+            actor_ref.set_role_state(game, RoleState::Revolutionary(Revolutionary{target: RevolutionaryTarget::Target(target)}));
+            actor_ref.reveal_players_role(game, target);
         }else{
             actor_ref.set_role_and_win_condition_and_revealed_group(game, RoleState::Jester(Jester::default()))
         };
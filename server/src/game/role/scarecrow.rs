@@ -35,7 +35,7 @@ impl RoleStateImpl for Scarecrow {
         let target_ref = visit.target;
 
         let mut blocked_players = target_ref.ward(game, midnight_variables, &[*visit]);
-        blocked_players.shuffle(&mut rand::rng());
+        blocked_players.shuffle(&mut *game.rng());
 
         let message = ChatMessageVariant::ScarecrowResult { players:
             PlayerReference::ref_vec_to_index(blocked_players.as_slice())
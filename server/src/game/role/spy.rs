@@ -46,7 +46,7 @@ impl RoleStateImpl for Spy {
                             .collect()
                     );
                 }
-                mafia_visits.shuffle(&mut rand::rng());
+                mafia_visits.shuffle(&mut *game.rng());
                 
                 actor_ref.push_night_message(midnight_variables, ChatMessageVariant::SpyMafiaVisit { players: mafia_visits });               
             },
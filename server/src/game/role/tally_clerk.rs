@@ -1,3 +1,4 @@
+use rand::Rng;
 use serde::Serialize;
 
 use crate::game::attack_power::DefensePower;
@@ -51,9 +52,9 @@ impl TallyClerk {
         out
     }
     fn confused_result(game: &Game, midnight_variables: &MidnightVariables)->u8{
-        let total_guilties = VerdictsToday::guilties(game).len();
+        let total_guilties = VerdictsToday::guilties(game).count();
 
-        let evil_count = Self::result(game, midnight_variables).saturating_add_signed(rand::random_range(0..=1));
+        let evil_count = Self::result(game, midnight_variables).saturating_add_signed(game.rng().random_range(0..=1));
         
         evil_count.min(total_guilties.try_into().unwrap_or(u8::MAX))
     }
@@ -27,7 +27,7 @@ impl RoleStateImpl for Tracker {
         if let Some(visit) = actor_visits.first(){
             
             let mut seen_players: Vec<PlayerReference> = visit.target.tracker_seen_visits(game, midnight_variables).into_iter().map(|v|v.target).collect();
-            seen_players.shuffle(&mut rand::rng());
+            seen_players.shuffle(&mut *game.rng());
 
             let message = ChatMessageVariant::TrackerResult { players:
                 PlayerReference::ref_vec_to_index(seen_players.as_slice())
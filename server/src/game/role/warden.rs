@@ -56,7 +56,7 @@ impl RoleStateImpl for Warden {
 
         
     }
-    fn get_current_receive_chat_groups(self, game: &Game, actor_ref: PlayerReference) -> std::collections::HashSet<crate::game::chat::ChatGroup> {
+    fn get_current_receive_chat_groups(self, game: &Game, actor_ref: PlayerReference) -> crate::vec_set::VecSet<crate::game::chat::ChatGroup> {
         common_role::get_current_receive_chat_groups(game, actor_ref)
             .into_iter()
             .chain([crate::game::chat::ChatGroup::Warden])
@@ -101,7 +101,7 @@ impl RoleStateImpl for Werewolf {
                     .for_each(|player_ref|{
 
                     let mut players: Vec<PlayerIndex> = player_ref.tracker_seen_visits(game, midnight_variables).into_iter().map(|p|p.target.index()).collect();
-                    players.shuffle(&mut rand::rng());
+                    players.shuffle(&mut *game.rng());
 
                     actor_ref.push_night_message(midnight_variables, 
                         ChatMessageVariant::WerewolfTrackingResult{
@@ -0,0 +1,810 @@
+//! Picks a role for every outline of a role list at once.
+//!
+//! Each outline can be any of its enabled roles, each as likely as its weight says
+//! (see [`RoleOutline::get_weighted_role_assignments`](super::role_list::RoleOutline::get_weighted_role_assignments)),
+//! but a role with a maximum count can only be picked that many times across the whole list,
+//! and the whole list has to meet the lobby's [constraints](super::role_list_constraints).
+//! Whether any assignment does that is decided first, so a list that can't be generated fails straight away,
+//! naming the outlines or constraints that can't be met. Outlines and constraints are named by their index, starting from 0.
+//!
+//! That's decided by sending a flow from the outlines, through the roles they could be, through the count constraints
+//! those roles are in, with each role and constraint letting through as many outlines as it allows.
+//! Role sets nest inside each other, so the count constraints do too, or don't overlap at all,
+//! and each role's constraints form a chain the flow can pass through in turn. If the flow breaks an exclusive constraint,
+//! it's sent again without one side of it, then without the other.
+//!
+//! Assignments are drawn by picking an option for every outline independently, and drawing again if a role was picked
+//! too many times or a constraint wasn't met. So among valid assignments, each is as likely as its options' weights make it.
+//! Lists can be tight enough that this rarely succeeds, so after [`MAX_DRAWS`] the outlines are filled one at a time
+//! in a random order instead, each from the options that still leave the rest of the list possible.
+//! That doesn't quite follow the weights, so it only starts a Markov chain, which walks between valid assignments
+//! for [`BURN_IN_STEPS`] before the first one is used, and [`STEPS_BETWEEN_ASSIGNMENTS`] before each one after.
+//! Every step is as likely to be taken backwards as forwards, once weighted by how likely its ends are,
+//! so the longer the chain walks, the closer each assignment comes to being as likely as its options' weights make it.
+
+use std::collections::{HashMap, VecDeque};
+
+use rand::{distr::{weighted::WeightedIndex, Distribution}, seq::{IndexedRandom, SliceRandom}, Rng};
+use serde::Serialize;
+
+use crate::vec_set::VecSet;
+
+use super::{role::Role, role_list::{RoleAssignment, RoleList, RoleOutlineOptionRoles}, role_list_constraints::RoleListConstraint};
+
+/// How many times assignments are drawn, before outlines are filled one at a time
+const MAX_DRAWS: usize = 1000;
+/// How many steps, for each outline, the Markov chain takes before its first assignment
+const BURN_IN_STEPS: usize = 200;
+/// How many steps, for each outline, the Markov chain takes between assignments
+const STEPS_BETWEEN_ASSIGNMENTS: usize = 20;
+/// How many flows deciding whether a list is possible can send, splitting exclusive constraints, before it gives up
+const MAX_FLOWS: usize = 1000;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "problem", rename_all = "camelCase")]
+pub enum RoleGenerationError {
+    /// The outline is a single role or role set, and none of its roles are enabled
+    NoEnabledRoles { outline: usize },
+    /// The outline has several options, and none of their roles are enabled
+    AllOptionsDisabled { outline: usize },
+    /// Every option of the outline that has an enabled role has a weight of 0
+    ZeroWeights { outline: usize },
+    /// These outlines can only be these roles, which can't fill all of them without going over a maximum count
+    MaximumCountExhausted { outlines: Vec<usize>, roles: Vec<Role> },
+    /// These constraints can't be met by the list. If no single constraint is the problem, this is all of them
+    ConstraintsCantBeMet { constraints: Vec<usize> },
+    /// So many exclusive constraints overlap that deciding whether they can all be met took too long
+    ConstraintsTooComplex,
+}
+
+/// Output is the same order as the role list
+pub fn generate<R: Rng + ?Sized>(
+    role_list: &RoleList,
+    enabled_roles: &VecSet<Role>,
+    constraints: &[RoleListConstraint],
+    rng: &mut R
+) -> Result<Vec<RoleAssignment>, RoleGenerationError> {
+    Ok(Generator::new(role_list, enabled_roles, constraints)?.generate(rng))
+}
+
+/// Generates the same role list many times, only deciding that it's possible once
+pub struct Generator {
+    options: Options,
+    /// A valid role for every outline, found while deciding the list is possible
+    valid: Vec<usize>,
+    /// Once drawing has failed, assignments come from here instead
+    chain: Option<Chain>,
+}
+
+impl Generator {
+    pub fn new(role_list: &RoleList, enabled_roles: &VecSet<Role>, constraints: &[RoleListConstraint]) -> Result<Self, RoleGenerationError> {
+        let options = Options::new(role_list, enabled_roles, constraints)?;
+        options.check_possible()?;
+        options.check_constraints()?;
+
+        let all_rules = vec![true; options.rules.len()];
+        match options.complete(&vec![None; options.outlines.len()], &all_rules) {
+            Completion::Found(valid) => Ok(Self { options, valid, chain: None }),
+            Completion::Impossible => Err(RoleGenerationError::ConstraintsCantBeMet { constraints: (0..constraints.len()).collect() }),
+            Completion::TooComplex => Err(RoleGenerationError::ConstraintsTooComplex),
+        }
+    }
+
+    /// Output is the same order as the role list
+    pub fn generate<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Vec<RoleAssignment> {
+        let outlines = self.options.outlines.len();
+        if let Some(chain) = &mut self.chain {
+            chain.walk(&self.options, STEPS_BETWEEN_ASSIGNMENTS.saturating_mul(outlines), rng);
+            return self.options.assignments(&chain.picks);
+        }
+
+        for _ in 0..MAX_DRAWS {
+            if let Some(assignments) = self.options.draw(rng) {
+                return assignments;
+            }
+        }
+
+        let mut chain = Chain::new(&self.options, self.options.fill_one_at_a_time(&self.valid, rng));
+        chain.walk(&self.options, BURN_IN_STEPS.saturating_mul(outlines), rng);
+        let assignments = self.options.assignments(&chain.picks);
+        self.chain = Some(chain);
+        assignments
+    }
+}
+
+/// The enabled options of every outline. Roles are numbered, so outlines can be matched to them
+struct Options {
+    /// For each outline, each of its options, the index of its role, and its weight
+    outlines: Vec<Vec<(RoleAssignment, usize, f64)>>,
+    /// Picks an option of each outline by weight
+    pickers: Vec<WeightedIndex<f64>>,
+    /// For each outline, the options with each role, and their total weight
+    options_by_role: Vec<HashMap<usize, (Vec<usize>, f64)>>,
+    roles: Vec<Role>,
+    /// How many outlines each role can be picked for
+    capacities: Vec<usize>,
+    /// The same order as the constraints
+    rules: Vec<Rule>,
+    /// For each role, the smallest count rule it's in
+    role_rules: Vec<Option<usize>>,
+}
+
+/// A constraint, with which numbered roles are in its role sets, and its counts worked out for this list
+enum Rule {
+    /// `parent` is the smallest other count rule with all of this one's roles
+    Count { roles: Vec<bool>, min: usize, max: usize, parent: Option<usize> },
+    Exclusive { first: Vec<bool>, second: Vec<bool> },
+}
+
+enum Completion {
+    /// A role for every outline
+    Found(Vec<usize>),
+    Impossible,
+    TooComplex,
+}
+
+impl Options {
+    fn new(role_list: &RoleList, enabled_roles: &VecSet<Role>, constraints: &[RoleListConstraint]) -> Result<Self, RoleGenerationError> {
+        let mut roles = Vec::new();
+        let mut role_indices = HashMap::new();
+        let mut outlines = Vec::new();
+        let mut pickers = Vec::new();
+        let mut options_by_role = Vec::new();
+
+        for (outline, role_outline) in role_list.0.iter().enumerate() {
+            let options: Vec<(RoleAssignment, usize, f64)> = role_outline.get_weighted_role_assignments(enabled_roles)
+                .into_iter()
+                .map(|(assignment, weight)| {
+                    let role = assignment.role();
+                    let index = *role_indices.entry(role).or_insert_with(|| {
+                        roles.push(role);
+                        roles.len().saturating_sub(1)
+                    });
+                    (assignment, index, weight)
+                })
+                .collect();
+
+            let Ok(picker) = WeightedIndex::new(options.iter().map(|(_, _, weight)| *weight)) else {
+                let any_enabled = role_outline.get_all_roles().iter().any(|role| enabled_roles.contains(role));
+                return Err(if any_enabled {
+                    RoleGenerationError::ZeroWeights { outline }
+                } else if role_outline.options.len() > 1 {
+                    RoleGenerationError::AllOptionsDisabled { outline }
+                } else {
+                    RoleGenerationError::NoEnabledRoles { outline }
+                });
+            };
+            let mut by_role: HashMap<usize, (Vec<usize>, f64)> = HashMap::new();
+            for (option, (_, role, weight)) in options.iter().enumerate() {
+                let (role_options, total) = by_role.entry(*role).or_default();
+                role_options.push(option);
+                *total += weight;
+            }
+            pickers.push(picker);
+            options_by_role.push(by_role);
+            outlines.push(options);
+        }
+
+        let capacities = roles.iter()
+            .map(|role| role.maximum_count().map_or(outlines.len(), usize::from))
+            .collect();
+
+        let players = role_list.0.len();
+        let numbered = |set: &RoleOutlineOptionRoles| {
+            let set = set.get_roles();
+            roles.iter().map(|role| set.contains(role)).collect::<Vec<bool>>()
+        };
+        let mut rules: Vec<Rule> = constraints.iter()
+            .map(|constraint| match constraint {
+                RoleListConstraint::Count { roles, min, max } => Rule::Count {
+                    roles: numbered(roles),
+                    min: min.map_or(0, |min| min.resolve(players)),
+                    max: max.map_or(usize::MAX, |max| max.resolve(players)),
+                    parent: None,
+                },
+                RoleListConstraint::Exclusive { first, second } => Rule::Exclusive { first: numbered(first), second: numbered(second) },
+            })
+            .collect();
+
+        // Count rules are ordered from the smallest, and among ones with the same roles, from the last.
+        // So the rules a role is in, in that order, each have the roles of the ones before
+        let count_rules: Vec<(usize, Vec<bool>, usize)> = rules.iter()
+            .enumerate()
+            .filter_map(|(index, rule)| match rule {
+                Rule::Count { roles, .. } => Some((index, roles.clone(), roles.iter().filter(|in_rule| **in_rule).count())),
+                Rule::Exclusive { .. } => None,
+            })
+            .collect();
+        let smallest = |rules: &mut dyn Iterator<Item = &(usize, Vec<bool>, usize)>| rules
+            .min_by_key(|(index, _, size)| (*size, std::cmp::Reverse(*index)))
+            .map(|(index, _, _)| *index);
+
+        for (index, roles, size) in &count_rules {
+            let new_parent = smallest(&mut count_rules.iter().filter(|(other, other_roles, other_size)|
+                other != index &&
+                roles.iter().zip(other_roles).all(|(in_rule, in_other)| !in_rule || *in_other) &&
+                (other_size > size || other < index)
+            ));
+            if let Some(Rule::Count { parent, .. }) = rules.get_mut(*index) {
+                *parent = new_parent;
+            }
+        }
+        let role_rules = (0..roles.len())
+            .map(|role| smallest(&mut count_rules.iter().filter(|(_, roles, _)| contains(roles, role))))
+            .collect();
+
+        Ok(Self { outlines, pickers, options_by_role, roles, capacities, rules, role_rules })
+    }
+
+    fn check_possible(&self) -> Result<(), RoleGenerationError> {
+        let all_outlines: Vec<usize> = (0..self.outlines.len()).collect();
+
+        match unfillable(self, &all_outlines, &self.capacities) {
+            None => Ok(()),
+            Some((mut outlines, roles)) => {
+                outlines.sort();
+                let mut roles: Vec<Role> = roles.into_iter().filter_map(|role| self.roles.get(role).copied()).collect();
+                roles.sort();
+                Err(RoleGenerationError::MaximumCountExhausted { outlines, roles })
+            }
+        }
+    }
+
+    /// Names the constraints that can't be met even on their own
+    fn check_constraints(&self) -> Result<(), RoleGenerationError> {
+        let unfilled = vec![None; self.outlines.len()];
+        let mut constraints = Vec::new();
+
+        for constraint in 0..self.rules.len() {
+            let only_this = (0..self.rules.len()).map(|rule| rule == constraint).collect::<Vec<bool>>();
+            match self.complete(&unfilled, &only_this) {
+                Completion::Found(_) => {},
+                Completion::Impossible => constraints.push(constraint),
+                Completion::TooComplex => return Err(RoleGenerationError::ConstraintsTooComplex),
+            }
+        }
+
+        if constraints.is_empty() {
+            Ok(())
+        } else {
+            Err(RoleGenerationError::ConstraintsCantBeMet { constraints })
+        }
+    }
+
+    /// Finds a role for every outline that isn't `picked` yet, so the list meets the `active` rules
+    fn complete(&self, picked: &[Option<usize>], active: &[bool]) -> Completion {
+        let mut forbidden = vec![false; self.roles.len()];
+
+        // Once one side of an exclusive rule is picked, the other side can't be
+        for (rule, _) in self.rules.iter().zip(active).filter(|(_, active)| **active) {
+            let Rule::Exclusive { first, second } = rule else {continue};
+            let has_first = picked.iter().flatten().any(|role| contains(first, *role));
+            let has_second = picked.iter().flatten().any(|role| contains(second, *role));
+            if has_first && has_second {
+                return Completion::Impossible;
+            }
+            for (role, forbidden) in forbidden.iter_mut().enumerate() {
+                let (in_first, in_second) = (contains(first, role), contains(second, role));
+                if (in_second && (in_first || has_first)) || (in_first && has_second) {
+                    *forbidden = true;
+                }
+            }
+        }
+
+        let mut flows = MAX_FLOWS;
+        self.complete_without(picked, active, forbidden, &mut flows)
+    }
+
+    /// Completes the list without the `forbidden` roles. If the flow breaks an exclusive rule, tries again without each side
+    fn complete_without(&self, picked: &[Option<usize>], active: &[bool], forbidden: Vec<bool>, flows: &mut usize) -> Completion {
+        let Some(remaining) = flows.checked_sub(1) else {return Completion::TooComplex};
+        *flows = remaining;
+
+        let Some(completion) = self.flow(picked, active, &forbidden) else {return Completion::Impossible};
+
+        let broken = self.rules.iter().zip(active).find_map(|(rule, active)| match rule {
+            Rule::Exclusive { first, second } if
+                *active &&
+                completion.iter().any(|role| contains(first, *role)) &&
+                completion.iter().any(|role| contains(second, *role))
+                => Some([first, second]),
+            _ => None
+        });
+        let Some(sides) = broken else {return Completion::Found(completion)};
+
+        let mut too_complex = false;
+        for side in sides {
+            let forbidden = forbidden.iter().zip(side).map(|(forbidden, in_side)| *forbidden || *in_side).collect();
+            match self.complete_without(picked, active, forbidden, flows) {
+                Completion::Found(completion) => return Completion::Found(completion),
+                Completion::Impossible => {},
+                Completion::TooComplex => too_complex = true,
+            }
+        }
+        if too_complex {Completion::TooComplex} else {Completion::Impossible}
+    }
+
+    /// Sends a flow from the outlines that aren't `picked`, through the roles they could be that aren't `forbidden`,
+    /// through the `active` count rules. Returns a role for every outline, if the flow gets every outline through
+    fn flow(&self, picked: &[Option<usize>], active: &[bool], forbidden: &[bool]) -> Option<Vec<usize>> {
+        let (source, sink) = (0, 1);
+        let outline_node = |outline: usize| outline.saturating_add(2);
+        let role_node = |role: usize| role.saturating_add(2).saturating_add(self.outlines.len());
+        let rule_node = |rule: usize| rule.saturating_add(2).saturating_add(self.outlines.len()).saturating_add(self.roles.len());
+        let mut network = Network::new(rule_node(self.rules.len()));
+
+        let mut picked_counts = vec![0usize; self.roles.len()];
+        for role in picked.iter().flatten() {
+            let count = picked_counts.get_mut(*role)?;
+            *count = count.saturating_add(1);
+        }
+
+        let unfilled: Vec<usize> = (0..self.outlines.len()).filter(|outline| picked.get(*outline).is_some_and(Option::is_none)).collect();
+        let mut option_edges = Vec::new();
+        for outline in &unfilled {
+            network.add_edge(source, outline_node(*outline), 0, 1)?;
+            let mut roles: Vec<usize> = self.outlines.get(*outline)?.iter()
+                .map(|(_, role, _)| *role)
+                .filter(|role| !contains(forbidden, *role))
+                .collect();
+            roles.sort_unstable();
+            roles.dedup();
+            for role in roles {
+                let edge = network.add_edge(outline_node(*outline), role_node(role), 0, 1)?;
+                option_edges.push((*outline, role, edge));
+            }
+        }
+
+        // Rules that aren't active are passed straight through
+        let next_rule = |mut rule: Option<usize>| {
+            while let Some(index) = rule {
+                if active.get(index).copied().unwrap_or_default() {break}
+                rule = match self.rules.get(index) {
+                    Some(Rule::Count { parent, .. }) => *parent,
+                    _ => None,
+                };
+            }
+            rule.map_or(sink, rule_node)
+        };
+        for (role, (capacity, picked_count)) in self.capacities.iter().zip(&picked_counts).enumerate() {
+            let to = next_rule(self.role_rules.get(role).copied().flatten());
+            network.add_edge(role_node(role), to, 0, capacity.checked_sub(*picked_count)?)?;
+        }
+        for (index, rule) in self.rules.iter().enumerate() {
+            let Rule::Count { roles, min, max, parent } = rule else {continue};
+            if !active.get(index).copied().unwrap_or_default() {continue}
+            let count = picked.iter().flatten().filter(|role| contains(roles, **role)).count();
+            network.add_edge(rule_node(index), next_rule(*parent), min.saturating_sub(count), max.checked_sub(count)?)?;
+        }
+        network.add_edge(sink, source, unfilled.len(), unfilled.len())?;
+
+        if !network.feasible() {
+            return None;
+        }
+
+        let mut completion = picked.to_vec();
+        for (outline, role, edge) in option_edges {
+            if network.flow(edge) > 0 {
+                *completion.get_mut(outline)? = Some(role);
+            }
+        }
+        completion.into_iter().collect()
+    }
+
+    /// Whether `roles`, one for every outline, meet every rule
+    fn meets_rules(&self, roles: &[usize]) -> bool {
+        self.rules.iter().all(|rule| match rule {
+            Rule::Count { roles: rule_roles, min, max, .. } => {
+                let count = roles.iter().filter(|role| contains(rule_roles, **role)).count();
+                (*min..=*max).contains(&count)
+            },
+            Rule::Exclusive { first, second } => {
+                !roles.iter().any(|role| contains(first, *role)) ||
+                !roles.iter().any(|role| contains(second, *role))
+            }
+        })
+    }
+
+    /// Picks an option for every outline independently.
+    /// Returns `None` if a role was picked too many times, or a constraint wasn't met
+    fn draw<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<Vec<RoleAssignment>> {
+        let mut picked = vec![0usize; self.roles.len()];
+        let mut roles = Vec::with_capacity(self.outlines.len());
+        let mut assignments = Vec::with_capacity(self.outlines.len());
+
+        for (options, picker) in self.outlines.iter().zip(&self.pickers) {
+            let (assignment, role, _) = options.get(picker.sample(rng))?;
+            let count = picked.get_mut(*role)?;
+            *count = count.saturating_add(1);
+            if *count > self.capacities.get(*role).copied().unwrap_or_default() {
+                return None;
+            }
+            roles.push(*role);
+            assignments.push(assignment.clone());
+        }
+
+        self.meets_rules(&roles).then_some(assignments)
+    }
+
+    /// Each pick is checked by completing the rest of the list, and that completion is kept,
+    /// so an outline can always fall back on its role in the last one. `valid` is the first one.
+    /// Returns the option picked for each outline
+    fn fill_one_at_a_time<R: Rng + ?Sized>(&self, valid: &[usize], rng: &mut R) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.outlines.len()).collect();
+        order.shuffle(rng);
+
+        let all_rules = vec![true; self.rules.len()];
+        let mut completion = valid.to_vec();
+        let mut picked: Vec<Option<usize>> = vec![None; self.outlines.len()];
+        let mut picked_options: Vec<Option<usize>> = vec![None; self.outlines.len()];
+
+        for outline in order {
+            let options = self.outlines.get(outline).map(Vec::as_slice).unwrap_or_default();
+            let fallback = completion.get(outline).copied();
+            let mut untried: Vec<usize> = (0..options.len()).collect();
+
+            // Options are tried in a random order, the more likely ones usually first
+            let option = loop {
+                let Ok(index) = WeightedIndex::new(untried.iter().map(|option| options.get(*option).map_or(0.0, |(_, _, weight)| *weight))) else {
+                    break options.iter().position(|(_, role, _)| Some(*role) == fallback);
+                };
+                let option = untried.swap_remove(index.sample(rng));
+                let Some((_, role, _)) = options.get(option) else {continue};
+                if Some(*role) == fallback {
+                    break Some(option);
+                }
+
+                if let Some(slot) = picked.get_mut(outline) {
+                    *slot = Some(*role);
+                }
+                if let Completion::Found(new_completion) = self.complete(&picked, &all_rules) {
+                    completion = new_completion;
+                    break Some(option);
+                }
+            };
+
+            let Some(option) = option else {continue};
+            let Some((_, role, _)) = options.get(option) else {continue};
+            if let Some(slot) = picked.get_mut(outline) {
+                *slot = Some(*role);
+            }
+            if let Some(slot) = picked_options.get_mut(outline) {
+                *slot = Some(option);
+            }
+        }
+
+        picked_options.into_iter().flatten().collect()
+    }
+
+    fn role(&self, outline: usize, option: usize) -> Option<usize> {
+        self.outlines.get(outline)?.get(option).map(|(_, role, _)| *role)
+    }
+
+    fn weight(&self, outline: usize, option: usize) -> f64 {
+        self.outlines.get(outline).and_then(|options| options.get(option)).map_or(0.0, |(_, _, weight)| *weight)
+    }
+
+    /// The options of `outline` with `role`, and their total weight
+    fn with_role(&self, outline: usize, role: usize) -> Option<&(Vec<usize>, f64)> {
+        self.options_by_role.get(outline)?.get(&role)
+    }
+
+    fn assignments(&self, picks: &[usize]) -> Vec<RoleAssignment> {
+        self.outlines.iter()
+            .zip(picks)
+            .filter_map(|(options, option)| options.get(*option).map(|(assignment, _, _)| assignment.clone()))
+            .collect()
+    }
+}
+
+impl Rule {
+    /// The roles counted by this rule. Count rules only have one side
+    fn sides(&self) -> (&[bool], &[bool]) {
+        match self {
+            Rule::Count { roles, .. } => (roles, &[]),
+            Rule::Exclusive { first, second } => (first, second),
+        }
+    }
+}
+
+/// A Markov chain over valid assignments
+struct Chain {
+    /// The option picked for each outline
+    picks: Vec<usize>,
+    /// How many outlines each role is picked for
+    role_counts: Vec<usize>,
+    /// For each rule, how many outlines are picked with roles on each of its sides
+    rule_counts: Vec<(usize, usize)>,
+}
+
+impl Chain {
+    /// `picks` has to be valid
+    fn new(options: &Options, picks: Vec<usize>) -> Self {
+        let roles: Vec<usize> = picks.iter().enumerate().filter_map(|(outline, option)| options.role(outline, *option)).collect();
+        let mut chain = Self { picks, role_counts: vec![0; options.roles.len()], rule_counts: vec![(0, 0); options.rules.len()] };
+        chain.apply(options, &[], &roles);
+        chain
+    }
+
+    /// Takes `steps` steps, each re-picking an outline, swapping two outlines' roles, or re-picking two outlines.
+    /// Re-picks are drawn by weight and taken if the list is still valid. Swaps never change which roles are in the list,
+    /// so they're taken as often as the weight of the roles swapped to, over the weight of the roles swapped from
+    fn walk<R: Rng + ?Sized>(&mut self, options: &Options, steps: usize, rng: &mut R) {
+        let outlines = self.picks.len();
+        if outlines == 0 {return}
+
+        for _ in 0..steps {
+            let first = rng.random_range(0..outlines);
+            let second = (outlines > 1).then(|| {
+                let second = rng.random_range(0..outlines.saturating_sub(1));
+                if second >= first {second.saturating_add(1)} else {second}
+            });
+            match (rng.random_range(0..3u8), second) {
+                (0, _) | (_, None) => self.repick(options, &[first], rng),
+                (1, Some(second)) => self.swap(options, first, second, rng),
+                (_, Some(second)) => self.repick(options, &[first, second], rng),
+            }
+        }
+    }
+
+    fn role(&self, options: &Options, outline: usize) -> Option<usize> {
+        options.role(outline, *self.picks.get(outline)?)
+    }
+
+    fn repick<R: Rng + ?Sized>(&mut self, options: &Options, outlines: &[usize], rng: &mut R) {
+        let mut new_picks = Vec::with_capacity(outlines.len());
+        let mut removed = Vec::with_capacity(outlines.len());
+        let mut added = Vec::with_capacity(outlines.len());
+        for outline in outlines {
+            let Some(picker) = options.pickers.get(*outline) else {return};
+            let option = picker.sample(rng);
+            let (Some(old_role), Some(new_role)) = (self.role(options, *outline), options.role(*outline, option)) else {return};
+            new_picks.push((*outline, option));
+            removed.push(old_role);
+            added.push(new_role);
+        }
+
+        if !self.allows(options, &removed, &added) {return}
+        self.apply(options, &removed, &added);
+        for (outline, option) in new_picks {
+            if let Some(pick) = self.picks.get_mut(outline) {
+                *pick = option;
+            }
+        }
+    }
+
+    fn swap<R: Rng + ?Sized>(&mut self, options: &Options, first: usize, second: usize, rng: &mut R) {
+        let (Some(first_role), Some(second_role)) = (self.role(options, first), self.role(options, second)) else {return};
+        if first_role == second_role {return}
+        let (Some((first_options, first_to)), Some((second_options, second_to))) =
+            (options.with_role(first, second_role), options.with_role(second, first_role)) else {return};
+        let (Some((_, first_from)), Some((_, second_from))) =
+            (options.with_role(first, first_role), options.with_role(second, second_role)) else {return};
+
+        if rng.random::<f64>() >= (first_to * second_to) / (first_from * second_from) {return}
+
+        let (Ok(first_option), Ok(second_option)) = (
+            first_options.choose_weighted(rng, |option| options.weight(first, *option)),
+            second_options.choose_weighted(rng, |option| options.weight(second, *option)),
+        ) else {return};
+        if let Some(pick) = self.picks.get_mut(first) {
+            *pick = *first_option;
+        }
+        if let Some(pick) = self.picks.get_mut(second) {
+            *pick = *second_option;
+        }
+    }
+
+    /// Whether the list is still valid with the `removed` roles swapped for the `added` ones
+    fn allows(&self, options: &Options, removed: &[usize], added: &[usize]) -> bool {
+        added.iter().all(|role| {
+            let count = self.role_counts.get(*role).copied().unwrap_or_default();
+            changed(count, removed, added, |other| other == *role) <= options.capacities.get(*role).copied().unwrap_or_default()
+        }) &&
+        options.rules.iter().zip(&self.rule_counts).all(|(rule, (first_count, second_count))| {
+            let (first, second) = rule.sides();
+            let first_count = changed(*first_count, removed, added, |role| contains(first, role));
+            let second_count = changed(*second_count, removed, added, |role| contains(second, role));
+            match rule {
+                Rule::Count { min, max, .. } => (*min..=*max).contains(&first_count),
+                Rule::Exclusive { .. } => first_count == 0 || second_count == 0,
+            }
+        })
+    }
+
+    fn apply(&mut self, options: &Options, removed: &[usize], added: &[usize]) {
+        for role in removed {
+            if let Some(count) = self.role_counts.get_mut(*role) {
+                *count = count.saturating_sub(1);
+            }
+        }
+        for role in added {
+            if let Some(count) = self.role_counts.get_mut(*role) {
+                *count = count.saturating_add(1);
+            }
+        }
+        for (rule, (first_count, second_count)) in options.rules.iter().zip(&mut self.rule_counts) {
+            let (first, second) = rule.sides();
+            *first_count = changed(*first_count, removed, added, |role| contains(first, role));
+            *second_count = changed(*second_count, removed, added, |role| contains(second, role));
+        }
+    }
+}
+
+/// `count`, with the `removed` roles in a set taken off, and the `added` ones put on
+fn changed(count: usize, removed: &[usize], added: &[usize], in_set: impl Fn(usize) -> bool) -> usize {
+    count
+        .saturating_add(added.iter().filter(|role| in_set(**role)).count())
+        .saturating_sub(removed.iter().filter(|role| in_set(**role)).count())
+}
+
+fn contains(roles: &[bool], role: usize) -> bool {
+    roles.get(role).copied().unwrap_or_default()
+}
+
+/// A flow network where edges can have a minimum flow as well as a maximum
+struct Network {
+    /// Each edge's end, and how much more can flow along it. Each edge is followed by its reverse
+    edges: Vec<(usize, usize)>,
+    /// The edges leaving each node
+    from_node: Vec<Vec<usize>>,
+    /// How much each node has to take in and send out, because of edges' minimums
+    minimum_in: Vec<usize>,
+    minimum_out: Vec<usize>,
+}
+
+impl Network {
+    fn new(nodes: usize) -> Self {
+        Self {
+            edges: Vec::new(),
+            from_node: vec![Vec::new(); nodes],
+            minimum_in: vec![0; nodes],
+            minimum_out: vec![0; nodes],
+        }
+    }
+
+    /// Returns `None` if `min` is more than `max`
+    fn add_edge(&mut self, from: usize, to: usize, min: usize, max: usize) -> Option<usize> {
+        let edge = self.edges.len();
+        self.edges.push((to, max.checked_sub(min)?));
+        self.edges.push((from, 0));
+        self.from_node.get_mut(from)?.push(edge);
+        self.from_node.get_mut(to)?.push(edge.saturating_add(1));
+
+        let minimum_in = self.minimum_in.get_mut(to)?;
+        *minimum_in = minimum_in.saturating_add(min);
+        let minimum_out = self.minimum_out.get_mut(from)?;
+        *minimum_out = minimum_out.saturating_add(min);
+        Some(edge)
+    }
+
+    /// How much flows along an edge above its minimum
+    fn flow(&self, edge: usize) -> usize {
+        self.edges.get(edge ^ 1).map_or(0, |(_, capacity)| *capacity)
+    }
+
+    /// Whether every edge's minimum can be met at once. Every node has to send out as much as it takes in
+    fn feasible(&mut self) -> bool {
+        let nodes = self.from_node.len();
+        let (source, sink) = (nodes, nodes.saturating_add(1));
+        self.from_node.push(Vec::new());
+        self.from_node.push(Vec::new());
+
+        // Each node is given the minimums it takes in, and has to get rid of the minimums it sends out
+        let mut needed = 0usize;
+        for node in 0..nodes {
+            let minimum_in = self.minimum_in.get(node).copied().unwrap_or_default();
+            let minimum_out = self.minimum_out.get(node).copied().unwrap_or_default();
+            if let Some(extra) = minimum_in.checked_sub(minimum_out).filter(|extra| *extra > 0) {
+                self.add_edge(source, node, 0, extra);
+                needed = needed.saturating_add(extra);
+            } else if let Some(missing) = minimum_out.checked_sub(minimum_in).filter(|missing| *missing > 0) {
+                self.add_edge(node, sink, 0, missing);
+            }
+        }
+
+        self.max_flow(source, sink) == needed
+    }
+
+    fn max_flow(&mut self, source: usize, sink: usize) -> usize {
+        let mut total = 0usize;
+        loop {
+            // The edge each node was reached by
+            let mut reached_by: Vec<Option<usize>> = vec![None; self.from_node.len()];
+            let mut queue = VecDeque::from([source]);
+            while let Some(node) = queue.pop_front() {
+                if node == sink {break}
+                for edge in self.from_node.get(node).map(Vec::as_slice).unwrap_or_default() {
+                    let Some((to, capacity)) = self.edges.get(*edge).copied() else {continue};
+                    if capacity == 0 || to == source {continue}
+                    let Some(slot) = reached_by.get_mut(to) else {continue};
+                    if slot.is_some() {continue}
+                    *slot = Some(*edge);
+                    queue.push_back(to);
+                }
+            }
+
+            let mut path = Vec::new();
+            let mut node = sink;
+            while let Some(edge) = reached_by.get(node).copied().flatten() {
+                path.push(edge);
+                node = self.edges.get(edge ^ 1).map_or(source, |(from, _)| *from);
+                if node == source {break}
+            }
+            if node != source || path.is_empty() {
+                return total;
+            }
+
+            let added = path.iter().filter_map(|edge| self.edges.get(*edge)).map(|(_, capacity)| *capacity).min().unwrap_or_default();
+            for edge in path {
+                if let Some((_, capacity)) = self.edges.get_mut(edge) {
+                    *capacity = capacity.saturating_sub(added);
+                }
+                if let Some((_, capacity)) = self.edges.get_mut(edge ^ 1) {
+                    *capacity = capacity.saturating_add(added);
+                }
+            }
+            total = total.saturating_add(added);
+        }
+    }
+}
+
+/// Gives each of `outlines` a role, without giving any role to more outlines than its capacity.
+/// If that can't be done, returns outlines that can't all be given a role, and the only roles they could be given
+fn unfillable(options: &Options, outlines: &[usize], capacities: &[usize]) -> Option<(Vec<usize>, Vec<usize>)> {
+    let mut matching = Matching { options, capacities, given_to: vec![Vec::new(); options.roles.len()] };
+
+    for outline in outlines {
+        let mut visited = vec![false; options.roles.len()];
+        if !matching.give_role(*outline, &mut visited) {
+            // Every role this outline could be given is full, and so is every role the outlines given those could move to.
+            // So those outlines are one more than those roles can take
+            let roles: Vec<usize> = (0..visited.len()).filter(|role| visited.get(*role).copied().unwrap_or_default()).collect();
+            let outlines = std::iter::once(*outline)
+                .chain(roles.iter().flat_map(|role| matching.given_to.get(*role).cloned().unwrap_or_default()))
+                .collect();
+            return Some((outlines, roles));
+        }
+    }
+    None
+}
+
+struct Matching<'a> {
+    options: &'a Options,
+    capacities: &'a [usize],
+    /// The outlines each role is given to
+    given_to: Vec<Vec<usize>>,
+}
+
+impl Matching<'_> {
+    /// Gives `outline` a role, moving outlines that already have one to another role if that makes room
+    fn give_role(&mut self, outline: usize, visited: &mut [bool]) -> bool {
+        let roles: Vec<usize> = self.options.outlines.get(outline)
+            .map(|options| options.iter().map(|(_, role, _)| *role).collect())
+            .unwrap_or_default();
+
+        for role in roles {
+            let Some(seen) = visited.get_mut(role) else {continue};
+            if *seen {continue}
+            *seen = true;
+
+            let capacity = self.capacities.get(role).copied().unwrap_or_default();
+            let given_to = self.given_to.get(role).cloned().unwrap_or_default();
+            if given_to.len() < capacity {
+                if let Some(given_to) = self.given_to.get_mut(role) {
+                    given_to.push(outline);
+                }
+                return true;
+            }
+            for (index, other) in given_to.into_iter().enumerate() {
+                if self.give_role(other, visited) {
+                    if let Some(slot) = self.given_to.get_mut(role).and_then(|given_to| given_to.get_mut(index)) {
+                        *slot = outline;
+                    }
+                    return true;
+                }
+            }
+        }
+        false
+    }
+}
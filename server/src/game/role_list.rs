@@ -1,4 +1,4 @@
-use rand::seq::IndexedRandom;
+use rand::{seq::IndexedRandom, Rng};
 use serde::{Deserialize, Serialize};
 use vec1::{
     vec1,
@@ -7,24 +7,17 @@ use vec1::{
 
 use crate::vec_set::{vec_set, VecSet};
 
-use super::{components::{insider_group::InsiderGroupID, win_condition::WinCondition}, game_conclusion::GameConclusion, role::Role};
+use super::{
+    components::{insider_group::InsiderGroupID, win_condition::WinCondition}, game_conclusion::GameConclusion, role::Role,
+    role_generation::{self, RoleGenerationError}, role_list_constraints::RoleListConstraint
+};
 
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct RoleList(pub Vec<RoleOutline>);
 impl RoleList {
-    /// Output is the same order as the rolelist
-    pub fn create_random_role_assignments(&self, enabled_roles: &VecSet<Role>) -> Option<Vec<RoleAssignment>> {
-        let mut generated_data = Vec::<RoleAssignment>::new();
-        for entry in self.0.iter(){
-            if let Some(player_initialization_data) = entry.get_random_role_assignments(
-                enabled_roles, &generated_data.iter().map(|datum| datum.role).collect::<Vec<Role>>()
-            ){
-                generated_data.push(player_initialization_data);
-            }else{
-                return None;
-            }
-        }
-        Some(generated_data)
+    /// Output is the same order as the rolelist. See [`role_generation`]
+    pub fn create_random_role_assignments<R: Rng + ?Sized>(&self, enabled_roles: &VecSet<Role>, constraints: &[RoleListConstraint], rng: &mut R) -> Result<Vec<RoleAssignment>, RoleGenerationError> {
+        role_generation::generate(self, enabled_roles, constraints, rng)
     }
     pub fn simplify(&mut self){
         for entry in self.0.iter_mut(){
@@ -63,6 +56,11 @@ impl RoleAssignment{
                 WinCondition::GameConclusionReached { win_if_any: win_if_any.clone() },
         }
     }
+    /// Same as [`PlayerReference::keeps_game_running`](super::player::PlayerReference::keeps_game_running),
+    /// for the player given this assignment when the game starts
+    pub fn keeps_game_running(&self)->bool{
+        GameConclusion::player_keeps_game_running(self.role, &self.insider_groups(), &self.win_condition())
+    }
 }
 
 
@@ -93,7 +91,8 @@ impl Default for RoleOutline {
         Self {options: vec1![RoleOutlineOption{
             win_condition: Default::default(),
             insider_groups: Default::default(),
-            roles: RoleOutlineOptionRoles::RoleSet { role_set: RoleSet::Any }
+            roles: RoleOutlineOptionRoles::RoleSet { role_set: RoleSet::Any },
+            weight: None
         }]}
     }
 }
@@ -102,7 +101,8 @@ impl RoleOutline{
         RoleOutline{options: vec1![RoleOutlineOption{
             win_condition: Default::default(),
             insider_groups: Default::default(),
-            roles: RoleOutlineOptionRoles::Role{role}
+            roles: RoleOutlineOptionRoles::Role{role},
+            weight: None
         }]}
     }
     pub fn get_role_assignments(&self) -> Vec<RoleAssignment> {
@@ -116,12 +116,35 @@ impl RoleOutline{
                     })
             ).collect()
     }
-    pub fn get_random_role_assignments(&self, enabled_roles: &VecSet<Role>, taken_roles: &[Role]) -> Option<RoleAssignment> {
-        let options = self.get_role_assignments()
+    /// Every enabled role this outline can be, with how likely it is compared to the others.
+    /// An option with a weight shares it evenly between its enabled roles, and an option without one counts each of its roles once,
+    /// as if it had a weight of however many enabled roles it has.
+    /// So an outline without weights picks evenly between all its roles, and one where every option has a weight picks options by their weights.
+    /// Mixing the two compares weights to numbers of roles: `[townKilling, jester with weight 1]` is Jester once for every town killing role.
+    /// Roles with no chance of being picked are left out
+    pub fn get_weighted_role_assignments(&self, enabled_roles: &VecSet<Role>) -> Vec<(RoleAssignment, f64)> {
+        self.options.iter()
+            .flat_map(|option| {
+                let roles: Vec<Role> = option.roles.get_roles().into_iter().filter(|role| enabled_roles.contains(role)).collect();
+                let role_weight = match option.weight {
+                    Some(weight) => f64::from(weight) / f64::from(u16::try_from(roles.len()).unwrap_or(u16::MAX)),
+                    None => 1.0,
+                };
+                let roles = if role_weight > 0.0 {roles} else {Vec::new()};
+                roles.into_iter()
+                    .map(move |role| (RoleAssignment{
+                        role,
+                        insider_groups: option.insider_groups.clone(),
+                        win_condition: option.win_condition.clone()
+                    }, role_weight))
+            }).collect()
+    }
+    pub fn get_random_role_assignments<R: Rng + ?Sized>(&self, enabled_roles: &VecSet<Role>, taken_roles: &[Role], rng: &mut R) -> Option<RoleAssignment> {
+        let options = self.get_weighted_role_assignments(enabled_roles)
             .into_iter()
-            .filter(|r|role_can_generate(r.role, enabled_roles, taken_roles))
+            .filter(|(r, _)|role_can_generate(r.role, enabled_roles, taken_roles))
             .collect::<Vec<_>>();
-        options.choose(&mut rand::rng()).cloned()
+        options.choose_weighted(rng, |(_, weight)| *weight).ok().map(|(assignment, _)| assignment.clone())
     }
     pub fn get_all_roles(&self) -> Vec<Role>{
         self.options.iter()
@@ -129,6 +152,9 @@ impl RoleOutline{
             .collect()
     }
     pub fn simplify(&mut self){
+        // Merging options would change how likely weighted ones are
+        if self.options.iter().any(|option| option.weight.is_some()) {return}
+
         let mut new_options = self.options.to_vec();
 
         new_options = new_options.into_iter().collect::<VecSet<_>>().into_iter().collect();
@@ -188,6 +214,10 @@ pub struct RoleOutlineOption {
     pub win_condition: RoleOutlineOptionWinCondition,
     #[serde(flatten, skip_serializing_if = "RoleOutlineOptionInsiderGroups::is_default")]
     pub insider_groups: RoleOutlineOptionInsiderGroups,
+    /// How likely this option is compared to the outline's others, see [`RoleOutline::get_weighted_role_assignments`].
+    /// Anything other than a number from 0 to 65535 fails to parse, rather than being dropped
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub weight: Option<u16>,
 }
 
 /// Watch this!
@@ -215,6 +245,11 @@ impl<'de> Deserialize<'de> for RoleOutlineOption {
                     }
                 }
             }
+            if let Some(value) = map.get("weight") {
+                let weight = serde_json::from_value(value.clone())
+                    .map_err(|_| serde::de::Error::custom(format!("weight must be a number from 0 to {}, not {value}", u16::MAX)))?;
+                option.weight = Some(weight);
+            }
             if let Some(value) = map.get("roleSet") {
                 if let Ok(string_role_set) = serde_json::to_string(value) {
                     if let Ok(role_set) = serde_json::from_str(string_role_set.as_str()) {
@@ -297,6 +332,28 @@ pub enum RoleSet {
     Minions
 }
 impl RoleSet{
+    pub fn all()->Vec<RoleSet>{
+        vec![
+            RoleSet::Any,
+
+            RoleSet::Town,
+            RoleSet::TownCommon,
+            RoleSet::TownInvestigative,
+            RoleSet::TownProtective,
+            RoleSet::TownKilling,
+            RoleSet::TownSupport,
+
+            RoleSet::Mafia,
+            RoleSet::MafiaSupport,
+            RoleSet::MafiaKilling,
+
+            RoleSet::Cult,
+            RoleSet::Fiends,
+
+            RoleSet::Neutral,
+            RoleSet::Minions
+        ]
+    }
     pub fn get_roles(&self) -> VecSet<Role> {
         match self {
             RoleSet::Any => Role::values(),
@@ -395,4 +452,4 @@ pub fn role_can_generate(role: Role, enabled_roles: &VecSet<Role>, taken_roles:
         Some(max) => taken_roles.iter().filter(|r|**r==role).count() < max.into(),
         None => true,
     }
-}
\ No newline at end of file
+}
\ No newline at end of file
@@ -0,0 +1,137 @@
+//! Estimates what a role list generates, for the lobby's balance preview.
+//!
+//! The list is generated [`SAMPLES`] times, the same way a game generates it,
+//! and every chance is how often something happened among those. So they're only accurate to a percent or two.
+
+use std::collections::BTreeMap;
+
+use rand::Rng;
+use serde::Serialize;
+
+use crate::vec_set::VecSet;
+
+use super::{
+    components::win_condition::WinCondition, game_conclusion::GameConclusion, role::Role,
+    role_generation::{Generator, RoleGenerationError}, role_list::{RoleAssignment, RoleList, RoleSet},
+    role_list_constraints::RoleListConstraint
+};
+
+/// How many times the list is generated for an analysis
+pub const SAMPLES: u32 = 1000;
+
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RoleListAnalysis {
+    /// Why the list can't be generated. If it can't, there's nothing else in the analysis
+    pub problem: Option<RoleGenerationError>,
+    /// For each role set, the chance of the list having each number of its roles, starting from none.
+    /// The role sets `town`, `mafia`, `cult`, `fiends`, `neutral` and `minions` are the factions
+    pub role_set_counts: BTreeMap<RoleSet, Vec<f64>>,
+    /// The chance of each role being in the list at least once. Roles that never were aren't included
+    pub roles: BTreeMap<Role, f64>,
+    /// The chance a generated list ends the game as soon as it starts.
+    /// Starting a game tries many lists, and only fails if every one of them would
+    pub ends_instantly: f64,
+}
+
+/// The last analysis of a lobby's settings. Analyzing generates the list many times,
+/// so it's only done again once the role list, enabled roles or constraints change
+#[derive(Default)]
+pub struct CachedAnalysis(Option<Box<AnalyzedSettings>>);
+
+struct AnalyzedSettings {
+    role_list: RoleList,
+    enabled_roles: VecSet<Role>,
+    constraints: Vec<RoleListConstraint>,
+    analysis: RoleListAnalysis,
+}
+
+impl CachedAnalysis {
+    pub fn get(&mut self, role_list: &RoleList, enabled_roles: &VecSet<Role>, constraints: &[RoleListConstraint]) -> RoleListAnalysis {
+        if let Some(cached) = &self.0 {
+            if cached.role_list == *role_list && cached.enabled_roles == *enabled_roles && cached.constraints == constraints {
+                return cached.analysis.clone();
+            }
+        }
+
+        let analysis = analyze(role_list, enabled_roles, constraints, &mut rand::rng());
+        self.0 = Some(Box::new(AnalyzedSettings {
+            role_list: role_list.clone(),
+            enabled_roles: enabled_roles.clone(),
+            constraints: constraints.to_vec(),
+            analysis: analysis.clone(),
+        }));
+        analysis
+    }
+}
+
+pub fn analyze<R: Rng + ?Sized>(
+    role_list: &RoleList,
+    enabled_roles: &VecSet<Role>,
+    constraints: &[RoleListConstraint],
+    rng: &mut R
+) -> RoleListAnalysis {
+    let role_sets: Vec<(RoleSet, VecSet<Role>)> = RoleSet::all().into_iter()
+        .map(|role_set| {
+            let roles = role_set.get_roles();
+            (role_set, roles)
+        })
+        .collect();
+
+    let mut generator = match Generator::new(role_list, enabled_roles, constraints) {
+        Ok(generator) => generator,
+        Err(problem) => return RoleListAnalysis { problem: Some(problem), ..Default::default() },
+    };
+
+    let mut role_set_counts: BTreeMap<RoleSet, Vec<u32>> = BTreeMap::new();
+    let mut roles: BTreeMap<Role, u32> = BTreeMap::new();
+    let mut ends_instantly = 0u32;
+    // Working out whether a player keeps the game running builds their role's default state, which is slow,
+    // so it's only done once for each assignment
+    let mut players_by_assignment: Vec<(RoleAssignment, (Role, bool, WinCondition))> = Vec::new();
+
+    for _ in 0..SAMPLES {
+        let assignments = generator.generate(rng);
+
+        for (role_set, set_roles) in &role_sets {
+            let count = assignments.iter().filter(|assignment| set_roles.contains(&assignment.role())).count();
+            let counts = role_set_counts.entry(role_set.clone()).or_default();
+            if counts.len() <= count {
+                counts.resize(count.saturating_add(1), 0);
+            }
+            if let Some(times) = counts.get_mut(count) {
+                *times = times.saturating_add(1);
+            }
+        }
+
+        let present: VecSet<Role> = assignments.iter().map(|assignment| assignment.role()).collect();
+        for role in present {
+            let times = roles.entry(role).or_default();
+            *times = times.saturating_add(1);
+        }
+
+        let players: Vec<_> = assignments.iter()
+            .map(|assignment| {
+                if let Some((_, player)) = players_by_assignment.iter().find(|(known, _)| known == assignment) {
+                    return player.clone();
+                }
+                let player = (assignment.role(), assignment.keeps_game_running(), assignment.win_condition());
+                players_by_assignment.push((assignment.clone(), player.clone()));
+                player
+            })
+            .collect();
+        if GameConclusion::reached_by(&players).is_some() {
+            ends_instantly = ends_instantly.saturating_add(1);
+        }
+    }
+
+    let chance = |times: u32| f64::from(times) / f64::from(SAMPLES);
+    RoleListAnalysis {
+        problem: None,
+        role_set_counts: role_set_counts.into_iter()
+            .map(|(role_set, counts)| (role_set, counts.into_iter().map(chance).collect()))
+            .collect(),
+        roles: roles.into_iter().map(|(role, times)| (role, chance(times))).collect(),
+        ends_instantly: chance(ends_instantly),
+    }
+}
@@ -0,0 +1,45 @@
+//! Rules a host can put on the whole generated role list, on top of its outlines and each role's maximum count.
+//! They're enforced by [`role_generation`](super::role_generation), and numbered by their index in
+//! [`Settings::role_list_constraints`](super::settings::Settings::role_list_constraints), starting from 0.
+
+use serde::{Deserialize, Serialize};
+
+use super::role_list::RoleOutlineOptionRoles;
+
+/// How many constraints a lobby can have
+pub const MAX_ROLE_LIST_CONSTRAINTS: usize = 32;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum RoleListConstraint {
+    /// The list has at least `min` and at most `max` roles from `roles`
+    Count {
+        roles: RoleOutlineOptionRoles,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min: Option<ConstraintCount>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max: Option<ConstraintCount>,
+    },
+    /// The list never has roles from both `first` and `second`
+    Exclusive {
+        first: RoleOutlineOptionRoles,
+        second: RoleOutlineOptionRoles,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConstraintCount {
+    Fixed(u8),
+    /// One for every this many players, rounded down. 0 counts as none
+    PerPlayers(u8),
+}
+
+impl ConstraintCount {
+    pub fn resolve(self, players: usize) -> usize {
+        match self {
+            ConstraintCount::Fixed(count) => count.into(),
+            ConstraintCount::PerPlayers(players_each) => players.checked_div(players_each.into()).unwrap_or_default(),
+        }
+    }
+}
@@ -4,7 +4,7 @@ use serde::{Serialize, Deserialize};
 
 use crate::vec_set::VecSet;
 
-use super::{modifiers::ModifierType, phase::PhaseType, role::Role, role_list::RoleList};
+use super::{modifiers::ModifierType, phase::PhaseType, role::Role, role_list::RoleList, role_list_constraints::RoleListConstraint};
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -13,6 +13,8 @@ pub struct Settings{
     pub phase_times: PhaseTimeSettings,
     pub enabled_roles: VecSet<Role>,
     pub enabled_modifiers: VecSet<ModifierType>,
+    #[serde(default)]
+    pub role_list_constraints: Vec<RoleListConstraint>,
 }
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -18,59 +18,14 @@ pub mod websocket_listener;
 pub mod room;
 pub mod lobby;
 pub mod packet;
+pub mod protocol;
 pub mod client_connection;
+pub mod config;
+pub mod log;
+pub mod metrics;
 pub mod vec_map;
 pub mod vec_set;
 
-pub mod log {
-    #[macro_export]
-    /// Log a statement to the console. 
-    /// When logging using this macro, a timestamp and possibly a marker is added to the message.
-    /// 
-    /// # Examples
-    /// ```
-    /// use mafia_server::log;
-    /// log!(error "Error location"; "Error message");
-    /// log!(error "Game::new"; "Failed to generate role. rolelist wasnt big enough for number of players");
-    /// log!(info "Listener"; "{}: {}", "Received message", "message");
-    /// ```
-    /// 
-    /// # Markers
-    /// - `fatal`: Prints the word FATAL
-    /// - `error`: Prints red and writes "WARN"
-    /// - `important`: 
-    /// - `info`: 
-    /// 
-    /// if none are put then it defaults to info
-    /// 
-    macro_rules! log {
-        // Each case in this macro definition is for a different log marker.
-        // None
-        ($expr:expr) => {
-            println!("\x1b[0;90m{}\x1b[0m {}", chrono::Local::now().format("%m.%d %I:%M:%S"), $expr)
-        };
-        // Fatal error
-        (fatal $prefix:expr; $($expr:expr),*) => {
-            log!(&format!("\x1b[0;1;91m[{}] FATAL\x1b[0m \x1b[0;1;41m{}\x1b[0m", $prefix, &format!($($expr),*)))
-        };
-        // Warning error
-        (error $prefix:expr; $($expr:expr),*) => {
-            log!(&format!("\x1b[0;1;91m[{}] WARN\x1b[0m {}", $prefix, &format!($($expr),*)))
-        };
-        // Important
-        (important $prefix:expr; $($expr:expr),*) => {
-            log!(&format!("\x1b[0;1;93m[{}]\x1b[0m {}", $prefix, &format!($($expr),*)))
-        };
-        // Info
-        (info $prefix:expr; $($expr:expr),*) => {
-            log!(&format!("\x1b[0;1;32m[{}]\x1b[0m {}", $prefix, &format!($($expr),*)))
-        };
-        // Default (use info)
-        ($prefix:expr; $($expr:expr),*) => {
-            log!(info $prefix; $($expr),*)
-        };
-    }
-}
 pub mod strings{
     pub trait TidyableString {
         fn trim_whitespace(&self) -> Self;
@@ -1,7 +1,7 @@
 use std::collections::VecDeque;
 use std::time::Instant;
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::game::Game;
 use crate::game::game_client::{GameClient, GameClientLocation};
@@ -18,7 +18,7 @@ pub struct LobbyClient{
     pub last_message_times: VecDeque<Instant>,
 }
 
-#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub enum Ready {
     Host,
@@ -26,7 +26,7 @@ pub enum Ready {
     NotReady,
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(tag = "type")]
 #[serde(rename_all = "camelCase")]
 pub enum LobbyClientType{
@@ -45,13 +45,27 @@ impl LobbyClient {
             last_message_times: VecDeque::new()
         }
     }
+    pub fn new_bot(name: String)->Self{
+        LobbyClient{
+            connection: ClientConnection::Bot,
+            ready: Ready::Ready,
+            client_type: LobbyClientType::Player{name},
+            last_message_times: VecDeque::new()
+        }
+    }
     pub fn new_from_game_client(game: &Game, game_client: GameClient)->Self{
 
         match game_client.client_location {
             GameClientLocation::Player(player) => {
                 LobbyClient{
                     connection: player.connection(game).clone(),
-                    ready: if game_client.host { Ready::Host } else { Ready::NotReady },
+                    ready: if game_client.host {
+                        Ready::Host
+                    } else if player.is_bot(game) {
+                        Ready::Ready
+                    } else {
+                        Ready::NotReady
+                    },
                     client_type: LobbyClientType::Player{name: player.name(game).to_string()},
                     last_message_times: VecDeque::new()
                 }
@@ -79,13 +93,15 @@ impl LobbyClient {
         self.ready == Ready::Host
     }
 
+    pub fn is_bot(&self) -> bool {
+        matches!(self.connection, ClientConnection::Bot)
+    }
+
     pub fn is_spectator(&self) -> bool {
         matches!(self.client_type, LobbyClientType::Spectator)
     }
 
     pub fn send(&self, message: ToClientPacket) {
-        if let ClientConnection::Connected(ref sender) = self.connection {
-            sender.send(message);
-        }
+        self.connection.send_packet(message);
     }
 }
\ No newline at end of file
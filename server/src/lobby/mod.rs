@@ -5,22 +5,22 @@ use std::{collections::VecDeque, time::{Duration, Instant}};
 
 use lobby_client::{LobbyClient, LobbyClientType, Ready};
 
-use crate::{client_connection::ClientConnection, game::{role_list::RoleOutline, settings::Settings}, packet::{RoomPreviewData, RejectJoinReason, ToClientPacket}, room::{name_validation, JoinRoomClientResult, RemoveRoomClientResult, RoomClientID, RoomState, RoomTickResult}, vec_map::VecMap, websocket_connections::connection::ClientSender};
+use crate::{client_connection::ClientConnection, config::ServerConfig, game::{role_list::RoleOutline, role_list_analysis::CachedAnalysis, settings::Settings}, packet::{RoomPreviewData, RejectJoinReason, ToClientPacket}, room::{name_validation, JoinRoomClientResult, RemoveRoomClientResult, RoomClientID, RoomState, RoomTickResult}, vec_map::VecMap, websocket_connections::connection::ClientSender};
 
 pub struct Lobby {
     pub name: String,
     pub settings: Settings,
     pub clients: VecMap<RoomClientID, LobbyClient>,
+    pub role_list_analysis: CachedAnalysis,
 }
 
 impl Lobby {
-    const DISCONNECT_TIMER_SECS: u64 = 5;
-
     pub fn new() -> Self {
         Self {
             name: name_validation::DEFAULT_SERVER_NAME.to_string(),
             settings: Settings::default(),
             clients: VecMap::new(),
+            role_list_analysis: CachedAnalysis::default(),
         }
     }
 
@@ -28,11 +28,12 @@ impl Lobby {
         if !self.clients.iter().any(|p|p.1.is_host()) {
             let next_available_player = self.clients.iter_mut()
                 .filter(|(&id, _)| skip.is_none_or(|s| s != id))
+                .filter(|(_, c)| !c.is_bot())
                 .map(|(_, c)| c).next();
 
             if let Some(new_host) = next_available_player {
                 new_host.set_host();
-            } else if let Some(new_host) = self.clients.values_mut().next(){
+            } else if let Some(new_host) = self.clients.values_mut().find(|c| !c.is_bot()){
                 new_host.set_host();
             }
         }
@@ -71,6 +72,7 @@ impl Lobby {
         send.send(ToClientPacket::RoleList { role_list: self.settings.role_list.clone() });
         send.send(ToClientPacket::EnabledRoles { roles: self.settings.enabled_roles.clone().into_iter().collect() });
         send.send(ToClientPacket::EnabledModifiers { modifiers: self.settings.enabled_modifiers.clone().into_iter().collect() });
+        send.send(ToClientPacket::RoleListConstraints { constraints: self.settings.role_list_constraints.clone() });
     }
 
     pub fn set_player_name(&mut self, room_client_id: RoomClientID, name: String) {
@@ -87,7 +89,7 @@ impl Lobby {
             }).collect::<Vec<_>>()
         };
         
-        let new_name: String = name_validation::sanitize_name(name, &other_player_names);
+        let new_name: String = name_validation::sanitize_name(name, &other_player_names, &mut rand::rng());
 
         if let Some(player) = self.clients.get_mut(&room_client_id){
             if let LobbyClientType::Player { name } = &mut player.client_type {
@@ -98,6 +100,48 @@ impl Lobby {
         self.send_players();
     }
 
+    /// Adds a bot player, which is always ready.
+    /// Returns `None` if the lobby has no room for another client.
+    pub fn add_bot(&mut self) -> Option<RoomClientID> {
+        let room_client_id = self.next_client_id()?;
+        let name = self.new_player_name();
+
+        self.clients.insert(room_client_id, LobbyClient::new_bot(name));
+
+        self.set_rolelist_length();
+        self.send_players();
+        for player in self.clients.iter(){
+            if let ClientConnection::Connected(send) = &player.1.connection {
+                self.send_settings(send);
+            }
+        }
+
+        Some(room_client_id)
+    }
+
+    /// Returns `None` if the lobby is full
+    fn next_client_id(&self) -> Option<RoomClientID> {
+        if self.clients.len() >= ServerConfig::get().max_clients_per_room {
+            return None;
+        }
+        (self.clients
+            .iter()
+            .map(|(i,_)|*i)
+            .fold(0u32, u32::max) as RoomClientID).checked_add(1)
+    }
+
+    fn new_player_name(&self) -> String {
+        let player_names = self.clients.values().filter_map(|p| {
+            if let LobbyClientType::Player { name } = p.client_type.clone() {
+                Some(name)
+            } else {
+                None
+            }
+        }).collect::<Vec<_>>();
+
+        name_validation::sanitize_name("".to_string(), &player_names, &mut rand::rng())
+    }
+
     pub fn set_rolelist_length(&mut self) {
         let length = self.clients.iter()
             .filter(|p| matches!(p.1.client_type, LobbyClientType::Player{..}))
@@ -121,7 +165,7 @@ impl Lobby {
     }
     
     pub fn new_from_game(name: String, settings: Settings, clients: VecMap<RoomClientID, LobbyClient>) -> Self {
-        let new = Self { name, settings, clients };
+        let new = Self { name, settings, clients, role_list_analysis: CachedAnalysis::default() };
 
         for (id, client) in new.clients.iter() {
             client.send(ToClientPacket::YourId { player_id: *id });
@@ -144,24 +188,12 @@ impl RoomState for Lobby {
     }
 
     fn join_client(&mut self, send: &ClientSender) -> Result<JoinRoomClientResult, RejectJoinReason> {
-        let player_names = self.clients.values().filter_map(|p| {
-            if let LobbyClientType::Player { name } = p.client_type.clone() {
-                Some(name)
-            } else {
-                None
-            }
-        }).collect::<Vec<_>>();
-
-        let name = name_validation::sanitize_name("".to_string(), &player_names);
+        let name = self.new_player_name();
         
         let new_player = LobbyClient::new(name.clone(), send.clone(), self.clients.is_empty());
-        let Some(room_client_id) =
-            (self.clients
-                .iter()
-                .map(|(i,_)|*i)
-                .fold(0u32, u32::max) as RoomClientID).checked_add(1) else {
-                    return Err(RejectJoinReason::RoomFull)
-                };
+        let Some(room_client_id) = self.next_client_id() else {
+            return Err(RejectJoinReason::RoomFull)
+        };
 
         self.clients.insert(room_client_id, new_player);
 
@@ -195,7 +227,7 @@ impl RoomState for Lobby {
             }
         }
 
-        if self.clients.is_empty() {
+        if self.clients.values().all(LobbyClient::is_bot) {
             RemoveRoomClientResult::RoomShouldClose
         } else {
             RemoveRoomClientResult::Success
@@ -209,9 +241,7 @@ impl RoomState for Lobby {
             return self.remove_client(id);
         }
 
-        client.connection = ClientConnection::CouldReconnect { 
-            disconnect_timer: Duration::from_secs(Self::DISCONNECT_TIMER_SECS)
-        };
+        client.connection = client.connection.lost(ServerConfig::get().lobby_disconnect_timer());
 
         self.ensure_host_exists(None);
 
@@ -225,7 +255,7 @@ impl RoomState for Lobby {
             return Err(RejectJoinReason::PlayerDoesntExist)
         };
         match &mut client.connection {
-            ClientConnection::Connected(_) => Err(RejectJoinReason::PlayerTaken),
+            ClientConnection::Connected(_) | ClientConnection::Bot => Err(RejectJoinReason::PlayerTaken),
             ClientConnection::CouldReconnect { .. } => {
                 client.connection = ClientConnection::Connected(send.clone());
     
@@ -250,7 +280,7 @@ impl RoomState for Lobby {
         let mut to_remove = vec![];
 
         for client in self.clients.iter_mut() {
-            if let ClientConnection::CouldReconnect { disconnect_timer } = &mut client.1.connection {
+            if let ClientConnection::CouldReconnect { disconnect_timer, .. } = &mut client.1.connection {
                 if let Some(time_remaining) = disconnect_timer.checked_sub(time_passed) {
                     *disconnect_timer = time_remaining;
                 } else {
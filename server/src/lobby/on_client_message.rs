@@ -1,6 +1,6 @@
 use std::collections::VecDeque;
 
-use crate::{game::{chat::{ChatMessage, ChatMessageVariant}, game_client::{GameClient, GameClientLocation}, phase::PhaseType, player::{PlayerIndex, PlayerInitializeParameters, PlayerReference}, spectator::{spectator_pointer::{SpectatorIndex, SpectatorPointer}, SpectatorInitializeParameters}, Game, RejectStartReason}, log, packet::{ToClientPacket, ToServerPacket}, room::{name_validation::{self, sanitize_server_name}, RemoveRoomClientResult, RoomClientID, RoomState}, strings::TidyableString, vec_map::VecMap, websocket_connections::connection::ClientSender};
+use crate::{game::{chat::{ChatMessage, ChatMessageVariant}, game_client::{GameClient, GameClientLocation}, phase::PhaseType, player::{PlayerIndex, PlayerInitializeParameters, PlayerReference}, role_list_constraints::MAX_ROLE_LIST_CONSTRAINTS, spectator::{spectator_pointer::{SpectatorIndex, SpectatorPointer}, SpectatorInitializeParameters}, Game, RejectStartReason}, log, metrics, packet::{ToClientPacket, ToServerPacket}, room::{name_validation::{self, sanitize_server_name}, RemoveRoomClientResult, RoomClientID, RoomState}, strings::TidyableString, vec_map::VecMap, websocket_connections::connection::ClientSender};
 
 use super::{lobby_client::{LobbyClient, LobbyClientType, Ready}, Lobby};
 
@@ -42,7 +42,7 @@ impl Lobby {
                     }
                 }).collect::<Vec<_>>();
 
-                let new_name = name_validation::sanitize_name("".to_string(), &player_names);
+                let new_name = name_validation::sanitize_name("".to_string(), &player_names, &mut rand::rng());
 
                 if let Some(player) = self.clients.get_mut(&room_client_id){
                     match &player.client_type {
@@ -140,6 +140,7 @@ impl Lobby {
                                 next_player_index = new_player_index;
                             } else {
                                 send.send(ToClientPacket::RejectStart { reason: RejectStartReason::TooManyClients });
+                                metrics::start_rejected(&RejectStartReason::TooManyClients);
                                 break 'packet_match;
                             }
                         },
@@ -152,17 +153,19 @@ impl Lobby {
                                 next_spectator_index = new_spectator_index;
                             } else {
                                 send.send(ToClientPacket::RejectStart { reason: RejectStartReason::TooManyClients });
+                                metrics::start_rejected(&RejectStartReason::TooManyClients);
                                 break 'packet_match;
                             }
                         }
                     }
                 }
 
-                let game = match Game::new(self.name.clone(), self.settings.clone(), game_clients, game_player_params, game_spectator_params){
+                let game = match Game::new(self.name.clone(), self.settings.clone(), game_clients, game_player_params, game_spectator_params, rand::random()){
                     Ok(game) => game,
                     Err(err) => {
-                        send.send(ToClientPacket::RejectStart { reason: err });
+                        metrics::start_rejected(&err);
                         log!(info "Lobby"; "Failed to start game: {:?}", err);
+                        send.send(ToClientPacket::RejectStart { reason: err });
                         break 'packet_match
                     }
                 };
@@ -233,6 +236,20 @@ impl Lobby {
                 
                 self.send_to_all(ToClientPacket::RoleList { role_list });
             }
+            ToServerPacket::AnalyzeRoleList => {
+                let analysis = self.role_list_analysis.get(&self.settings.role_list, &self.settings.enabled_roles, &self.settings.role_list_constraints);
+                send.send(ToClientPacket::RoleListAnalysis { analysis });
+            }
+            ToServerPacket::SetRoleListConstraints { constraints } => {
+                if let Some(player) = self.clients.get(&room_client_id){
+                    if !player.is_host() {break 'packet_match}
+                }
+                if constraints.len() > MAX_ROLE_LIST_CONSTRAINTS {break 'packet_match}
+
+                self.settings.role_list_constraints = constraints.clone();
+
+                self.send_to_all(ToClientPacket::RoleListConstraints { constraints });
+            }
             ToServerPacket::SetEnabledRoles { roles } => {
                 self.settings.enabled_roles = roles.into_iter().collect();
                 let roles = self.settings.enabled_roles.clone().into_iter().collect();
@@ -259,10 +276,19 @@ impl Lobby {
                     if !player.is_host() { break 'packet_match }
                 }
                 if let Some(player) = self.clients.get_mut(&player_id) {
+                    if player.is_bot() { break 'packet_match }
                     player.set_host();
                 }
                 self.send_players();
             }
+            ToServerPacket::HostAddBot => {
+                if let Some(player) = self.clients.get(&room_client_id) {
+                    if !player.is_host() { break 'packet_match }
+                }
+                if self.add_bot().is_none() {
+                    log!(info "Lobby"; "Couldn't add a bot, the lobby is full");
+                }
+            }
             ToServerPacket::RelinquishHost => {
                 if let Some(player) = self.clients.get_mut(&room_client_id) {
                     if !player.is_host() { break 'packet_match }
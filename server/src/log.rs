@@ -0,0 +1,221 @@
+//! Logging for the whole server, through the [`log!`](crate::log!) macro.
+//!
+//! Lines below the configured [`LogLevel`] are skipped, and each line is either
+//! colored text for a terminal or one JSON object, depending on the [`LogFormat`].
+//!
+//! While a room handles an event, every line logged on that thread carries the room code,
+//! and the ID of the client the event came from, if any. While the room is in a game,
+//! lines also carry the day number and phase. See [`LogContext`].
+
+use std::{cell::Cell, str::FromStr, sync::OnceLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{game::phase::PhaseType, room::RoomClientID, websocket_listener::RoomCode};
+
+static SETTINGS: OnceLock<(LogLevel, LogFormat)> = OnceLock::new();
+
+thread_local! {
+    static CONTEXT: Cell<LogContext> = const { Cell::new(LogContext::NONE) };
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Info,
+    Important,
+    Error,
+    Fatal,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Colored text, for reading in a terminal
+    Pretty,
+    /// One JSON object per line, for log collectors
+    Json,
+}
+
+/// Where a line was logged from. Fields that aren't known are left out.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct LogContext {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub room_code: Option<RoomCode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<RoomClientID>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub day: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub phase: Option<PhaseType>,
+}
+
+impl LogContext {
+    pub const NONE: Self = Self { room_code: None, client_id: None, day: None, phase: None };
+
+    /// The context of every line logged on this thread
+    pub fn get() -> Self {
+        CONTEXT.get()
+    }
+    /// Sets the context of every line logged on this thread, until it's set again
+    pub fn set(context: Self) {
+        CONTEXT.set(context);
+    }
+    pub fn set_client(client_id: Option<RoomClientID>) {
+        Self::set(Self { client_id, ..Self::get() });
+    }
+    /// Only sets the day and phase if this thread is in a room, so games that aren't in one, like simulations, don't get them
+    pub fn set_game_time(day: u8, phase: PhaseType) {
+        let context = Self::get();
+        if context.room_code.is_some() {
+            Self::set(Self { day: Some(day), phase: Some(phase), ..context });
+        }
+    }
+}
+
+/// Sets the level and format of every line logged from now on.
+/// Does nothing if it was already set, so call this before starting the server.
+/// Until this is called, every line is logged as [`LogFormat::Pretty`].
+pub fn init(level: LogLevel, format: LogFormat) {
+    let _ = SETTINGS.set((level, format));
+}
+
+fn settings() -> (LogLevel, LogFormat) {
+    SETTINGS.get().copied().unwrap_or((LogLevel::Info, LogFormat::Pretty))
+}
+
+pub fn enabled(level: LogLevel) -> bool {
+    level >= settings().0
+}
+
+/// Use [`log!`](crate::log!) instead, so the message isn't formatted if its level is disabled
+pub fn write(level: LogLevel, prefix: &str, message: &str) {
+    println!("{}", line(level, prefix, message));
+}
+
+/// The line [`write`] prints, in the configured format and with this thread's context
+pub fn line(level: LogLevel, prefix: &str, message: &str) -> String {
+    let context = LogContext::get();
+
+    match settings().1 {
+        LogFormat::Pretty => pretty_line(level, prefix, message, &context),
+        LogFormat::Json => json_line(level, prefix, message, context),
+    }
+}
+
+fn pretty_line(level: LogLevel, prefix: &str, message: &str, context: &LogContext) -> String {
+    let time = chrono::Local::now().format("%m.%d %I:%M:%S");
+    let line = match level {
+        LogLevel::Fatal => format!("\x1b[0;1;91m[{prefix}] FATAL\x1b[0m \x1b[0;1;41m{message}\x1b[0m"),
+        LogLevel::Error => format!("\x1b[0;1;91m[{prefix}] WARN\x1b[0m {message}"),
+        LogLevel::Important => format!("\x1b[0;1;93m[{prefix}]\x1b[0m {message}"),
+        LogLevel::Info => format!("\x1b[0;1;32m[{prefix}]\x1b[0m {message}"),
+    };
+
+    let mut location = Vec::new();
+    if let Some(room_code) = context.room_code {
+        location.push(format!("room {room_code}"));
+    }
+    if let Some(client_id) = context.client_id {
+        location.push(format!("client {client_id}"));
+    }
+    if let (Some(day), Some(phase)) = (context.day, context.phase) {
+        location.push(format!("day {day} {phase:?}"));
+    }
+
+    if location.is_empty() {
+        format!("\x1b[0;90m{time}\x1b[0m {line}")
+    } else {
+        format!("\x1b[0;90m{time}\x1b[0m {line} \x1b[0;90m({})\x1b[0m", location.join(", "))
+    }
+}
+
+fn json_line(level: LogLevel, prefix: &str, message: &str, context: LogContext) -> String {
+    #[derive(Serialize)]
+    struct JsonLine<'a> {
+        time: String,
+        level: LogLevel,
+        prefix: &'a str,
+        message: &'a str,
+        #[serde(flatten)]
+        context: LogContext,
+    }
+
+    let line = JsonLine { time: chrono::Local::now().to_rfc3339(), level, prefix, message, context };
+    serde_json::to_string(&line).unwrap_or_else(|_| message.to_string())
+}
+
+impl FromStr for LogLevel {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "info" => Ok(Self::Info),
+            "important" => Ok(Self::Important),
+            "error" => Ok(Self::Error),
+            "fatal" => Ok(Self::Fatal),
+            _ => Err(()),
+        }
+    }
+}
+
+impl FromStr for LogFormat {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pretty" => Ok(Self::Pretty),
+            "json" => Ok(Self::Json),
+            _ => Err(()),
+        }
+    }
+}
+
+#[macro_export]
+/// Log a statement to the console.
+/// When logging using this macro, a timestamp, a marker and whatever [`LogContext`](crate::log::LogContext) is set is added to the message.
+///
+/// # Examples
+/// ```
+/// use mafia_server::log;
+/// log!(error "Error location"; "Error message");
+/// log!(error "Game::new"; "Failed to generate role. rolelist wasnt big enough for number of players");
+/// log!(info "Listener"; "{}: {}", "Received message", "message");
+/// ```
+///
+/// # Markers
+/// - `fatal`: Prints the word FATAL
+/// - `error`: Prints red and writes "WARN"
+/// - `important`:
+/// - `info`:
+///
+/// if none are put then it defaults to info
+///
+macro_rules! log {
+    // Each case in this macro definition is for a different log marker.
+    (@ $level:expr, $prefix:expr; $($expr:expr),*) => {
+        if $crate::log::enabled($level) {
+            $crate::log::write($level, $prefix, &format!($($expr),*))
+        }
+    };
+    // Fatal error
+    (fatal $prefix:expr; $($expr:expr),*) => {
+        $crate::log!(@ $crate::log::LogLevel::Fatal, $prefix; $($expr),*)
+    };
+    // Warning error
+    (error $prefix:expr; $($expr:expr),*) => {
+        $crate::log!(@ $crate::log::LogLevel::Error, $prefix; $($expr),*)
+    };
+    // Important
+    (important $prefix:expr; $($expr:expr),*) => {
+        $crate::log!(@ $crate::log::LogLevel::Important, $prefix; $($expr),*)
+    };
+    // Info
+    (info $prefix:expr; $($expr:expr),*) => {
+        $crate::log!(@ $crate::log::LogLevel::Info, $prefix; $($expr),*)
+    };
+    // Default (use info)
+    ($prefix:expr; $($expr:expr),*) => {
+        $crate::log!(info $prefix; $($expr),*)
+    };
+}
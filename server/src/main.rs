@@ -1,5 +1,5 @@
 
-use mafia_server::{log, websocket_connections::websocket_server::create_ws_server};
+use mafia_server::{config::ServerConfig, log, websocket_connections::websocket_server::create_ws_server};
 use std::{thread, time::Duration};
 
 
@@ -13,10 +13,18 @@ use std::{thread, time::Duration};
 async fn main() -> ! {
 
     dotenv::dotenv().ok();
-    let address = std::env::var("WS_ADDRESS").expect("Missing environment variabled WS_ADDRESS");
+    let config = match ServerConfig::load() {
+        Ok(config) => config,
+        Err(err) => {
+            log!(fatal "Config"; "{}", err);
+            std::process::exit(1);
+        }
+    };
+    log::init(config.log_level, config.log_format);
+    ServerConfig::set(config.clone());
 
     loop {
-        create_ws_server(&address).await;
+        create_ws_server(&config.ws_address, config.replay_directory.clone(), config.data_directory.clone()).await;
         // This delay is only to make sure disconnect messages are sent before the server restarts
         thread::sleep(Duration::from_secs(1));
         log!(important "Server"; "Restarting...");
@@ -0,0 +1,226 @@
+//! Counters of what happened on the server since it started, served over HTTP at `/metrics`
+//! in the Prometheus text format.
+//!
+//! Counters are recorded from anywhere with the functions in this module.
+//! Gauges, like how many rooms are open, are read from the listener when the metrics are rendered.
+
+use std::{collections::BTreeMap, fmt::Write, sync::{Mutex, PoisonError}, time::Duration};
+
+use crate::{
+    game::{game_conclusion::GameConclusion, RejectStartReason},
+    packet::ToServerPacket, websocket_listener::{IpLimitExceeded, ServerStats}
+};
+
+static METRICS: Mutex<Metrics> = Mutex::new(Metrics::new());
+
+struct Metrics {
+    packets_received: BTreeMap<&'static str, u64>,
+    rate_limits_exceeded: u64,
+    ip_limits_exceeded: BTreeMap<&'static str, u64>,
+    games_started: u64,
+    games_ended: BTreeMap<&'static str, u64>,
+    game_length_secs_sum: f64,
+    starts_rejected: BTreeMap<&'static str, u64>,
+    rejoins: BTreeMap<&'static str, u64>,
+}
+
+impl Metrics {
+    const fn new() -> Self {
+        Self {
+            packets_received: BTreeMap::new(),
+            rate_limits_exceeded: 0,
+            ip_limits_exceeded: BTreeMap::new(),
+            games_started: 0,
+            games_ended: BTreeMap::new(),
+            game_length_secs_sum: 0.0,
+            starts_rejected: BTreeMap::new(),
+            rejoins: BTreeMap::new(),
+        }
+    }
+}
+
+fn metrics(record: impl FnOnce(&mut Metrics)) {
+    record(&mut METRICS.lock().unwrap_or_else(PoisonError::into_inner));
+}
+
+/// The type `packet` is sent with, like `sendChatMessage`
+pub fn packet_name(packet: &ToServerPacket) -> &'static str {
+    match packet {
+        ToServerPacket::Ping => "ping",
+        ToServerPacket::Hello { .. } => "hello",
+        ToServerPacket::RoomListRequest => "lobbyListRequest",
+        ToServerPacket::ReJoin { .. } => "reJoin",
+        ToServerPacket::Join { .. } => "join",
+        ToServerPacket::Host => "host",
+        ToServerPacket::Leave => "leave",
+        ToServerPacket::Kick { .. } => "kick",
+        ToServerPacket::SetPlayerHost { .. } => "setPlayerHost",
+        ToServerPacket::RelinquishHost => "relinquishHost",
+        ToServerPacket::HostAddBot => "hostAddBot",
+        ToServerPacket::HostSetRoomPrivate { .. } => "hostSetRoomPrivate",
+        ToServerPacket::HostSetRoomPassword { .. } => "hostSetRoomPassword",
+        ToServerPacket::HostCreateInviteCode => "hostCreateInviteCode",
+        ToServerPacket::SendLobbyMessage { .. } => "sendLobbyMessage",
+        ToServerPacket::SetSpectator { .. } => "setSpectator",
+        ToServerPacket::SetName { .. } => "setName",
+        ToServerPacket::ReadyUp { .. } => "readyUp",
+        ToServerPacket::SetRoomName { .. } => "setLobbyName",
+        ToServerPacket::StartGame => "startGame",
+        ToServerPacket::SetRoleList { .. } => "setRoleList",
+        ToServerPacket::SetRoleOutline { .. } => "setRoleOutline",
+        ToServerPacket::SimplifyRoleList => "simplifyRoleList",
+        ToServerPacket::AnalyzeRoleList => "analyzeRoleList",
+        ToServerPacket::SetRoleListConstraints { .. } => "setRoleListConstraints",
+        ToServerPacket::SetPhaseTime { .. } => "setPhaseTime",
+        ToServerPacket::SetPhaseTimes { .. } => "setPhaseTimes",
+        ToServerPacket::SetEnabledRoles { .. } => "setEnabledRoles",
+        ToServerPacket::SetEnabledModifiers { .. } => "setEnabledModifiers",
+        ToServerPacket::HostDataRequest => "hostDataRequest",
+        ToServerPacket::HostForceBackToLobby => "hostForceBackToLobby",
+        ToServerPacket::HostForceEndGame => "hostForceEndGame",
+        ToServerPacket::HostForceSkipPhase => "hostForceSkipPhase",
+        ToServerPacket::HostForceSetPlayerName { .. } => "hostForceSetPlayerName",
+        ToServerPacket::Judgement { .. } => "judgement",
+        ToServerPacket::SendChatMessage { .. } => "sendChatMessage",
+        ToServerPacket::SendWhisper { .. } => "sendWhisper",
+        ToServerPacket::SaveWill { .. } => "saveWill",
+        ToServerPacket::SaveNotes { .. } => "saveNotes",
+        ToServerPacket::SaveCrossedOutOutlines { .. } => "saveCrossedOutOutlines",
+        ToServerPacket::SaveDeathNote { .. } => "saveDeathNote",
+        ToServerPacket::AbilityInput { .. } => "abilityInput",
+        ToServerPacket::SetDoomsayerGuess { .. } => "setDoomsayerGuess",
+        ToServerPacket::SetConsortOptions { .. } => "setConsortOptions",
+        ToServerPacket::VoteFastForwardPhase { .. } => "voteFastForwardPhase",
+    }
+}
+
+fn ip_limit_name(limit: IpLimitExceeded) -> &'static str {
+    match limit {
+        IpLimitExceeded::Banned => "banned",
+        IpLimitExceeded::TooManyConnections => "tooManyConnections",
+        IpLimitExceeded::TooManyRoomsCreated => "tooManyRoomsCreated",
+        IpLimitExceeded::TooManyPackets => "tooManyPackets",
+    }
+}
+
+fn conclusion_name(conclusion: &GameConclusion) -> &'static str {
+    match conclusion {
+        GameConclusion::Town => "town",
+        GameConclusion::Mafia => "mafia",
+        GameConclusion::Cult => "cult",
+        GameConclusion::Fiends => "fiends",
+        GameConclusion::Politician => "politician",
+        GameConclusion::NiceList => "niceList",
+        GameConclusion::NaughtyList => "naughtyList",
+        GameConclusion::Draw => "draw",
+    }
+}
+
+fn reject_start_name(reason: &RejectStartReason) -> &'static str {
+    match reason {
+        RejectStartReason::TooManyClients => "tooManyClients",
+        RejectStartReason::GameEndsInstantly { .. } => "gameEndsInstantly",
+        RejectStartReason::RoleListTooSmall => "roleListTooSmall",
+        RejectStartReason::RoleListCannotCreateRoles(_) => "roleListCannotCreateRoles",
+        RejectStartReason::ZeroTimeGame => "zeroTimeGame",
+        RejectStartReason::PlayerDisconnected => "playerDisconnected",
+    }
+}
+
+fn increment<K: Ord>(counts: &mut BTreeMap<K, u64>, key: K) {
+    let count = counts.entry(key).or_default();
+    *count = count.saturating_add(1);
+}
+
+pub fn packet_received(packet: &ToServerPacket) {
+    let name = packet_name(packet);
+    metrics(|metrics| increment(&mut metrics.packets_received, name));
+}
+
+pub fn rate_limit_exceeded() {
+    metrics(|metrics| metrics.rate_limits_exceeded = metrics.rate_limits_exceeded.saturating_add(1));
+}
+
+pub fn ip_limit_exceeded(limit: IpLimitExceeded) {
+    let name = ip_limit_name(limit);
+    metrics(|metrics| increment(&mut metrics.ip_limits_exceeded, name));
+}
+
+pub fn game_started() {
+    metrics(|metrics| metrics.games_started = metrics.games_started.saturating_add(1));
+}
+
+pub fn game_ended(conclusion: &GameConclusion, length: Duration) {
+    let name = conclusion_name(conclusion);
+    metrics(|metrics| {
+        increment(&mut metrics.games_ended, name);
+        metrics.game_length_secs_sum += length.as_secs_f64();
+    });
+}
+
+pub fn start_rejected(reason: &RejectStartReason) {
+    let name = reject_start_name(reason);
+    metrics(|metrics| increment(&mut metrics.starts_rejected, name));
+}
+
+pub fn rejoined(accepted: bool) {
+    metrics(|metrics| increment(&mut metrics.rejoins, if accepted { "accepted" } else { "rejected" }));
+}
+
+/// Renders every metric in the Prometheus text format
+pub fn render(stats: &ServerStats) -> String {
+    let metrics = METRICS.lock().unwrap_or_else(PoisonError::into_inner);
+    let mut out = String::new();
+
+    write_header(&mut out, "mafia_packets_received_total", "counter", "Packets received from clients, by type");
+    for (packet_type, count) in &metrics.packets_received {
+        let _ = writeln!(out, "mafia_packets_received_total{{type=\"{packet_type}\"}} {count}");
+    }
+
+    write_header(&mut out, "mafia_rate_limits_exceeded_total", "counter", "Messages refused because the client sent too many");
+    let _ = writeln!(out, "mafia_rate_limits_exceeded_total {}", metrics.rate_limits_exceeded);
+
+    write_header(&mut out, "mafia_ip_limits_exceeded_total", "counter", "Connections, rooms and packets refused because of their IP address, by limit");
+    for (limit, count) in &metrics.ip_limits_exceeded {
+        let _ = writeln!(out, "mafia_ip_limits_exceeded_total{{limit=\"{limit}\"}} {count}");
+    }
+
+    write_header(&mut out, "mafia_games_started_total", "counter", "Games started");
+    let _ = writeln!(out, "mafia_games_started_total {}", metrics.games_started);
+
+    write_header(&mut out, "mafia_games_ended_total", "counter", "Games ended, by conclusion");
+    for (conclusion, count) in &metrics.games_ended {
+        let _ = writeln!(out, "mafia_games_ended_total{{conclusion=\"{conclusion}\"}} {count}");
+    }
+
+    write_header(&mut out, "mafia_game_length_seconds", "summary", "How long games took to end");
+    let _ = writeln!(out, "mafia_game_length_seconds_sum {}", metrics.game_length_secs_sum);
+    let _ = writeln!(out, "mafia_game_length_seconds_count {}", metrics.games_ended.values().sum::<u64>());
+
+    write_header(&mut out, "mafia_starts_rejected_total", "counter", "Attempts to start a game that were refused, by reason");
+    for (reason, count) in &metrics.starts_rejected {
+        let _ = writeln!(out, "mafia_starts_rejected_total{{reason=\"{reason}\"}} {count}");
+    }
+
+    write_header(&mut out, "mafia_rejoins_total", "counter", "Reconnects through rejoin, by whether the room let the client back in");
+    for (result, count) in &metrics.rejoins {
+        let _ = writeln!(out, "mafia_rejoins_total{{result=\"{result}\"}} {count}");
+    }
+
+    write_header(&mut out, "mafia_rooms_open", "gauge", "Rooms open right now, by whether they're in the lobby or in a game");
+    let _ = writeln!(out, "mafia_rooms_open{{state=\"lobby\"}} {}", stats.rooms_in_lobby);
+    let _ = writeln!(out, "mafia_rooms_open{{state=\"game\"}} {}", stats.rooms_in_game);
+
+    write_header(&mut out, "mafia_connected_clients", "gauge", "Websocket connections open right now");
+    let _ = writeln!(out, "mafia_connected_clients {}", stats.connected_clients);
+
+    write_header(&mut out, "mafia_uptime_seconds", "gauge", "How long the server has been running");
+    let _ = writeln!(out, "mafia_uptime_seconds {}", stats.uptime_secs);
+
+    out
+}
+
+fn write_header(out: &mut String, name: &str, metric_type: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {metric_type}");
+}
@@ -27,8 +27,9 @@ use crate::{
         ability_input::*, chat::{ChatGroup, ChatMessage}, components::{insider_group::InsiderGroupID, tags::Tag}, game_client::GameClientLocation, grave::Grave, modifiers::ModifierType, phase::{PhaseState, PhaseType}, player::{PlayerIndex, PlayerReference}, role::{
             doomsayer::DoomsayerGuess,
             ClientRoleStateEnum, Role
-        }, role_list::{RoleList, RoleOutline}, settings::PhaseTimeSettings, verdict::Verdict, GameOverReason, RejectStartReason
-    }, lobby::lobby_client::LobbyClient, room::RoomClientID, vec_map::VecMap, vec_set::VecSet, websocket_listener::RoomCode
+        }, role_list::{RoleList, RoleOutline}, role_list_analysis::RoleListAnalysis, role_list_constraints::RoleListConstraint, settings::PhaseTimeSettings, verdict::Verdict, GameOverReason, RejectStartReason
+    }, lobby::lobby_client::LobbyClient, protocol::{Capability, RejectHelloReason}, room::{access::Secret, reconnect_token::ReconnectToken, RoomClientID}, vec_map::VecMap, vec_set::VecSet,
+    websocket_connections::heartbeat::ConnectionQuality, websocket_listener::RoomCode
 };
 
 #[derive(Serialize, Debug, Clone)]
@@ -44,6 +45,8 @@ pub struct RoomPreviewData {
 pub struct HostDataPacketGameClient {
     pub client_type: GameClientLocation,
     pub connection: ClientConnection,
+    /// `None` unless the client is connected
+    pub connection_quality: Option<ConnectionQuality>,
     pub host: bool,
 }
 
@@ -52,11 +55,20 @@ pub struct HostDataPacketGameClient {
 #[serde(tag = "type")]
 pub enum ToClientPacket{
     Pong,
+    #[serde(rename_all = "camelCase")]
+    Hello{protocol_version: u32, capabilities: VecSet<Capability>},
+    #[serde(rename_all = "camelCase")]
+    RejectHello{reason: RejectHelloReason, protocol_version: u32},
     
     #[serde(rename_all = "camelCase")]
     RateLimitExceeded,
     
     ForcedDisconnect,
+    /// From an operator, to every connected client
+    ServerAnnouncement{message: String},
+    /// The server is about to restart on an operator's request. Players can rejoin their room afterwards, if rooms are kept
+    #[serde(rename_all = "camelCase")]
+    ServerRestarting{in_secs: u64},
     #[serde(rename = "forcedOutsideLobby")]
     ForcedOutsideRoom,
 
@@ -66,14 +78,18 @@ pub enum ToClientPacket{
         #[serde(rename = "lobbies")]
         rooms: HashMap<RoomCode, RoomPreviewData>
     },
+    /// `resumed` is true if the client rejoined and is only sent the packets it missed, so it should keep what it has
     #[serde(rename_all = "camelCase")]
-    AcceptJoin{room_code: RoomCode, in_game: bool, player_id: RoomClientID, spectator: bool},
+    AcceptJoin{room_code: RoomCode, in_game: bool, player_id: RoomClientID, spectator: bool, reconnect_token: ReconnectToken, resumed: bool},
     RejectJoin{reason: RejectJoinReason},
     
     // Lobby
     #[serde(rename = "lobbyName")]
     RoomName{name: String},
     #[serde(rename_all = "camelCase")]
+    RoomAccess{private: bool, has_password: bool},
+    InviteCode{code: String},
+    #[serde(rename_all = "camelCase")]
     YourId{player_id: RoomClientID},
     #[serde(rename_all = "camelCase")]
     LobbyClients{clients: VecMap<RoomClientID, LobbyClient>},
@@ -90,6 +106,9 @@ pub enum ToClientPacket{
     RoleList{role_list: RoleList},
     #[serde(rename_all = "camelCase")]
     RoleOutline{index: u8, role_outline: RoleOutline},
+    /// Answers [`ToServerPacket::AnalyzeRoleList`]
+    RoleListAnalysis{analysis: RoleListAnalysis},
+    RoleListConstraints{constraints: Vec<RoleListConstraint>},
     #[serde(rename_all = "camelCase")]
     PhaseTime{phase: PhaseType, time: u16},
     #[serde(rename_all = "camelCase")]
@@ -128,6 +147,12 @@ pub enum ToClientPacket{
     YourAllowedControllers{
         save: VecMap<ControllerID, SavedController>
     },
+    /// Controllers that were added or changed, and those that were removed, since the client was last sent its controllers
+    #[serde(rename_all = "camelCase")]
+    YourAllowedControllersUpdate{
+        changed: VecMap<ControllerID, SavedController>,
+        removed: Vec<ControllerID>,
+    },
 
     #[serde(rename_all = "camelCase")]
     YourRoleLabels{role_labels: VecMap<PlayerIndex, Role>},
@@ -163,22 +188,32 @@ pub enum RejectJoinReason {
     RoomFull,
     RoomDoesntExist,
     ServerBusy,
+    /// The client's IP address created as many rooms as it's allowed recently
+    CreatingRoomsTooFast,
 
     PlayerTaken,
     PlayerDoesntExist,
+
+    PasswordRequired,
+    WrongPassword,
+    InviteRequired,
+    InvalidInviteCode,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(tag = "type", rename_all = "camelCase")]
 pub enum ToServerPacket{
     Ping,
+    #[serde(rename_all = "camelCase")]
+    Hello{protocol_version: u32, #[serde(default)] capabilities: VecSet<Capability>},
     // Pre Lobby
     #[serde(rename = "lobbyListRequest")]
     RoomListRequest,
+    /// `last_sequence` is the number of the last packet the client got from the room, if it still has what the room sent it
     #[serde(rename_all = "camelCase")]
-    ReJoin{room_code: RoomCode, player_id: RoomClientID},
+    ReJoin{room_code: RoomCode, player_id: RoomClientID, reconnect_token: ReconnectToken, #[serde(default)] last_sequence: Option<u64>},
     #[serde(rename_all = "camelCase")]
-    Join{room_code: RoomCode},
+    Join{room_code: RoomCode, password: Option<Secret>, invite_code: Option<Secret>},
     Host,
     Leave,
     #[serde(rename_all = "camelCase")]
@@ -186,6 +221,10 @@ pub enum ToServerPacket{
     #[serde(rename_all = "camelCase")]
     SetPlayerHost{player_id: RoomClientID},
     RelinquishHost,
+    HostAddBot,
+    HostSetRoomPrivate{private: bool},
+    HostSetRoomPassword{password: Option<Secret>},
+    HostCreateInviteCode,
 
     // Lobby
     SendLobbyMessage{text: String},
@@ -203,6 +242,10 @@ pub enum ToServerPacket{
     SetRoleOutline{index: u8, role_outline: RoleOutline},
     #[serde(rename_all = "camelCase")]
     SimplifyRoleList,
+    /// Asks for a [`ToClientPacket::RoleListAnalysis`] of the lobby's role list and enabled roles
+    AnalyzeRoleList,
+    /// At most [`MAX_ROLE_LIST_CONSTRAINTS`](crate::game::role_list_constraints::MAX_ROLE_LIST_CONSTRAINTS)
+    SetRoleListConstraints{constraints: Vec<RoleListConstraint>},
     #[serde(rename_all = "camelCase")]
     SetPhaseTime{phase: PhaseType, time: u16},
     #[serde(rename_all = "camelCase")]
@@ -0,0 +1,125 @@
+//! The hello exchange every client starts with.
+//!
+//! A client sends [`ToServerPacket::Hello`](crate::packet::ToServerPacket::Hello) with the protocol version it speaks
+//! and the optional [`Capability`]s it supports. The server answers with
+//! [`ToClientPacket::Hello`](crate::packet::ToClientPacket::Hello), carrying the capabilities both sides support,
+//! or with [`ToClientPacket::RejectHello`](crate::packet::ToClientPacket::RejectHello) if it can't talk to the client.
+//! Until a client's hello is accepted, every packet from it other than pings is rejected.
+//!
+//! Optional packets are only sent to clients with the capability they need, see [`Capability::of`].
+//! Packets are JSON text frames, unless the client agrees to [`Capability::MessagePack`], see [`WireFormat`].
+
+use serde::{Deserialize, Serialize};
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::{packet::{ToClientPacket, ToServerPacket}, vec_set::VecSet};
+
+/// Raised whenever a packet changes in a way older clients can't understand
+///
+/// 2: `yourAllowedControllers` is only sent in full on joining, after that changes come in `yourAllowedControllersUpdate`
+/// 3: `rejectStart` reasons are objects tagged with `type`, with details of what stopped the game from starting
+pub const PROTOCOL_VERSION: u32 = 3;
+/// The oldest protocol version this server still speaks
+pub const MIN_PROTOCOL_VERSION: u32 = 3;
+
+/// Optional packet kinds, which a client only gets if it says it supports them
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Capability {
+    /// `roomAccess` and `inviteCode`
+    RoomAccess,
+    /// Packets are sent to the client as MessagePack in binary frames, instead of JSON in text frames
+    MessagePack,
+    /// `serverAnnouncement` and `serverRestarting`
+    ServerNotices,
+    /// `roleListAnalysis`
+    RoleListAnalysis,
+    /// `roleListConstraints`
+    RoleListConstraints,
+    /// `yourAllowedControllersUpdate`. Clients without it are sent all of `yourAllowedControllers` whenever they change
+    AllowedControllersUpdate,
+    /// A capability this server doesn't know about, from a newer client. It's never agreed to.
+    #[serde(other)]
+    Unknown,
+}
+
+impl Capability {
+    /// Every capability this server supports
+    pub const ALL: [Capability; 6] = [
+        Capability::RoomAccess, Capability::MessagePack, Capability::ServerNotices,
+        Capability::RoleListAnalysis, Capability::RoleListConstraints, Capability::AllowedControllersUpdate
+    ];
+
+    /// The capability a client needs to be sent this packet, if any
+    pub fn of(packet: &ToClientPacket) -> Option<Self> {
+        match packet {
+            ToClientPacket::RoomAccess { .. } |
+            ToClientPacket::InviteCode { .. } => Some(Self::RoomAccess),
+            ToClientPacket::ServerAnnouncement { .. } |
+            ToClientPacket::ServerRestarting { .. } => Some(Self::ServerNotices),
+            ToClientPacket::RoleListAnalysis { .. } => Some(Self::RoleListAnalysis),
+            ToClientPacket::RoleListConstraints { .. } => Some(Self::RoleListConstraints),
+            ToClientPacket::YourAllowedControllersUpdate { .. } => Some(Self::AllowedControllersUpdate),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RejectHelloReason {
+    /// The client sent something else before saying hello, so it's probably from before versioning
+    HelloRequired,
+    /// The client should reload to get the current version
+    ClientTooOld,
+    /// The server hasn't been updated to the client's version yet
+    ClientTooNew,
+}
+
+/// The capabilities both sides support, if the server can talk to a client with this version
+pub fn negotiate(protocol_version: u32, capabilities: &VecSet<Capability>) -> Result<VecSet<Capability>, RejectHelloReason> {
+    if protocol_version < MIN_PROTOCOL_VERSION {
+        return Err(RejectHelloReason::ClientTooOld);
+    }
+    if protocol_version > PROTOCOL_VERSION {
+        return Err(RejectHelloReason::ClientTooNew);
+    }
+
+    Ok(Capability::ALL.into_iter().filter(|capability| capabilities.contains(capability)).collect())
+}
+
+/// How packets are written to a client
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WireFormat {
+    Json,
+    /// Much smaller than JSON for packets like `yourAllowedControllers` and `addChatMessages`,
+    /// which matters to players on slow mobile connections
+    MessagePack,
+}
+
+impl WireFormat {
+    pub fn agreed_to(capabilities: &VecSet<Capability>) -> Self {
+        if capabilities.contains(&Capability::MessagePack) {
+            Self::MessagePack
+        } else {
+            Self::Json
+        }
+    }
+
+    pub fn encode(self, packet: &impl Serialize) -> Result<Message, String> {
+        match self {
+            Self::Json => serde_json::to_string(packet).map(Message::text).map_err(|error| error.to_string()),
+            // Named, so structs are written as maps like JSON objects, which the tagged packet enums need
+            Self::MessagePack => rmp_serde::to_vec_named(packet).map(Message::binary).map_err(|error| error.to_string()),
+        }
+    }
+
+    /// Clients can send either format whatever they agreed to, text frames are JSON and binary frames are MessagePack
+    pub fn decode(message: &Message) -> Result<ToServerPacket, String> {
+        match message {
+            Message::Text(text) => serde_json::from_str(text.as_str()).map_err(|error| error.to_string()),
+            Message::Binary(bytes) => rmp_serde::from_slice(bytes).map_err(|error| error.to_string()),
+            _ => Err("not a data frame".to_string()),
+        }
+    }
+}
@@ -0,0 +1,189 @@
+//! Who can get into a room.
+//!
+//! A private room isn't in the room list. The host can set a password, and hand out invite codes
+//! that each let one client in without it. A private room without a password can only be joined with an invite code.
+//! Clients that were already in the room rejoin with their reconnect token instead.
+//!
+//! Passwords are only kept salted and hashed, so a snapshot of the room doesn't give them away.
+//! The passwords and invite codes clients send are [`Secret`]s, so they aren't logged either.
+
+use std::fmt::Debug;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{packet::RejectJoinReason, strings::TidyableString, vec_map::VecMap};
+
+use super::{reconnect_token::{random_secret, secrets_match, ReconnectToken}, RoomClientID};
+
+const MAX_PASSWORD_LENGTH: usize = 64;
+/// The oldest invite code stops working when another is created past this
+pub const MAX_INVITE_CODES: usize = 32;
+
+/// A password or invite code sent by a client
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(secret: String) -> Self {
+        Self(secret)
+    }
+    pub fn reveal(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Secrets are never logged
+impl Debug for Secret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Secret(..)")
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RoomAccess {
+    private: bool,
+    password: Option<HashedPassword>,
+    /// Each invite code, and when it was created in milliseconds since the Unix epoch
+    #[serde(deserialize_with = "deserialize_invite_codes")]
+    invite_codes: VecMap<String, i64>,
+    /// The token each client was last given, keyed by their ID
+    reconnect_tokens: VecMap<RoomClientID, ReconnectToken>,
+}
+
+impl RoomAccess {
+    pub fn is_private(&self) -> bool {
+        self.private
+    }
+    pub fn set_private(&mut self, private: bool) {
+        self.private = private;
+    }
+    pub fn has_password(&self) -> bool {
+        self.password.is_some()
+    }
+    /// An empty password removes it
+    pub fn set_password(&mut self, password: Option<String>) {
+        self.password = password
+            .map(|password| password.trim_whitespace().truncate(MAX_PASSWORD_LENGTH))
+            .filter(|password| !password.is_empty())
+            .map(|password| HashedPassword::new(&password));
+    }
+
+    pub fn create_invite_code(&mut self) -> String {
+        let newest = self.invite_codes.values().max().copied();
+        if self.invite_codes.len() >= MAX_INVITE_CODES {
+            let oldest = self.invite_codes.iter().min_by_key(|(_, created)| **created).map(|(code, _)| code.clone());
+            if let Some(oldest) = oldest {
+                self.invite_codes.remove(&oldest);
+            }
+        }
+
+        // Codes created in the same millisecond still get different times, so it's always clear which is oldest
+        let now = chrono::Utc::now().timestamp_millis();
+        let created = newest.map_or(now, |newest| now.max(newest.saturating_add(1)));
+        let code = random_secret::<5>();
+        self.invite_codes.insert(code.clone(), created);
+        code
+    }
+
+    /// Whether a new client can join with these.
+    /// If they include an invite code, it should be used up with [`RoomAccess::use_invite_code`] once the client has joined.
+    pub fn check_join(&self, password: Option<&str>, invite_code: Option<&str>) -> Result<(), RejectJoinReason> {
+        if let Some(invite_code) = invite_code {
+            return if self.invite_codes.contains_key(&invite_code.to_string()) {
+                Ok(())
+            } else {
+                Err(RejectJoinReason::InvalidInviteCode)
+            };
+        }
+
+        match (&self.password, password) {
+            (Some(hashed), Some(given)) if hashed.matches(given) => Ok(()),
+            (Some(_), Some(_)) => Err(RejectJoinReason::WrongPassword),
+            (Some(_), None) => Err(RejectJoinReason::PasswordRequired),
+            (None, _) if self.private => Err(RejectJoinReason::InviteRequired),
+            (None, _) => Ok(()),
+        }
+    }
+    pub fn use_invite_code(&mut self, invite_code: &str) {
+        self.invite_codes.remove(&invite_code.to_string());
+    }
+
+    /// Gives the client a new token, so an old one can't be used to take its place
+    pub fn new_reconnect_token(&mut self, id: RoomClientID) -> ReconnectToken {
+        let token = ReconnectToken::generate();
+        self.reconnect_tokens.insert(id, token.clone());
+        token
+    }
+    pub fn check_rejoin(&self, id: RoomClientID, token: &ReconnectToken) -> bool {
+        self.reconnect_tokens.get(&id).is_some_and(|secret| secret.matches(token))
+    }
+    /// Called once a client has left for good
+    pub fn forget_client(&mut self, id: RoomClientID) {
+        self.reconnect_tokens.remove(&id);
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(from = "SavedPassword")]
+struct HashedPassword {
+    salt: String,
+    hash: String,
+}
+
+impl HashedPassword {
+    fn new(password: &str) -> Self {
+        let salt = random_secret::<16>();
+        Self { hash: Self::hash(&salt, password), salt }
+    }
+
+    fn matches(&self, given: &str) -> bool {
+        secrets_match(&self.hash, &Self::hash(&self.salt, given))
+    }
+
+    fn hash(salt: &str, password: &str) -> String {
+        Sha256::new().chain_update(salt).chain_update(password).finalize()
+            .iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SavedPassword {
+    Hashed { salt: String, hash: String },
+    /// Rooms saved before passwords were hashed
+    Plain(String),
+}
+
+impl From<SavedPassword> for HashedPassword {
+    fn from(saved: SavedPassword) -> Self {
+        match saved {
+            SavedPassword::Hashed { salt, hash } => Self { salt, hash },
+            SavedPassword::Plain(password) => Self::new(&password),
+        }
+    }
+}
+
+/// Passwords are secret, so they're never logged
+impl Debug for HashedPassword {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "HashedPassword(..)")
+    }
+}
+
+/// Rooms saved before invite codes were dated have a list of codes, which are all treated as the oldest
+fn deserialize_invite_codes<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<VecMap<String, i64>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum SavedInviteCodes {
+        Dated(VecMap<String, i64>),
+        Undated(Vec<String>),
+    }
+
+    Ok(match SavedInviteCodes::deserialize(deserializer)? {
+        SavedInviteCodes::Dated(codes) => codes,
+        SavedInviteCodes::Undated(codes) => codes.into_iter().map(|code| (code, 0)).collect(),
+    })
+}
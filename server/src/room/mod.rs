@@ -1,6 +1,10 @@
 
+pub mod access;
 pub mod on_client_message;
 pub mod name_validation;
+pub mod reconnect_token;
+pub mod snapshot;
+pub mod task;
 
 use std::time::Duration;
 
@@ -1,12 +1,9 @@
-use std::time::{Duration, Instant};
+use std::time::Instant;
 
-use crate::{game::on_client_message::GameClientMessageResult, lobby::on_client_message::LobbyClientMessageResult, log, packet::{ToClientPacket, ToServerPacket}, websocket_connections::connection::ClientSender};
+use crate::{config::ServerConfig, game::on_client_message::GameClientMessageResult, lobby::on_client_message::LobbyClientMessageResult, log, metrics, packet::{ToClientPacket, ToServerPacket}, websocket_connections::connection::ClientSender};
 
 use super::{RoomClientID, Room};
 
-pub const MESSAGE_PER_SECOND_LIMIT: u16 = 1;
-pub const MESSAGE_PER_SECOND_LIMIT_TIME: Duration = Duration::from_secs(10);
-
 pub enum RoomClientMessageResult {
     LobbyAction(LobbyClientMessageResult),
     GameAction(GameClientMessageResult),
@@ -29,16 +26,18 @@ impl Room {
                     return RoomClientMessageResult::None;
                 };
 
+                let config = ServerConfig::get();
                 let now = Instant::now();
                 while let Some(time) = last_message_times.front() {
-                    if now.duration_since(*time) > MESSAGE_PER_SECOND_LIMIT_TIME {
+                    if now.duration_since(*time) > config.message_rate_limit_window() {
                         last_message_times.pop_front();
                     } else {
                         break;
                     }
                 }
-                if last_message_times.len() as u64 >= MESSAGE_PER_SECOND_LIMIT_TIME.as_secs().saturating_mul(MESSAGE_PER_SECOND_LIMIT.into()) {
+                if last_message_times.len() as u64 >= config.message_rate_limit_window_secs.saturating_mul(config.message_rate_limit.into()) {
                     send.send(ToClientPacket::RateLimitExceeded);
+                    metrics::rate_limit_exceeded();
                     return RoomClientMessageResult::None;
                 }
                 last_message_times.push_back(now);
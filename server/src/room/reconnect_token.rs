@@ -0,0 +1,38 @@
+use std::fmt::Debug;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// An unguessable secret a client is given every time it joins a room.
+/// Rejoining needs the latest one, so nobody else can take a disconnected client's place.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReconnectToken(String);
+
+impl ReconnectToken {
+    pub fn generate() -> Self {
+        Self(random_secret::<16>())
+    }
+
+    pub fn matches(&self, token: &ReconnectToken) -> bool {
+        secrets_match(&self.0, &token.0)
+    }
+}
+
+/// `N` random bytes, written in hex
+pub(super) fn random_secret<const N: usize>() -> String {
+    let bytes: [u8; N] = rand::rng().random();
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Takes as long to compare however much of `given` is right, so a secret can't be guessed a character at a time
+pub(super) fn secrets_match(secret: &str, given: &str) -> bool {
+    secret.len() == given.len() &&
+        secret.bytes().zip(given.bytes()).fold(0, |difference, (a, b)| difference | (a ^ b)) == 0
+}
+
+/// Tokens are secret, so they're never logged
+impl Debug for ReconnectToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ReconnectToken(..)")
+    }
+}
@@ -0,0 +1,197 @@
+//! Rooms are snapshotted to disk so they survive the server restarting.
+//!
+//! A lobby is saved as its settings and clients.
+//! A game is saved as its [`Replay`], which rebuilds the exact same game when played back,
+//! along with a [`GameCheckpoint`] of where it was. A game that plays back to somewhere else isn't restored,
+//! and neither is one whose play back fails or panics; each of those is logged as an error.
+//! Every client of a restored room has lost connection, so they can get back in with `ReJoin`,
+//! using the reconnect token they were last given. Clients of a restored lobby have `restored_lobby_disconnect_timer_secs` to,
+//! rather than the few seconds a lobby usually waits. Private rooms stay private.
+
+use std::{collections::HashMap, fs::File, io::{self, BufReader, BufWriter}, panic::{self, AssertUnwindSafe}, path::Path, time::Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    client_connection::ClientConnection, config::ServerConfig,
+    game::{phase::PhaseType, player::PlayerReference, replay::{playback::ReplayError, Replay}, role::Role, role_list_analysis::CachedAnalysis, settings::Settings, Game},
+    lobby::{lobby_client::{LobbyClient, LobbyClientType, Ready}, Lobby}, log,
+    vec_map::VecMap, websocket_listener::RoomCode
+};
+
+use super::{access::RoomAccess, Room, RoomClientID};
+
+const ROOMS_FILE_NAME: &str = "rooms.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum RoomSnapshot {
+    #[serde(rename_all = "camelCase")]
+    Lobby {
+        name: String,
+        settings: Settings,
+        clients: VecMap<RoomClientID, LobbyClientSnapshot>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Game {
+        replay: Replay,
+        /// Missing from games saved before checkpoints were
+        #[serde(default)]
+        checkpoint: Option<GameCheckpoint>,
+    },
+}
+
+/// Where a game was when it was saved, to check its replay plays back to the same place
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GameCheckpoint {
+    pub day: u8,
+    pub phase: PhaseType,
+    /// Each player's role, and whether they're alive, in player index order
+    pub players: Vec<(Role, bool)>,
+}
+
+impl GameCheckpoint {
+    pub fn new(game: &Game) -> Self {
+        Self {
+            day: game.day_number(),
+            phase: game.current_phase().phase(),
+            players: PlayerReference::all_players(game).map(|player| (player.role(game), player.alive(game))).collect(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum RestoreError {
+    Replay(ReplayError),
+    /// Playing the replay back panicked
+    Panicked,
+    /// The replay played back to somewhere other than where the game was saved
+    Diverged { saved: GameCheckpoint, restored: GameCheckpoint },
+}
+
+/// A room as it's saved to disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedRoom {
+    #[serde(flatten)]
+    pub snapshot: RoomSnapshot,
+    #[serde(flatten)]
+    pub access: RoomAccess,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LobbyClientSnapshot {
+    pub ready: Ready,
+    pub client_type: LobbyClientType,
+    #[serde(default)]
+    pub bot: bool,
+}
+
+impl Room {
+    pub fn snapshot(&self) -> RoomSnapshot {
+        match self {
+            Room::Lobby(lobby) => RoomSnapshot::Lobby {
+                name: lobby.name.clone(),
+                settings: lobby.settings.clone(),
+                clients: lobby.clients.iter()
+                    .map(|(id, client)| (*id, LobbyClientSnapshot {
+                        ready: client.ready.clone(),
+                        client_type: client.client_type.clone(),
+                        bot: client.is_bot(),
+                    }))
+                    .collect(),
+            },
+            Room::Game(game) => RoomSnapshot::Game {
+                replay: game.replay().clone(),
+                checkpoint: Some(GameCheckpoint::new(game)),
+            },
+        }
+    }
+
+    pub fn restore(snapshot: RoomSnapshot) -> Result<Room, RestoreError> {
+        match snapshot {
+            RoomSnapshot::Lobby { name, settings, clients } => {
+                Ok(Room::Lobby(Lobby {
+                    name,
+                    settings,
+                    clients: clients.into_iter()
+                        .map(|(id, client)| (id, LobbyClient {
+                            connection: if client.bot {
+                                ClientConnection::Bot
+                            } else {
+                                ClientConnection::CouldReconnect {
+                                    disconnect_timer: ServerConfig::get().restored_lobby_disconnect_timer(),
+                                    sender: None
+                                }
+                            },
+                            ready: client.ready,
+                            client_type: client.client_type,
+                            last_message_times: Default::default(),
+                        }))
+                        .collect(),
+                    role_list_analysis: CachedAnalysis::default(),
+                }))
+            },
+            RoomSnapshot::Game { replay, checkpoint } => {
+                let game = panic::catch_unwind(AssertUnwindSafe(|| Game::from_replay(replay)))
+                    .map_err(|_| RestoreError::Panicked)?
+                    .map_err(RestoreError::Replay)?;
+
+                if let Some(saved) = checkpoint {
+                    let restored = GameCheckpoint::new(&game);
+                    if restored != saved {
+                        return Err(RestoreError::Diverged { saved, restored });
+                    }
+                }
+                Ok(Room::Game(game))
+            },
+        }
+    }
+}
+
+/// Writes every room to `directory`, replacing the last snapshot
+pub fn save_rooms(directory: &Path, rooms: &VecMap<RoomCode, SavedRoom>) -> io::Result<()> {
+    let start = Instant::now();
+
+    std::fs::create_dir_all(directory)?;
+
+    // Write to a temporary file first so a crash while writing doesn't lose the last snapshot
+    let temporary_path = directory.join(format!("{ROOMS_FILE_NAME}.tmp"));
+    serde_json::to_writer(BufWriter::new(File::create(&temporary_path)?), rooms)?;
+    std::fs::rename(temporary_path, directory.join(ROOMS_FILE_NAME))?;
+
+    log!(info "Snapshot"; "Saved {} rooms in {:?}", rooms.len(), start.elapsed());
+
+    Ok(())
+}
+
+/// Rebuilds every room saved in `directory`, skipping and logging any that can't be rebuilt
+pub fn load_rooms(directory: &Path) -> io::Result<HashMap<RoomCode, (Room, RoomAccess)>> {
+    let path = directory.join(ROOMS_FILE_NAME);
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+
+    let saved_rooms: VecMap<RoomCode, SavedRoom> = serde_json::from_reader(BufReader::new(File::open(path)?))?;
+
+    let saved = saved_rooms.len();
+    let mut rooms = HashMap::new();
+    for (room_code, saved_room) in saved_rooms {
+        match Room::restore(saved_room.snapshot) {
+            Ok(room) => {
+                rooms.insert(room_code, (room, saved_room.access));
+            },
+            Err(err) => log!(error "Snapshot"; "Couldn't restore room {room_code}: {err:?}"),
+        }
+    }
+
+    if rooms.len() < saved {
+        log!(error "Snapshot"; "Restored only {} of {saved} rooms", rooms.len());
+    } else {
+        log!(important "Snapshot"; "Restored {} rooms", rooms.len());
+    }
+
+    Ok(rooms)
+}
@@ -0,0 +1,497 @@
+//! Every room runs on its own task, with its own event channel and tick loop,
+//! so a slow room doesn't hold up any other room.
+//!
+//! The listener sends [`RoomEvent`]s to a room through its [`RoomHandle`].
+//! The room tells the listener when it removes a client or closes with [`RoomUpdate`]s.
+//! If handling an event panics, only that room is closed and the rest of the server keeps running.
+//!
+//! Every client in the room has an [`Outbox`], which lets a client that rejoins be sent only what it missed.
+
+use std::{cell::Cell, collections::{BTreeSet, HashMap}, net::SocketAddr, panic::{self, AssertUnwindSafe}, path::PathBuf, sync::{atomic::{AtomicBool, Ordering}, Arc, Mutex}, time::Duration};
+
+use serde::Serialize;
+use tokio::{sync::{mpsc::{self, UnboundedReceiver, UnboundedSender}, oneshot}, time::{Instant, MissedTickBehavior}};
+
+use crate::{
+    config::ServerConfig, game::{game_conclusion::GameConclusion, on_client_message::GameClientMessageResult, phase::PhaseType, Game},
+    lobby::on_client_message::LobbyClientMessageResult, log::LogContext, log, metrics,
+    packet::{RejectJoinReason, RoomPreviewData, ToClientPacket, ToServerPacket},
+    websocket_connections::{connection::ClientSender, heartbeat::ConnectionQuality, outbox::Outbox, ForceLock}, websocket_listener::RoomCode
+};
+
+use super::{access::{RoomAccess, Secret}, on_client_message::RoomClientMessageResult, reconnect_token::ReconnectToken, snapshot::SavedRoom, JoinRoomClientResult, RemoveRoomClientResult, Room, RoomClientID, RoomState};
+
+thread_local! {
+    static HANDLING_ROOM_EVENT: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Whether this thread is in the middle of handling an event for a room.
+/// A panic while this is true only closes that room.
+pub fn is_handling_room_event() -> bool {
+    HANDLING_ROOM_EVENT.get()
+}
+
+/// Clients are identified by their address, so a client's join and the packets it sends afterwards
+/// always reach the room in the order they were sent.
+pub enum RoomEvent {
+    Join { address: SocketAddr, sender: ClientSender, password: Option<Secret>, invite_code: Option<Secret> },
+    Rejoin { address: SocketAddr, sender: ClientSender, room_client_id: RoomClientID, reconnect_token: ReconnectToken, last_sequence: Option<u64> },
+    Packet { address: SocketAddr, packet: ToServerPacket },
+    Leave { address: SocketAddr },
+    LoseConnection { address: SocketAddr },
+    Snapshot { reply: oneshot::Sender<SavedRoom> },
+    /// From an operator, see [`crate::websocket_connections::admin`]
+    Inspect { reply: oneshot::Sender<RoomInspection> },
+    /// From an operator, who can kick anyone without being a host
+    Kick { room_client_id: RoomClientID },
+    /// From an operator
+    Close,
+}
+
+/// What an operator sees of a room
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RoomInspection {
+    pub preview: RoomPreviewData,
+    pub private: bool,
+    /// `None` in the lobby
+    pub day: Option<u8>,
+    /// `None` in the lobby
+    pub phase: Option<PhaseType>,
+    pub clients: Vec<InspectedClient>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct InspectedClient {
+    pub id: RoomClientID,
+    /// `None` for spectators
+    pub name: Option<String>,
+    pub host: bool,
+    /// `None` unless the client is connected
+    pub address: Option<SocketAddr>,
+    /// `None` unless the client is connected
+    pub connection_quality: Option<ConnectionQuality>,
+}
+
+pub enum RoomUpdate {
+    /// The room removed a client, or didn't let it in
+    ClientRemoved { room_code: RoomCode, address: SocketAddr },
+    Closed { room_code: RoomCode },
+}
+
+#[derive(Clone)]
+pub struct RoomHandle {
+    events: UnboundedSender<RoomEvent>,
+    preview: Arc<Mutex<RoomPreviewData>>,
+    private: Arc<AtomicBool>,
+}
+
+impl RoomHandle {
+    /// Starts a task that runs `room` until it closes, or until every handle to it is dropped
+    pub fn spawn(room_code: RoomCode, room: Room, access: RoomAccess, updates: UnboundedSender<RoomUpdate>, replay_directory: Option<PathBuf>) -> Self {
+        let (events, receiver) = mpsc::unbounded_channel();
+        let preview = Arc::new(Mutex::new(room.get_preview_data()));
+        let private = Arc::new(AtomicBool::new(access.is_private()));
+        // A restored game's length is measured from when it was restored
+        let game_started_at = matches!(&room, Room::Game(game) if game.ticking).then(Instant::now);
+
+        let task = RoomTask {
+            room_code,
+            room,
+            clients: HashMap::new(),
+            outboxes: HashMap::new(),
+            access,
+            updates,
+            replay_directory,
+            preview: preview.clone(),
+            private: private.clone(),
+            game_started_at,
+        };
+        tokio::spawn(task.run(receiver));
+
+        Self { events, preview, private }
+    }
+
+    /// Returns false if the room has closed
+    pub fn send(&self, event: RoomEvent) -> bool {
+        self.events.send(event).is_ok()
+    }
+
+    pub fn preview(&self) -> RoomPreviewData {
+        self.preview.force_lock().clone()
+    }
+
+    /// Private rooms aren't in the room list
+    pub fn is_private(&self) -> bool {
+        self.private.load(Ordering::Relaxed)
+    }
+
+    /// Returns `None` if the room has closed
+    pub async fn snapshot(&self) -> Option<SavedRoom> {
+        let (reply, receiver) = oneshot::channel();
+        if !self.send(RoomEvent::Snapshot { reply }) {
+            return None;
+        }
+        receiver.await.ok()
+    }
+
+    /// Returns `None` if the room has closed
+    pub async fn inspect(&self) -> Option<RoomInspection> {
+        let (reply, receiver) = oneshot::channel();
+        if !self.send(RoomEvent::Inspect { reply }) {
+            return None;
+        }
+        receiver.await.ok()
+    }
+}
+
+struct RoomTaskClient {
+    id: RoomClientID,
+    sender: ClientSender,
+}
+
+struct RoomTask {
+    room_code: RoomCode,
+    room: Room,
+    /// Clients that are connected to this room through the listener
+    clients: HashMap<SocketAddr, RoomTaskClient>,
+    /// Kept while a client is disconnected, so it can be sent what it missed when it rejoins
+    outboxes: HashMap<RoomClientID, Arc<Mutex<Outbox>>>,
+    access: RoomAccess,
+    updates: UnboundedSender<RoomUpdate>,
+    replay_directory: Option<PathBuf>,
+    preview: Arc<Mutex<RoomPreviewData>>,
+    private: Arc<AtomicBool>,
+    /// When the current game started, until its end is recorded
+    game_started_at: Option<Instant>,
+}
+
+enum RoomFlow {
+    Continue,
+    Close,
+}
+
+impl RoomTask {
+    async fn run(mut self, mut events: UnboundedReceiver<RoomEvent>) {
+        let mut tick_interval = tokio::time::interval(ServerConfig::get().tick_interval());
+        tick_interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut last_tick = Instant::now();
+
+        loop {
+            let result = tokio::select! {
+                event = events.recv() => match event {
+                    Some(event) => self.catch_panic(|task| task.handle_event(event)),
+                    // Every handle was dropped, so the server is shutting down
+                    None => return,
+                },
+                _ = tick_interval.tick() => {
+                    let time_passed = last_tick.elapsed();
+                    last_tick = Instant::now();
+                    self.catch_panic(|task| task.tick(time_passed))
+                }
+            };
+
+            match result {
+                Some(RoomFlow::Continue) => {
+                    self.record_game_end();
+                    *self.preview.force_lock() = self.room.get_preview_data();
+                },
+                Some(RoomFlow::Close) => {
+                    self.close();
+                    return;
+                },
+                None => {
+                    log!(error "Room"; "Room {} panicked, closing it", self.room_code);
+                    self.close();
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Returns `None` if `handle` panicked
+    fn catch_panic(&mut self, handle: impl FnOnce(&mut Self) -> RoomFlow) -> Option<RoomFlow> {
+        HANDLING_ROOM_EVENT.set(true);
+        LogContext::set(self.log_context());
+        let result = panic::catch_unwind(AssertUnwindSafe(|| handle(self)));
+        LogContext::set(LogContext::NONE);
+        HANDLING_ROOM_EVENT.set(false);
+        result.ok()
+    }
+
+    fn log_context(&self) -> LogContext {
+        let (day, phase) = match &self.room {
+            Room::Game(game) => (Some(game.day_number()), Some(game.current_phase().phase())),
+            Room::Lobby(_) => (None, None),
+        };
+        LogContext { room_code: Some(self.room_code), client_id: None, day, phase }
+    }
+
+    fn handle_event(&mut self, event: RoomEvent) -> RoomFlow {
+        match event {
+            RoomEvent::Join { address, sender, password, invite_code } => {
+                let outbox = Arc::new(Mutex::new(Outbox::new()));
+                outbox.force_lock().hold();
+                let room_sender = sender.with_outbox(outbox.clone());
+                let result = self.access.check_join(password.as_ref().map(Secret::reveal), invite_code.as_ref().map(Secret::reveal))
+                    .and_then(|_| self.room.join_client(&room_sender));
+                if let (Ok(_), Some(invite_code)) = (&result, invite_code) {
+                    self.access.use_invite_code(invite_code.reveal());
+                }
+                self.on_join_result(address, sender, outbox, result, None);
+            },
+            RoomEvent::Rejoin { address, sender, room_client_id, reconnect_token, last_sequence } => {
+                let existing_outbox = self.outboxes.get(&room_client_id).cloned();
+                let missed = last_sequence
+                    .zip(existing_outbox.as_ref())
+                    .and_then(|(last_sequence, outbox)| outbox.force_lock().missed_since(last_sequence));
+                // A client that can't be sent just what it missed is sent everything, numbered from the start again
+                let outbox = existing_outbox.filter(|_| missed.is_some()).unwrap_or_default();
+                outbox.force_lock().hold();
+                let room_sender = sender.with_outbox(outbox.clone());
+
+                let result = if self.access.check_rejoin(room_client_id, &reconnect_token) {
+                    self.room.rejoin_client(&room_sender, room_client_id)
+                } else {
+                    log!(info "Room"; "{address} tried to rejoin as {room_client_id} without its reconnect token");
+                    Err(RejectJoinReason::PlayerTaken)
+                };
+                metrics::rejoined(result.is_ok());
+                self.on_join_result(address, sender, outbox, result, missed);
+            },
+            RoomEvent::Packet { address, packet } => {
+                let Some(client) = self.clients.get(&address) else {
+                    log!(error "Room"; "Room {} received a packet from {address}, which isn't in it", self.room_code);
+                    return RoomFlow::Continue;
+                };
+                let (room_client_id, sender) = (client.id, client.sender.clone());
+                LogContext::set_client(Some(room_client_id));
+
+                return self.on_packet(room_client_id, &sender, packet);
+            },
+            RoomEvent::Leave { address } => {
+                let Some(client) = self.clients.remove(&address) else { return RoomFlow::Continue };
+                LogContext::set_client(Some(client.id));
+                self.access.forget_client(client.id);
+                self.outboxes.remove(&client.id);
+                if let RemoveRoomClientResult::RoomShouldClose = self.room.remove_client(client.id) {
+                    return RoomFlow::Close;
+                }
+            },
+            RoomEvent::LoseConnection { address } => {
+                let Some(client) = self.clients.remove(&address) else { return RoomFlow::Continue };
+                LogContext::set_client(Some(client.id));
+                if let RemoveRoomClientResult::RoomShouldClose = self.room.remove_client_rejoinable(client.id) {
+                    return RoomFlow::Close;
+                }
+            },
+            RoomEvent::Snapshot { reply } => {
+                let _ = reply.send(SavedRoom { snapshot: self.room.snapshot(), access: self.access.clone() });
+            },
+            RoomEvent::Inspect { reply } => {
+                let _ = reply.send(self.inspect());
+            },
+            RoomEvent::Kick { room_client_id } => {
+                log!(important "Room"; "An operator kicked {room_client_id}");
+                return self.kick(room_client_id);
+            },
+            RoomEvent::Close => {
+                log!(important "Room"; "An operator closed {}", self.room_code);
+                return RoomFlow::Close;
+            },
+        }
+
+        RoomFlow::Continue
+    }
+
+    /// `sender` is the listener's, which isn't numbered. `outbox` is held while the room handles the join,
+    /// so the client gets its answer before anything the room sent it meanwhile.
+    /// If `missed` is set, the client is sent only those packets instead of being initialized again.
+    fn on_join_result(
+        &mut self,
+        address: SocketAddr,
+        sender: ClientSender,
+        outbox: Arc<Mutex<Outbox>>,
+        result: Result<JoinRoomClientResult, RejectJoinReason>,
+        missed: Option<Vec<(u64, ToClientPacket)>>
+    ) {
+        match result {
+            Ok(JoinRoomClientResult { id, in_game, spectator }) => {
+                LogContext::set_client(Some(id));
+                let reconnect_token = self.access.new_reconnect_token(id);
+                let resumed = missed.is_some();
+                sender.send(ToClientPacket::AcceptJoin { room_code: self.room_code, in_game, player_id: id, spectator, reconnect_token, resumed });
+
+                let held = outbox.force_lock().release();
+                let room_sender = sender.with_outbox(outbox.clone());
+                if let Some(missed) = &missed {
+                    log!(info "Room"; "Resent {} missed packets to {id}", missed.len());
+                }
+                for (sequence, packet) in missed.into_iter().flatten().chain(held) {
+                    room_sender.resend(sequence, packet);
+                }
+                if !resumed {
+                    self.room.initialize_client(id, &room_sender);
+                }
+                // Access changes aren't sent to disconnected clients
+                room_sender.send(self.access_packet());
+
+                self.outboxes.insert(id, outbox);
+                self.clients.insert(address, RoomTaskClient { id, sender: room_sender });
+            }
+            Err(reason) => {
+                outbox.force_lock().release();
+                sender.send(ToClientPacket::RejectJoin { reason });
+                let _ = self.updates.send(RoomUpdate::ClientRemoved { room_code: self.room_code, address });
+            }
+        }
+    }
+
+    fn on_packet(&mut self, room_client_id: RoomClientID, sender: &ClientSender, packet: ToServerPacket) -> RoomFlow {
+        let packet = match packet {
+            ToServerPacket::Kick { player_id } => {
+                if !self.room.is_host(room_client_id) {return RoomFlow::Continue}
+                return self.kick(player_id);
+            },
+            ToServerPacket::HostSetRoomPrivate { private } => {
+                self.change_access(room_client_id, |access| access.set_private(private));
+                return RoomFlow::Continue;
+            },
+            ToServerPacket::HostSetRoomPassword { password } => {
+                self.change_access(room_client_id, |access| access.set_password(password.map(|password| password.reveal().to_string())));
+                return RoomFlow::Continue;
+            },
+            ToServerPacket::HostCreateInviteCode => {
+                if self.room.is_host(room_client_id) {
+                    sender.send(ToClientPacket::InviteCode { code: self.access.create_invite_code() });
+                }
+                return RoomFlow::Continue;
+            },
+            packet => packet,
+        };
+
+        match self.room.on_client_message(sender, room_client_id, packet) {
+            RoomClientMessageResult::LobbyAction(LobbyClientMessageResult::StartGame(game)) => {
+                log!(info "Room"; "Game started with room code {} and seed {}", self.room_code, game.seed());
+                metrics::game_started();
+                self.game_started_at = Some(Instant::now());
+
+                self.room = Room::Game(game);
+            },
+            RoomClientMessageResult::GameAction(GameClientMessageResult::BackToLobby(lobby)) => {
+                // A game abandoned before it ended isn't counted as ended
+                self.game_started_at = None;
+                if let Room::Game(game) = std::mem::replace(&mut self.room, Room::Lobby(lobby)) {
+                    self.save_replay(&game);
+                }
+            },
+            RoomClientMessageResult::GameAction(GameClientMessageResult::Close) |
+            RoomClientMessageResult::LobbyAction(LobbyClientMessageResult::Close) => {
+                return RoomFlow::Close;
+            },
+            _ => {}
+        }
+
+        RoomFlow::Continue
+    }
+
+    fn change_access(&mut self, host_id: RoomClientID, change: impl FnOnce(&mut RoomAccess)) {
+        if !self.room.is_host(host_id) {return}
+
+        change(&mut self.access);
+        self.private.store(self.access.is_private(), Ordering::Relaxed);
+
+        let packet = self.access_packet();
+        for client in self.clients.values() {
+            client.sender.send(packet.clone());
+        }
+    }
+
+    fn access_packet(&self) -> ToClientPacket {
+        ToClientPacket::RoomAccess { private: self.access.is_private(), has_password: self.access.has_password() }
+    }
+
+    fn kick(&mut self, kicked_id: RoomClientID) -> RoomFlow {
+        let kicked_address = self.clients.iter()
+            .find(|(_, client)| client.id == kicked_id)
+            .map(|(address, _)| *address);
+
+        if let Some(client) = kicked_address.and_then(|address| self.clients.remove(&address)) {
+            client.sender.send(ToClientPacket::RejectJoin { reason: RejectJoinReason::ServerBusy });
+            client.sender.send(ToClientPacket::ForcedOutsideRoom);
+        }
+        if let Some(address) = kicked_address {
+            let _ = self.updates.send(RoomUpdate::ClientRemoved { room_code: self.room_code, address });
+        }
+
+        // If the kicked client isn't connected, it's still removed from the room
+        self.access.forget_client(kicked_id);
+        self.outboxes.remove(&kicked_id);
+        match self.room.remove_client(kicked_id) {
+            RemoveRoomClientResult::RoomShouldClose => RoomFlow::Close,
+            RemoveRoomClientResult::Success |
+            RemoveRoomClientResult::ClientNotInRoom => RoomFlow::Continue,
+        }
+    }
+
+    fn inspect(&self) -> RoomInspection {
+        let preview = self.room.get_preview_data();
+        let (day, phase) = match &self.room {
+            Room::Game(game) => (Some(game.day_number()), Some(game.current_phase().phase())),
+            Room::Lobby(_) => (None, None),
+        };
+
+        // Spectators aren't in the preview, and disconnected players aren't in `clients`
+        let ids: BTreeSet<RoomClientID> = preview.players.iter().map(|(id, _)| *id)
+            .chain(self.clients.values().map(|client| client.id))
+            .collect();
+
+        let clients = ids.into_iter().map(|id| {
+            let connected = self.clients.iter().find(|(_, client)| client.id == id);
+            InspectedClient {
+                id,
+                name: preview.players.iter().find(|(player, _)| *player == id).map(|(_, name)| name.clone()),
+                host: self.room.is_host(id),
+                address: connected.map(|(address, _)| *address),
+                connection_quality: connected.and_then(|(_, client)| client.sender.connection_quality()),
+            }
+        }).collect();
+
+        RoomInspection { preview, private: self.access.is_private(), day, phase, clients }
+    }
+
+    fn tick(&mut self, time_passed: Duration) -> RoomFlow {
+        if self.room.tick(time_passed).close_room {
+            RoomFlow::Close
+        } else {
+            RoomFlow::Continue
+        }
+    }
+
+    /// Records how the current game ended, once it stops ticking
+    fn record_game_end(&mut self) {
+        let Room::Game(game) = &self.room else {return};
+        if game.ticking {return}
+        let Some(started_at) = self.game_started_at.take() else {return};
+
+        metrics::game_ended(&GameConclusion::get_premature_conclusion(game), started_at.elapsed());
+    }
+
+    fn close(&mut self) {
+        if let Room::Game(game) = &self.room {
+            self.save_replay(game);
+        }
+
+        let _ = self.updates.send(RoomUpdate::Closed { room_code: self.room_code });
+    }
+
+    fn save_replay(&self, game: &Game) {
+        let Some(replay_directory) = &self.replay_directory else {return};
+
+        match game.replay().save(replay_directory) {
+            Ok(path) => log!(info "Replay"; "Saved replay of {} to {}", self.room_code, path.display()),
+            Err(err) => log!(error "Replay"; "Failed to save replay of {}: {err}", self.room_code),
+        }
+    }
+}
@@ -0,0 +1,221 @@
+//! The operator console, a Unix socket at `admin_socket` that only the server's user can connect to.
+//! It's only available on Unix.
+//!
+//! Every line written to it is an [`AdminCommand`] as JSON, and is answered with an [`AdminResponse`] as JSON on one line.
+//! For example, with `socat`:
+//!
+//! ```text
+//! $ echo '{"type":"listRooms"}' | socat - UNIX-CONNECT:admin.sock
+//! $ echo '{"type":"restart","delaySecs":60}' | socat - UNIX-CONNECT:admin.sock
+//! ```
+
+use std::{fs::DirBuilder, io, net::IpAddr, os::unix::fs::{DirBuilderExt, PermissionsExt}, path::{Path, PathBuf}, sync::{Arc, Mutex}, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use tokio::{io::{AsyncBufReadExt, AsyncWriteExt, BufReader}, net::UnixListener, sync::{broadcast, Notify}};
+
+use crate::{
+    config::ServerConfig, log, packet::ToClientPacket, room::{task::{RoomEvent, RoomHandle, RoomInspection}, RoomClientID},
+    websocket_listener::{RoomCode, WebsocketListener}
+};
+
+use super::ForceLock;
+
+/// How long a room has to answer an inspection
+const INSPECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum AdminCommand {
+    ListRooms,
+    #[serde(rename_all = "camelCase")]
+    InspectRoom { room_code: RoomCode },
+    /// Moves everyone in the room back to the room list
+    #[serde(rename_all = "camelCase")]
+    CloseRoom { room_code: RoomCode },
+    #[serde(rename_all = "camelCase")]
+    Kick { room_code: RoomCode, player_id: RoomClientID },
+    /// Disconnects every client from `ip`, and refuses its connections for `duration_secs`,
+    /// or `ip_ban_duration_secs` if that isn't set
+    #[serde(rename_all = "camelCase")]
+    Ban { ip: IpAddr, duration_secs: Option<u64> },
+    /// Shown to every connected client
+    Announce { message: String },
+    /// Warns every connected client, then restarts the server after `delay_secs`.
+    /// Rooms are only kept through the restart if `data_directory` is set
+    #[serde(rename_all = "camelCase")]
+    Restart { delay_secs: u64 },
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum AdminResponse {
+    Rooms { rooms: Vec<AdminRoom> },
+    Room { room: AdminRoom },
+    Done,
+    Banned { disconnected: usize },
+    Announced { clients: usize },
+    #[serde(rename_all = "camelCase")]
+    Restarting { delay_secs: u64, rooms_kept: bool },
+    Error { message: String },
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminRoom {
+    pub room_code: RoomCode,
+    #[serde(flatten)]
+    pub inspection: RoomInspection,
+}
+
+impl AdminResponse {
+    fn error(message: impl Into<String>) -> Self {
+        Self::Error { message: message.into() }
+    }
+}
+
+/// Carries out `command`. `restart` is notified when the server should restart
+pub async fn execute(listener: &Arc<Mutex<WebsocketListener>>, restart: &Arc<Notify>, command: AdminCommand) -> AdminResponse {
+    log!(important "Admin"; "{:?}", command);
+
+    match command {
+        AdminCommand::ListRooms => {
+            let rooms: Vec<(RoomCode, RoomHandle)> = listener.force_lock().room_handles()
+                .map(|(room_code, room)| (room_code, room.clone()))
+                .collect();
+
+            let mut inspected = Vec::new();
+            for (room_code, room) in rooms {
+                if let Some(room) = inspect(room_code, &room).await {
+                    inspected.push(room);
+                }
+            }
+            inspected.sort_by_key(|room| room.room_code);
+            AdminResponse::Rooms { rooms: inspected }
+        },
+        AdminCommand::InspectRoom { room_code } => {
+            let Some(room) = listener.force_lock().get_room(&room_code).cloned() else {
+                return AdminResponse::error("That room doesn't exist");
+            };
+            match inspect(room_code, &room).await {
+                Some(room) => AdminResponse::Room { room },
+                None => AdminResponse::error("That room didn't answer"),
+            }
+        },
+        AdminCommand::CloseRoom { room_code } => send_to_room(listener, room_code, RoomEvent::Close),
+        AdminCommand::Kick { room_code, player_id } => send_to_room(listener, room_code, RoomEvent::Kick { room_client_id: player_id }),
+        AdminCommand::Ban { ip, duration_secs } => {
+            let duration = duration_secs.map_or(ServerConfig::get().ip_ban_duration(), Duration::from_secs);
+            let disconnected = listener.force_lock().ban(ip, duration);
+            log!(important "Admin"; "Banned {ip} for {} seconds, disconnecting {disconnected} clients", duration.as_secs());
+            AdminResponse::Banned { disconnected }
+        },
+        AdminCommand::Announce { message } => {
+            let clients = listener.force_lock().broadcast(ToClientPacket::ServerAnnouncement { message });
+            AdminResponse::Announced { clients }
+        },
+        AdminCommand::Restart { delay_secs } => {
+            let rooms_kept = {
+                let listener = listener.force_lock();
+                listener.broadcast(ToClientPacket::ServerRestarting { in_secs: delay_secs });
+                listener.keeps_rooms()
+            };
+
+            let restart = restart.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_secs(delay_secs)).await;
+                restart.notify_one();
+            });
+            AdminResponse::Restarting { delay_secs, rooms_kept }
+        },
+    }
+}
+
+async fn inspect(room_code: RoomCode, room: &RoomHandle) -> Option<AdminRoom> {
+    match tokio::time::timeout(INSPECT_TIMEOUT, room.inspect()).await {
+        Ok(Some(inspection)) => Some(AdminRoom { room_code, inspection }),
+        Ok(None) => None,
+        Err(_) => {
+            log!(error "Admin"; "Room {room_code} took too long to inspect");
+            None
+        }
+    }
+}
+
+fn send_to_room(listener: &Arc<Mutex<WebsocketListener>>, room_code: RoomCode, event: RoomEvent) -> AdminResponse {
+    match listener.force_lock().get_room(&room_code) {
+        Some(room) if room.send(event) => AdminResponse::Done,
+        _ => AdminResponse::error("That room doesn't exist"),
+    }
+}
+
+/// Binds the socket inside a directory only the server's user can open, and only moves it to `path` once it's private,
+/// so nobody else can connect while it still has the permissions the umask gave it
+fn bind_privately(path: &Path) -> io::Result<UnixListener> {
+    let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    let directory = path.with_file_name(format!(".{name}.{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    DirBuilder::new().mode(0o700).create(&directory)?;
+
+    let bound = directory.join("admin.sock");
+    let socket = UnixListener::bind(&bound)
+        .and_then(|socket| std::fs::set_permissions(&bound, std::fs::Permissions::from_mode(0o600)).map(|_| socket))
+        .and_then(|socket| std::fs::rename(&bound, path).map(|_| socket));
+    let _ = std::fs::remove_dir_all(&directory);
+    socket
+}
+
+/// Answers commands on the socket at `path` until `stop` is received, then closes every connection and removes the socket
+pub async fn serve(path: PathBuf, listener: Arc<Mutex<WebsocketListener>>, restart: Arc<Notify>, mut stop: broadcast::Receiver<()>) {
+    // Left over if the server didn't stop cleanly
+    let _ = std::fs::remove_file(&path);
+    let socket = match bind_privately(&path) {
+        Ok(socket) => socket,
+        Err(err) => {
+            log!(error "Admin"; "Failed to bind the admin socket to {}: {err}", path.display());
+            return;
+        }
+    };
+    log!(important "Admin"; "Listening on {}", path.display());
+
+    loop {
+        let stream = tokio::select! {
+            accepted = socket.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(err) => {
+                    log!(error "Admin"; "Failed to accept a connection: {err}");
+                    continue;
+                }
+            },
+            _ = stop.recv() => break,
+        };
+
+        let listener = listener.clone();
+        let restart = restart.clone();
+        let mut stop = stop.resubscribe();
+        tokio::spawn(async move {
+            let (reader, mut writer) = stream.into_split();
+            let mut lines = BufReader::new(reader).lines();
+            loop {
+                let line = tokio::select! {
+                    line = lines.next_line() => match line {
+                        Ok(Some(line)) => line,
+                        _ => break,
+                    },
+                    _ = stop.recv() => break,
+                };
+                if line.trim().is_empty() {continue}
+
+                let response = match serde_json::from_str(&line) {
+                    Ok(command) => execute(&listener, &restart, command).await,
+                    Err(err) => AdminResponse::error(format!("Invalid command: {err}")),
+                };
+                let Ok(mut response) = serde_json::to_string(&response) else {break};
+                response.push('\n');
+                if writer.write_all(response.as_bytes()).await.is_err() {break}
+            }
+        });
+    }
+
+    let _ = std::fs::remove_file(&path);
+}
@@ -1,18 +1,58 @@
-use std::net::SocketAddr;
+use std::{net::SocketAddr, sync::{Arc, Mutex}};
 
+use serde::Serialize;
 use tokio::sync::mpsc::UnboundedSender;
+use tokio_tungstenite::tungstenite::Message;
 
-use crate::packet::ToClientPacket;
+use crate::{packet::ToClientPacket, protocol::{Capability, WireFormat}, vec_set::VecSet};
+
+use super::{heartbeat::{ConnectionQuality, Heartbeat}, outbox::Outbox, ForceLock};
 
 #[derive(Debug, Clone)]
 pub struct Connection {
     tx: ClientSender,
-    address: SocketAddr
+    address: SocketAddr,
+    /// Agreed on in the client's hello, see [`crate::protocol`]
+    capabilities: Arc<Mutex<VecSet<Capability>>>,
+    heartbeat: Arc<Mutex<Heartbeat>>,
 }
 
 impl Connection {
-    pub fn new(tx: UnboundedSender<ToClientPacket>, address: SocketAddr) -> Self {
-        Self { tx: ClientSender { tx }, address }
+    pub fn new(tx: UnboundedSender<SequencedPacket>, address: SocketAddr) -> Self {
+        let heartbeat = Arc::new(Mutex::new(Heartbeat::new()));
+        let capabilities = Arc::new(Mutex::new(VecSet::new()));
+        Self {
+            tx: ClientSender { tx, outbox: None, heartbeat: Some(heartbeat.clone()), capabilities: Some(capabilities.clone()) },
+            address,
+            capabilities,
+            heartbeat,
+        }
+    }
+
+    pub fn set_capabilities(&self, capabilities: VecSet<Capability>) {
+        *self.capabilities.force_lock() = capabilities;
+    }
+    /// Whether this client can be sent the packet, optional packets need a capability
+    pub fn supports(&self, packet: &ToClientPacket) -> bool {
+        Capability::of(packet).is_none_or(|capability| self.capabilities.force_lock().contains(&capability))
+    }
+    /// Writes the packet in the format this client agreed to
+    pub fn encode(&self, packet: &impl Serialize) -> Result<Message, String> {
+        WireFormat::agreed_to(&self.capabilities.force_lock()).encode(packet)
+    }
+
+    /// Every frame from the client counts as an answer, pongs also measure the round trip
+    pub fn heard(&self, message: &Message) {
+        match message {
+            Message::Pong(payload) => self.heartbeat.force_lock().pong(payload),
+            _ => self.heartbeat.force_lock().heard(),
+        }
+    }
+    pub fn ping(&self) -> Message {
+        Message::Ping(self.heartbeat.force_lock().ping().into())
+    }
+    pub fn timed_out(&self) -> bool {
+        self.heartbeat.force_lock().timed_out()
     }
 
     pub fn address(&self) -> &SocketAddr {
@@ -33,13 +73,66 @@ impl PartialEq for Connection{
 }
 impl Eq for Connection {}
 
+/// A packet on its way to a client, numbered if the client is in a room, see [`Outbox`].
+/// The number is written alongside the packet's own fields.
+#[derive(Debug, Clone, Serialize)]
+pub struct SequencedPacket {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sequence: Option<u64>,
+    #[serde(flatten)]
+    pub packet: ToClientPacket,
+}
+
 #[derive(Debug, Clone)]
 pub struct ClientSender {
-    tx: UnboundedSender<ToClientPacket>
+    tx: UnboundedSender<SequencedPacket>,
+    outbox: Option<Arc<Mutex<Outbox>>>,
+    /// The connection's, if this sends to a websocket
+    heartbeat: Option<Arc<Mutex<Heartbeat>>>,
+    /// The connection's, if this sends to a websocket
+    capabilities: Option<Arc<Mutex<VecSet<Capability>>>>,
 }
 
 impl ClientSender {
+    pub fn new(tx: UnboundedSender<SequencedPacket>) -> Self {
+        Self { tx, outbox: None, heartbeat: None, capabilities: None }
+    }
+    /// Sends to the same client, numbering every packet into `outbox`
+    pub fn with_outbox(&self, outbox: Arc<Mutex<Outbox>>) -> Self {
+        Self { tx: self.tx.clone(), outbox: Some(outbox), heartbeat: self.heartbeat.clone(), capabilities: self.capabilities.clone() }
+    }
+    /// Whether the client agreed to `capability`. Senders that don't send to a websocket support everything
+    pub fn supports(&self, capability: Capability) -> bool {
+        self.capabilities.as_ref().is_none_or(|capabilities| capabilities.force_lock().contains(&capability))
+    }
+    /// `None` if this doesn't send to a websocket
+    pub fn connection_quality(&self) -> Option<ConnectionQuality> {
+        self.heartbeat.as_ref().map(|heartbeat| heartbeat.force_lock().quality())
+    }
+    /// If the outbox is [held](Outbox::hold), the packet is only numbered into it
     pub fn send(&self, message: ToClientPacket) {
-        let _ = self.tx.send(message);
+        let sequence = match &self.outbox {
+            Some(outbox) => {
+                let mut outbox = outbox.force_lock();
+                let sequence = outbox.push(message.clone());
+                if outbox.is_held() {
+                    return;
+                }
+                Some(sequence)
+            },
+            None => None,
+        };
+        let _ = self.tx.send(SequencedPacket { sequence, packet: message });
+    }
+    /// Numbers the packet into the outbox without sending it,
+    /// so a client that lost connection can be sent it when it rejoins
+    pub fn record(&self, message: ToClientPacket) {
+        if let Some(outbox) = &self.outbox {
+            outbox.force_lock().push(message);
+        }
+    }
+    /// Sends a packet the client missed, with the number it was given the first time
+    pub fn resend(&self, sequence: u64, message: ToClientPacket) {
+        let _ = self.tx.send(SequencedPacket { sequence: Some(sequence), packet: message });
     }
 }
\ No newline at end of file
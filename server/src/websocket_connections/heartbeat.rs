@@ -0,0 +1,113 @@
+//! The server pings every connection with websocket pings, which browsers answer on their own.
+//! A connection that hasn't been heard from in a while is closed,
+//! so a phone that went to sleep loses connection instead of looking connected for minutes.
+//!
+//! How long pings take to be answered is shown to hosts as the client's [`ConnectionQuality`].
+
+use std::time::Duration;
+
+use serde::Serialize;
+use tokio::time::Instant;
+
+use crate::config::ServerConfig;
+
+#[derive(Debug)]
+pub struct Heartbeat {
+    last_heard: Instant,
+    /// The payload of the latest ping that hasn't been answered yet, and when it was sent
+    unanswered_ping: Option<(u64, Instant)>,
+    /// When the oldest ping that hasn't been answered was sent.
+    /// Answering a ping answers every ping before it too, so only the latest one's payload is kept
+    unanswered_since: Option<Instant>,
+    next_ping: u64,
+    /// Averaged over the last few pings, so one slow answer doesn't make a connection look bad
+    round_trip_millis: Option<u64>,
+}
+
+impl Default for Heartbeat {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Heartbeat {
+    pub fn new() -> Self {
+        Self { last_heard: Instant::now(), unanswered_ping: None, unanswered_since: None, next_ping: 0, round_trip_millis: None }
+    }
+
+    /// Anything the client sends shows it's still there
+    pub fn heard(&mut self) {
+        self.last_heard = Instant::now();
+    }
+
+    /// The payload of a new ping. Only its answer is measured, but the connection
+    /// keeps lagging from the first ping that wasn't answered
+    pub fn ping(&mut self) -> Vec<u8> {
+        let payload = self.next_ping;
+        self.next_ping = self.next_ping.wrapping_add(1);
+        let now = Instant::now();
+        self.unanswered_ping = Some((payload, now));
+        self.unanswered_since.get_or_insert(now);
+        payload.to_be_bytes().to_vec()
+    }
+
+    pub fn pong(&mut self, payload: &[u8]) {
+        self.heard();
+
+        let Some((ping, sent)) = self.unanswered_ping else {return};
+        if payload != ping.to_be_bytes() {return}
+        self.unanswered_ping = None;
+        self.unanswered_since = None;
+
+        let round_trip = u64::try_from(sent.elapsed().as_millis()).unwrap_or(u64::MAX);
+        self.round_trip_millis = Some(match self.round_trip_millis {
+            Some(average) => average.saturating_mul(3).saturating_add(round_trip) / 4,
+            None => round_trip,
+        });
+    }
+
+    /// Whether the connection should be closed
+    pub fn timed_out(&self) -> bool {
+        self.last_heard.elapsed() > ServerConfig::get().heartbeat_timeout()
+    }
+
+    pub fn quality(&self) -> ConnectionQuality {
+        let lagging = self.unanswered_since
+            .is_some_and(|sent| sent.elapsed() > ServerConfig::get().heartbeat_interval());
+
+        ConnectionQuality {
+            round_trip_millis: self.round_trip_millis,
+            rating: match self.round_trip_millis {
+                _ if lagging => ConnectionRating::Lagging,
+                None => ConnectionRating::Unknown,
+                Some(millis) if Duration::from_millis(millis) < ConnectionRating::GOOD => ConnectionRating::Good,
+                Some(millis) if Duration::from_millis(millis) < ConnectionRating::FAIR => ConnectionRating::Fair,
+                Some(_) => ConnectionRating::Poor,
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionQuality {
+    /// `None` until the client has answered a ping
+    pub round_trip_millis: Option<u64>,
+    pub rating: ConnectionRating,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ConnectionRating {
+    Unknown,
+    Good,
+    Fair,
+    Poor,
+    /// The client hasn't answered the last ping, and another is about to be sent
+    Lagging,
+}
+
+impl ConnectionRating {
+    const GOOD: Duration = Duration::from_millis(150);
+    const FAIR: Duration = Duration::from_millis(400);
+}
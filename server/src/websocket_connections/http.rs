@@ -0,0 +1,182 @@
+//! Plain HTTP endpoints, served on the same address as the websocket server.
+//!
+//! - `GET /health` answers `200` while the server is running normally
+//! - `GET /rooms` lists every room, the same way the `lobbyList` packet does
+//! - `GET /stats` returns [`ServerStats`](crate::websocket_listener::ServerStats)
+//! - `GET /metrics` returns [`metrics`](crate::metrics) in the Prometheus text format
+//!
+//! Every request that isn't a websocket upgrade is handled here, then the connection is closed.
+
+use std::{io, pin::Pin, sync::{Arc, Mutex}, task::{Context, Poll}, time::Duration};
+
+use serde::Serialize;
+use tokio::{io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf}, net::TcpStream};
+
+use crate::{metrics, websocket_listener::WebsocketListener};
+
+/// Requests with a larger head than this are refused
+const MAX_REQUEST_HEAD_LENGTH: usize = 8 * 1024;
+/// How long a client has to send the head of its request, in total
+const REQUEST_HEAD_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub struct HttpRequest {
+    pub method: String,
+    pub path: String,
+    is_websocket_upgrade: bool,
+}
+
+/// A connection whose request head has been read. Reading from it gives back what was read first,
+/// so the whole request can still be given to the websocket handshake
+pub struct RequestStream {
+    read: Vec<u8>,
+    /// How much of `read` has been given back
+    replayed: usize,
+    stream: TcpStream,
+}
+
+impl HttpRequest {
+    /// Reads the head of the request, waiting at most [`REQUEST_HEAD_TIMEOUT`] for all of it
+    pub async fn read(stream: TcpStream) -> io::Result<(Self, RequestStream)> {
+        let mut stream = RequestStream { read: Vec::new(), replayed: 0, stream };
+        let request = tokio::time::timeout(REQUEST_HEAD_TIMEOUT, Self::read_head(&mut stream)).await
+            .unwrap_or_else(|_| Err(io::Error::new(io::ErrorKind::TimedOut, "request head took too long")))?;
+        Ok((request, stream))
+    }
+
+    async fn read_head(stream: &mut RequestStream) -> io::Result<Self> {
+        let mut buffer = [0; 1024];
+        loop {
+            let read = stream.stream.read(&mut buffer).await?;
+            if read == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            stream.read.extend_from_slice(buffer.get(..read).unwrap_or_default());
+
+            if let Some(end) = stream.read.windows(4).position(|window| window == b"\r\n\r\n") {
+                return Self::parse(&String::from_utf8_lossy(stream.read.get(..end).unwrap_or_default()));
+            }
+            if stream.read.len() >= MAX_REQUEST_HEAD_LENGTH {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "request head is too long"));
+            }
+        }
+    }
+
+    fn parse(head: &str) -> io::Result<Self> {
+        let mut lines = head.lines();
+        let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+
+        let (Some(method), Some(target)) = (request_line.next(), request_line.next()) else {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid request line"));
+        };
+
+        let is_websocket_upgrade = lines
+            .filter_map(|line| line.split_once(':'))
+            .any(|(name, value)| name.trim().eq_ignore_ascii_case("upgrade") && value.trim().eq_ignore_ascii_case("websocket"));
+
+        Ok(Self {
+            method: method.to_string(),
+            path: target.split('?').next().unwrap_or_default().to_string(),
+            is_websocket_upgrade,
+        })
+    }
+
+    pub fn is_websocket_upgrade(&self) -> bool {
+        self.is_websocket_upgrade
+    }
+}
+
+pub struct HttpResponse {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+impl HttpResponse {
+    fn json(status: u16, body: &impl Serialize) -> Self {
+        match serde_json::to_string(body) {
+            Ok(body) => Self { status, content_type: "application/json", body },
+            Err(_) => Self::error(500, "Failed to serialize response"),
+        }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Self { status, content_type: "application/json", body: serde_json::json!({ "error": message }).to_string() }
+    }
+
+    fn text(body: String) -> Self {
+        Self { status: 200, content_type: "text/plain; version=0.0.4", body }
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            503 => "Service Unavailable",
+            _ => "Internal Server Error",
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        format!(
+            "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nAccess-Control-Allow-Origin: *\r\nConnection: close\r\n\r\n{}",
+            self.status, self.reason(), self.content_type, self.body.len(), self.body
+        ).into_bytes()
+    }
+}
+
+pub fn route(listener: &Arc<Mutex<WebsocketListener>>, method: &str, path: &str) -> HttpResponse {
+    if method != "GET" {
+        return HttpResponse::error(405, "Only GET is supported");
+    }
+
+    // A poisoned listener means the server is about to restart
+    let Ok(listener) = listener.lock() else {
+        return HttpResponse::error(503, "The server is restarting");
+    };
+
+    match path {
+        "/health" => HttpResponse::json(200, &serde_json::json!({ "status": "ok" })),
+        "/rooms" => HttpResponse::json(200, &listener.room_previews()),
+        "/stats" => HttpResponse::json(200, &listener.stats()),
+        "/metrics" => HttpResponse::text(metrics::render(&listener.stats())),
+        _ => HttpResponse::error(404, "Not found"),
+    }
+}
+
+/// Answers `request`, which must have been read from `stream`, then closes the connection
+pub async fn respond(mut stream: RequestStream, request: HttpRequest, listener: &Arc<Mutex<WebsocketListener>>) -> io::Result<()> {
+    let response = route(listener, &request.method, &request.path);
+
+    stream.write_all(&response.to_bytes()).await?;
+    stream.shutdown().await
+}
+
+impl AsyncRead for RequestStream {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        let unread = this.read.get(this.replayed..).unwrap_or_default();
+        if unread.is_empty() {
+            return Pin::new(&mut this.stream).poll_read(cx, buf);
+        }
+
+        let length = unread.len().min(buf.remaining());
+        buf.put_slice(unread.get(..length).unwrap_or_default());
+        this.replayed = this.replayed.saturating_add(length);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncWrite for RequestStream {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.stream).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_shutdown(cx)
+    }
+}
@@ -1,6 +1,11 @@
 use std::sync::{Mutex, Arc, MutexGuard};
 
+#[cfg(unix)]
+pub mod admin;
 pub mod connection;
+pub mod heartbeat;
+pub mod http;
+pub mod outbox;
 pub mod websocket_server;
 
 pub trait ForceLock {
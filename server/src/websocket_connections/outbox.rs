@@ -0,0 +1,77 @@
+//! Packets sent to a client in a room are numbered, and the latest ones are kept in its [`Outbox`].
+//! A client that loses connection can rejoin with the number of the last packet it got,
+//! and is sent only what it missed instead of the whole room again.
+//!
+//! Packets keep being numbered into the outbox while the client is disconnected, see [`ClientSender::record`](super::connection::ClientSender::record).
+//! They're also only kept, not sent, while the room is answering a client's join, so the client gets its answer first.
+
+use std::collections::VecDeque;
+
+use crate::{config::ServerConfig, packet::ToClientPacket};
+
+#[derive(Debug)]
+pub struct Outbox {
+    /// The first packet is 1, so a client that has received nothing has received packet 0
+    next_sequence: u64,
+    packets: VecDeque<(u64, ToClientPacket)>,
+    /// While packets are held, the last one sent before they were
+    held_after: Option<u64>,
+}
+
+impl Default for Outbox {
+    fn default() -> Self {
+        Self { next_sequence: 1, packets: VecDeque::new(), held_after: None }
+    }
+}
+
+impl Outbox {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Numbers the packet and keeps it, forgetting the oldest packet if there are too many
+    pub fn push(&mut self, packet: ToClientPacket) -> u64 {
+        let sequence = self.next_sequence;
+        self.next_sequence = self.next_sequence.saturating_add(1);
+
+        self.packets.push_back((sequence, packet));
+        while self.packets.len() > ServerConfig::get().client_outbox_size {
+            self.packets.pop_front();
+        }
+        sequence
+    }
+
+    /// Packets are kept without being sent, until they're released
+    pub fn hold(&mut self) {
+        self.held_after = Some(self.next_sequence.saturating_sub(1));
+    }
+
+    pub fn is_held(&self) -> bool {
+        self.held_after.is_some()
+    }
+
+    /// Stops holding packets, and returns the ones that were held, to be sent
+    pub fn release(&mut self) -> Vec<(u64, ToClientPacket)> {
+        self.held_after.take()
+            .and_then(|last_sequence| self.missed_since(last_sequence))
+            .unwrap_or_default()
+    }
+
+    /// Every packet sent after `last_sequence`.
+    /// `None` if some of them were already forgotten, or the client got packets this outbox never sent,
+    /// so the client has to be sent everything again.
+    pub fn missed_since(&self, last_sequence: u64) -> Option<Vec<(u64, ToClientPacket)>> {
+        if last_sequence >= self.next_sequence {
+            return None;
+        }
+        let first_kept = self.packets.front().map_or(self.next_sequence, |(sequence, _)| *sequence);
+        if first_kept > last_sequence.saturating_add(1) {
+            return None;
+        }
+
+        Some(self.packets.iter()
+            .filter(|(sequence, _)| *sequence > last_sequence)
+            .cloned()
+            .collect())
+    }
+}
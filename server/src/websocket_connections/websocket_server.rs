@@ -1,18 +1,22 @@
-use crate::{log, websocket_connections::{connection::Connection, ForceLock}, websocket_listener::WebsocketListener};
-use tokio_tungstenite::tungstenite::Message;
-use std::{future::Future, net::SocketAddr, pin::pin, sync::{Arc, Mutex}};
+use crate::{config::ServerConfig, log, room::task, websocket_connections::{connection::Connection, http::{self, HttpRequest, RequestStream}, ForceLock}, websocket_listener::WebsocketListener};
+use std::{future::Future, net::SocketAddr, path::PathBuf, pin::pin, sync::{Arc, Mutex}};
 
 use futures_util::{future::{self, Either}, StreamExt, SinkExt};
 
-use tokio::sync::{mpsc, broadcast};
-use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{mpsc, broadcast, Notify};
+use tokio::{net::{TcpListener, TcpStream}, time::MissedTickBehavior};
+use tokio_tungstenite::WebSocketStream;
 
-pub async fn create_ws_server(server_address: &str) {
+pub async fn create_ws_server(server_address: &str, replay_directory: Option<PathBuf>, data_directory: Option<PathBuf>) {
     #[expect(clippy::panic, reason = "Server cannot start without TCP listener")]
     let tcp_listener = TcpListener::bind(&server_address).await.unwrap_or_else(|err| {
         panic!("Failed to bind websocket server to address {server_address}: {err}")
     });
     
+    // Rooms are restored before this server's panic hook is set,
+    // so a room that panics while being restored is just skipped instead of crashing the server
+    let event_listener: Arc<Mutex<_>> = Arc::new(Mutex::new(WebsocketListener::new(replay_directory, data_directory)));
+
     let mut crash_signal = broadcast::channel(1);
 
     {
@@ -22,79 +26,146 @@ pub async fn create_ws_server(server_address: &str) {
         let panic_crash_signal_sender = crash_signal.0.clone();
         let original_hook = std::panic::take_hook();
         std::panic::set_hook(Box::new(move |info| {
-            let _ = panic_crash_signal_sender.send(());
+            // A room that panics is closed by its own task, the rest of the server keeps running
+            if !task::is_handling_room_event() {
+                let _ = panic_crash_signal_sender.send(());
+            }
             original_hook(info)
         }))
     }
 
-    let event_listener: Arc<Mutex<_>> = Arc::new(Mutex::new(WebsocketListener::new()));
-    WebsocketListener::start_tick(event_listener.clone());
+    WebsocketListener::start_snapshots(event_listener.clone());
+    WebsocketListener::start_room_updates(event_listener.clone());
+
+    // Notified by the operator console
+    let restart = Arc::new(Notify::new());
+    start_admin_console(&event_listener, &restart, &crash_signal);
 
     log!(important "Server"; "Started listening on {server_address}");
 
     loop {
-        let (stream, client_address) = match future::select(
-            pin!(tcp_listener.accept()), 
-            pin!(crash_signal.1.recv())
-        ).await {
-            Either::Left((Ok((stream, client_address)), _)) => (stream, client_address),
-            Either::Left((Err(_), _)) => continue, // TCP connection failed
-            Either::Right(_) => break // Received crash signal
+        let (stream, client_address) = tokio::select! {
+            accepted = tcp_listener.accept() => match accepted {
+                Ok((stream, client_address)) => (stream, client_address),
+                Err(_) => continue, // TCP connection failed
+            },
+            _ = crash_signal.1.recv() => {
+                log!(fatal "Server"; "The server panicked!");
+                break
+            },
+            _ = restart.notified() => {
+                log!(important "Server"; "Restarting on an operator's request");
+                // Closes every connection, and the operator console
+                let _ = crash_signal.0.send(());
+                break
+            },
         };
         
+        // Checked before anything is read, so a connection that never sends its request still counts
+        if !event_listener.force_lock().admit(client_address) {
+            continue;
+        }
+
         let event_listener = event_listener.clone();
         let crash_signal = (crash_signal.0.clone(), crash_signal.1.resubscribe());
 
         tokio::spawn(handle_connection(stream, client_address, event_listener.clone(), crash_signal));
     }
 
-    log!(fatal "Server"; "The server panicked!");
+    WebsocketListener::save_rooms(&event_listener).await;
+    event_listener.force_lock().stop_rooms();
     log!(important "Server"; "Shutting down...");
 }
 
+#[cfg(unix)]
+fn start_admin_console(listener: &Arc<Mutex<WebsocketListener>>, restart: &Arc<Notify>, crash_signal: &(broadcast::Sender<()>, broadcast::Receiver<()>)) {
+    use crate::websocket_connections::admin;
+
+    let Some(path) = ServerConfig::get().admin_socket.clone() else {return};
+    tokio::spawn(admin::serve(path, listener.clone(), restart.clone(), crash_signal.1.resubscribe()));
+}
+
+#[cfg(not(unix))]
+fn start_admin_console(_: &Arc<Mutex<WebsocketListener>>, _: &Arc<Notify>, _: &(broadcast::Sender<()>, broadcast::Receiver<()>)) {
+    if ServerConfig::get().admin_socket.is_some() {
+        log!(error "Admin"; "The operator console is only available on Unix");
+    }
+}
+
 struct ConnectionError;
 
-enum NextEvent<A, B, C>
+enum NextEvent<A, B, C, D>
 where 
     A: Future + Unpin,
     B: Future + Unpin,
-    C: Future + Unpin
+    C: Future + Unpin,
+    D: Future + Unpin
 {
     TcpRecieved(A::Output),
     MpscReceieved(B::Output),
     CrashSignal(C::Output),
+    Heartbeat(D::Output),
 }
 
-impl<A, B, C> NextEvent<A, B, C> 
+impl<A, B, C, D> NextEvent<A, B, C, D> 
 where 
     A : Future + Unpin,
     B : Future + Unpin,
-    C : Future + Unpin
+    C : Future + Unpin,
+    D : Future + Unpin
 {
-    async fn from_futures(tcp_message: A, mpsc_message: B, crash_signal: C) -> Self {
-        match future::select(tcp_message, future::select(mpsc_message, crash_signal)).await {
-            Either::Left((tcp_message, _)) => Self::TcpRecieved(tcp_message),
-            Either::Right((Either::Left((mpsc_message, _)), _)) => Self::MpscReceieved(mpsc_message),
-            Either::Right((Either::Right((crash_signal, _)), _)) => Self::CrashSignal(crash_signal),
+    async fn from_futures(tcp_message: A, mpsc_message: B, crash_signal: C, heartbeat: D) -> Self {
+        match future::select(future::select(tcp_message, mpsc_message), future::select(crash_signal, heartbeat)).await {
+            Either::Left((Either::Left((tcp_message, _)), _)) => Self::TcpRecieved(tcp_message),
+            Either::Left((Either::Right((mpsc_message, _)), _)) => Self::MpscReceieved(mpsc_message),
+            Either::Right((Either::Left((crash_signal, _)), _)) => Self::CrashSignal(crash_signal),
+            Either::Right((Either::Right((heartbeat, _)), _)) => Self::Heartbeat(heartbeat),
+        }
+    }
+}
+
+/// Reads the connection's request, answering it if it's for HTTP.
+/// Returns `None` unless it's a websocket that finished its handshake
+async fn upgrade(
+    raw_stream: TcpStream,
+    client_address: SocketAddr,
+    listener: &Arc<Mutex<WebsocketListener>>
+) -> Option<WebSocketStream<RequestStream>> {
+    let (request, raw_stream) = match HttpRequest::read(raw_stream).await {
+        Ok(read) => read,
+        Err(error) => {
+            log!(info "Connection"; "Failed to read request from {}: {}", client_address, error);
+            return None;
+        }
+    };
+    if !request.is_websocket_upgrade() {
+        if let Err(error) = http::respond(raw_stream, request, listener).await {
+            log!(info "Http"; "Failed to respond to {}: {}", client_address, error);
+        }
+        return None;
+    }
+
+    match tokio_tungstenite::accept_async(raw_stream).await {
+        Ok(ws_stream) => Some(ws_stream),
+        Err(error) => {
+            log!(info "Connection"; "Failed to accept websocket handshake with {}: {}", client_address, error);
+            None
         }
     }
 }
 
 // Code within this function __SHOULD NOT PANIC__ except for listener methods.
 // There is a panic hook that restarts the server. The server doesn't need to restart if a connection fails, so don't panic -- just disconnect.
-/// This runs until the connection is closed.
+/// This runs until the connection is closed. The connection has to have been [admitted](WebsocketListener::admit)
 async fn handle_connection(
     raw_stream: TcpStream, 
     client_address: SocketAddr, 
     listener: Arc<Mutex<WebsocketListener>>,
     mut crash_signal: (broadcast::Sender<()>, broadcast::Receiver<()>)
 ) -> Result<(), ConnectionError> {
-    let ws_stream = match tokio_tungstenite::accept_async(raw_stream).await {
-        Ok(ws_stream) => ws_stream,
-        Err(error) => {
-            log!(info "Connection"; "Failed to accept websocket handshake with {}: {}", client_address, error);
-            return Err(ConnectionError);
-        }
+    let Some(ws_stream) = upgrade(raw_stream, client_address, &listener).await else {
+        listener.force_lock().on_abandon(client_address);
+        return Err(ConnectionError);
     };
 
     // Messages in this channel get received and rerouted to the client over TCP
@@ -109,16 +180,27 @@ async fn handle_connection(
             return Err(ConnectionError)
         };
         let connection = Connection::new(mpsc_sender, client_address);
-        log!(important "Connection"; "Connected: {}", client_address);
-        listener.on_connect(&connection);
-        connection
+        if listener.on_connect(&connection) {
+            log!(important "Connection"; "Connected: {}", client_address);
+            Some(connection)
+        } else {
+            None
+        }
     };
+    let Some(connection) = connection else {
+        let _ = tcp_sender.close().await;
+        return Err(ConnectionError)
+    };
+
+    let mut heartbeat = tokio::time::interval(ServerConfig::get().heartbeat_interval());
+    heartbeat.set_missed_tick_behavior(MissedTickBehavior::Delay);
 
     loop {
         match NextEvent::from_futures(
             pin!(tcp_receiver.next()),
             pin!(mpsc_receiver.recv()),
-            pin!(crash_signal.1.recv())
+            pin!(crash_signal.1.recv()),
+            pin!(heartbeat.tick())
         ).await {
             NextEvent::TcpRecieved(None) => break, // Channel has been closed
             NextEvent::TcpRecieved(Some(message)) => {
@@ -129,7 +211,10 @@ async fn handle_connection(
 
                 match message {
                     Ok(message) => {
-                        listener.on_message(&connection, &message);
+                        connection.heard(&message);
+                        if !listener.on_message(&connection, &message) {
+                            break
+                        }
                     }
                     Err(tokio_tungstenite::tungstenite::Error::ConnectionClosed) => break,
                     Err(err) => {
@@ -140,12 +225,17 @@ async fn handle_connection(
             }
             NextEvent::MpscReceieved(None) => break, // Channel has been closed
             NextEvent::MpscReceieved(Some(message)) => {
-                let Ok(json_message) = serde_json::to_string(&message) else {
-                    log!(error "Connection"; "Failed to parse packet. {:?}", &message);
-                    break
+                if !connection.supports(&message.packet) {continue}
+
+                let encoded = match connection.encode(&message) {
+                    Ok(encoded) => encoded,
+                    Err(error) => {
+                        log!(error "Connection"; "Failed to encode packet. {}. {:?}", error, &message);
+                        break
+                    }
                 };
     
-                match tcp_sender.send(Message::text(json_message)).await {
+                match tcp_sender.send(encoded).await {
                     Ok(_) => {},
                     Err(tokio_tungstenite::tungstenite::Error::ConnectionClosed) => break,
                     Err(err) => {
@@ -155,6 +245,16 @@ async fn handle_connection(
                 }
             }
             NextEvent::CrashSignal(..) => break, // Server has been closed
+            NextEvent::Heartbeat(..) => {
+                if connection.timed_out() {
+                    log!(important "Connection"; "Closing {} because it stopped answering", client_address);
+                    break
+                }
+                if let Err(err) = tcp_sender.send(connection.ping()).await {
+                    log!(info "Connection"; "Failed to ping {}. {}", client_address, err);
+                    break
+                }
+            }
         };
     }
 
@@ -1,6 +1,6 @@
-use std::{net::SocketAddr, ops::Mul, time::Duration};
+use std::net::SocketAddr;
 
-use crate::{room::{RoomClientID, Room}, packet::ToClientPacket, websocket_connections::connection::{ClientSender, Connection}};
+use crate::{packet::ToClientPacket, protocol::Capability, vec_set::VecSet, websocket_connections::connection::{ClientSender, Connection}};
 
 use super::{RoomCode, WebsocketListener};
 
@@ -53,21 +53,18 @@ impl ClientReference{
         self.deref_mut(listener).location = loc
     }
 
-    pub(super) fn get_room<'a>(&self, listener: &'a WebsocketListener)->Result<(&'a Room, RoomCode, RoomClientID),GetRoomError>{
-        self.location(listener).clone().get_room(listener)
-    }
-    pub(super) fn get_room_mut<'a>(&self, listener: &'a mut WebsocketListener)->Result<(&'a mut Room, RoomCode, RoomClientID),GetRoomError>{
-        self.location(listener).clone().get_room_mut(listener)
-    }
     pub(super) fn in_room(&self, listener: &WebsocketListener, room_code: RoomCode)->bool{
         self.deref(listener).in_room(room_code)
     }
 
-    pub(super) fn tick(&self, listener: &mut WebsocketListener){
-        self.deref_mut(listener).tick();
+    pub(super) fn said_hello(&self, listener: &WebsocketListener)->bool{
+        self.deref(listener).said_hello
     }
-    pub(super) fn ping_timed_out(&self, listener: &WebsocketListener)->bool{
-        self.deref(listener).ping_timed_out()
+    /// Only packets that need one of these capabilities are sent to the client from now on
+    pub(super) fn set_capabilities(&self, listener: &mut WebsocketListener, capabilities: VecSet<Capability>){
+        let client = self.deref_mut(listener);
+        client.connection.set_capabilities(capabilities);
+        client.said_hello = true;
     }
 }
 
@@ -75,27 +72,16 @@ impl ClientReference{
 pub(super) struct Client {
     connection: Connection,
     location: ClientLocation,
-    last_ping: tokio::time::Instant,
+    /// Whether the client's hello was accepted
+    said_hello: bool,
 }
+/// Whether a client is still there is checked by its connection, see [`crate::websocket_connections::heartbeat`]
 impl Client{
-    const PONG_INTERVAL: Duration = Duration::from_secs(5);
-
     pub(super) fn new(connection: Connection) -> Self {
         Self {
             connection,
             location: ClientLocation::OutsideRoom,
-            last_ping: tokio::time::Instant::now(),
-        }
-    }
-    pub(super) fn on_ping(&mut self) {
-        self.last_ping = tokio::time::Instant::now();
-    }
-    pub(super) fn ping_timed_out(&self) -> bool {
-        self.last_ping.elapsed() > Self::PONG_INTERVAL.mul(2)
-    }
-    pub(super) fn tick(&mut self) {
-        if Self::PONG_INTERVAL < tokio::time::Instant::now().saturating_duration_since(self.last_ping) {
-            self.connection.send(ToClientPacket::Pong);
+            said_hello: false,
         }
     }
     pub(super) fn send(&self, packet: ToClientPacket){
@@ -112,30 +98,15 @@ impl Client{
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub(super) enum ClientLocation {
+    /// The room knows which of its clients this is by its address
     InRoom{
         room_code: RoomCode,
-        room_client_id: RoomClientID,
     },
     OutsideRoom
 }
 impl ClientLocation{
     pub(super) fn in_room(&self, room_code: RoomCode)->bool{
-        let Self::InRoom { room_code: b, .. } = &self else {return false};
+        let Self::InRoom { room_code: b } = &self else {return false};
         room_code == *b 
     }
-    pub(super) fn get_room<'a>(&self, listener: &'a WebsocketListener)->Result<(&'a Room, RoomCode, RoomClientID),GetRoomError>{
-        let ClientLocation::InRoom{room_code, room_client_id} = &self else {return Err(GetRoomError::NotInRoom)};
-        let Some(room) = listener.get_room(room_code) else {return Err(GetRoomError::RoomDoesntExist)};
-        Ok((room, *room_code, *room_client_id))
-    }
-    pub(super) fn get_room_mut<'a>(&self, listener: &'a mut WebsocketListener)->Result<(&'a mut Room, RoomCode, RoomClientID),GetRoomError>{
-        let ClientLocation::InRoom{room_code, room_client_id} = &self else {return Err(GetRoomError::NotInRoom)};
-        let Some(room) = listener.get_room_mut(room_code) else {return Err(GetRoomError::RoomDoesntExist)};
-        Ok((room, *room_code, *room_client_id))
-    }
 }
-
-pub(super) enum GetRoomError{
-    NotInRoom,
-    RoomDoesntExist,
-}
\ No newline at end of file
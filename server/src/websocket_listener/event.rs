@@ -1,30 +1,95 @@
-use std::time::Duration;
+use std::net::SocketAddr;
+
 use tokio_tungstenite::tungstenite::Message;
 
-use crate::{room::RoomState, log, packet::ToServerPacket, websocket_connections::connection::Connection};
+use crate::{
+    config::ServerConfig, log, metrics, packet::ToClientPacket,
+    protocol::{RejectHelloReason, WireFormat, PROTOCOL_VERSION}, websocket_connections::connection::Connection
+};
 
-use super::{client::ClientReference, WebsocketListener, ValidateClientError};
+use super::{client::ClientReference, IpLimitExceeded, WebsocketListener, ValidateClientError};
 
 impl WebsocketListener{
-    pub fn on_connect(&mut self, connection: &Connection) {
+    /// Counts a connection as soon as it's accepted, before anything is read from it,
+    /// so connections that never finish their request still count towards the limits.
+    /// Returns false if the server already has as many connections as it allows,
+    /// or the connection's IP address can't open another one.
+    /// A connection that's let in either connects, or is given back with [`WebsocketListener::on_abandon`]
+    pub fn admit(&mut self, address: SocketAddr) -> bool {
+        if self.clients().len().saturating_add(self.admitted.len()) >= ServerConfig::get().max_connections {
+            log!(important "Connection"; "Refused {address}, too many connections");
+            return false;
+        }
+        if let Err(exceeded) = self.ip_limits.connect(address.ip()) {
+            log!(important "Limits"; "Refused {address}: {exceeded:?}");
+            metrics::ip_limit_exceeded(exceeded);
+            return false;
+        }
+        self.admitted.insert(address);
+        true
+    }
+
+    /// For a connection that was let in, but closed before it became a client, like one that only made an HTTP request
+    pub fn on_abandon(&mut self, address: SocketAddr) {
+        if self.admitted.remove(&address) {
+            self.ip_limits.disconnect(address.ip());
+        }
+    }
+
+    /// Connections that weren't [admitted](WebsocketListener::admit) are checked against the limits now.
+    /// Returns false if they refuse it
+    pub fn on_connect(&mut self, connection: &Connection) -> bool {
+        let address = *connection.address();
+        if !self.admitted.contains(&address) && !self.admit(address) {
+            return false;
+        }
+        self.admitted.remove(&address);
         self.create_client(connection);
+        true
     }
 
+    /// Only call this for connections that [`WebsocketListener::on_connect`] accepted
     pub fn on_disconnect(&mut self, connection: Connection) {
+        self.ip_limits.disconnect(connection.address().ip());
         if let Some(client) = ClientReference::new(connection.address(), self){
             self.delete_client(&client);
         }
     }
 
-    pub fn on_message(&mut self, connection: &Connection, message: &Message) {
-        if message.is_empty() { return }
+    /// Returns false if the connection should be closed, because its IP address sent too many packets.
+    /// Control frames, like the pongs answering the heartbeat, don't count
+    pub fn on_message(&mut self, connection: &Connection, message: &Message) -> bool {
+        if !(message.is_text() || message.is_binary()) { return true }
+
+        let ip = connection.address().ip();
+        if let Err(exceeded) = self.ip_limits.packet(ip) {
+            if exceeded == IpLimitExceeded::TooManyPackets {
+                log!(important "Limits"; "Banned {ip} for {} seconds, it sent too many packets", ServerConfig::get().ip_ban_duration_secs);
+            } else {
+                log!(important "Limits"; "Closing {}: {exceeded:?}", connection.address());
+            }
+            metrics::ip_limit_exceeded(exceeded);
+            return false;
+        }
 
-        log!(info "Listener"; "{}: {}", &connection.address().to_string(), message);
+        if message.is_empty() { return true }
 
-        let Ok(packet) = serde_json::from_str::<ToServerPacket>(message.to_string().as_str()) else {
-            log!(error "Listener"; "Recieved message but could not parse packet");
-            return
+        let packet = match WireFormat::decode(message) {
+            Ok(packet) => packet,
+            Err(error) => {
+                log!(error "Listener"; "Recieved message but could not parse packet: {}", error);
+                // A client that hasn't said hello is probably from before versioning, so tell it why it isn't understood
+                if let Some(client) = ClientReference::new(connection.address(), self) {
+                    if !client.said_hello(self) {
+                        client.send(self, ToClientPacket::RejectHello { reason: RejectHelloReason::HelloRequired, protocol_version: PROTOCOL_VERSION });
+                    }
+                }
+                return true
+            }
         };
+        // Only the type is logged, since packets can hold secrets like reconnect tokens
+        log!(info "Listener"; "{}: {}", connection.address(), metrics::packet_name(&packet));
+        metrics::packet_received(&packet);
 
         match self.validate_client(connection.address()) {
             Err(ValidateClientError::ClientDoesntExist) =>
@@ -35,31 +100,6 @@ impl WebsocketListener{
                 self.handle_message(client, packet)
             }
         }
-    }
-    pub(super) fn tick(&mut self, delta_time: Duration){
-        let mut closed_rooms = Vec::new();
-        let mut closed_clients = Vec::new();
-
-        for (room_code, room) in self.rooms_mut().iter_mut() {
-            let tick_data = room.tick(delta_time);
-            if tick_data.close_room {
-                closed_rooms.push(*room_code);
-            }
-        }
-
-        for client in ClientReference::all_clients(self){
-            client.tick(self);
-            if client.ping_timed_out(self) {
-                closed_clients.push(client);
-            }
-        }
-
-        for room_code in closed_rooms {
-            self.delete_room(room_code);
-        }
-        for client in closed_clients {
-            log!(important "Listener"; "Closed connection {} due to ping timed out", client.address(self));
-            self.delete_client(&client);
-        }
+        true
     }
 }
\ No newline at end of file
@@ -0,0 +1,179 @@
+//! Limits on what each IP address can do, so one script can't fill the server with connections, rooms or packets.
+//!
+//! An IP address that sends packets faster than `packet_rate_limit` is banned for `ip_ban_duration_secs`.
+//! While it's banned, new connections from it are refused, and its open connections are closed when they next send anything.
+//!
+//! Behind a reverse proxy every client has the proxy's address, so the limits there should be raised to match.
+
+use std::{collections::HashMap, net::IpAddr, time::Duration};
+
+use serde::Serialize;
+use tokio::time::Instant;
+
+use crate::config::ServerConfig;
+
+/// Why something from an IP address was refused
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IpLimitExceeded {
+    /// The address is banned, for sending too many packets or by an operator
+    Banned,
+    TooManyConnections,
+    TooManyRoomsCreated,
+    /// The address sent too many packets, and was just banned for it
+    TooManyPackets,
+}
+
+/// Counts events in back to back windows of a fixed length
+#[derive(Default)]
+struct RateWindow {
+    started: Option<Instant>,
+    count: u64,
+}
+
+impl RateWindow {
+    /// Counts an event, unless there were already `limit` in the current window
+    fn count(&mut self, now: Instant, window: Duration, limit: u64) -> bool {
+        if !self.is_current(now, window) {
+            self.started = Some(now);
+            self.count = 0;
+        }
+        if self.count >= limit {
+            return false;
+        }
+        self.count = self.count.saturating_add(1);
+        true
+    }
+    fn is_current(&self, now: Instant, window: Duration) -> bool {
+        self.started.is_some_and(|started| now.duration_since(started) < window)
+    }
+}
+
+#[derive(Default)]
+struct IpActivity {
+    connections: usize,
+    rooms_created: RateWindow,
+    packets: RateWindow,
+    banned_until: Option<Instant>,
+}
+
+pub struct IpLimits {
+    ips: HashMap<IpAddr, IpActivity>,
+
+    max_connections: usize,
+    room_creation_limit: u64,
+    room_creation_window: Duration,
+    packet_limit: u64,
+    packet_window: Duration,
+    ban_duration: Duration,
+}
+
+impl IpLimits {
+    pub fn new(config: &ServerConfig) -> Self {
+        Self {
+            ips: HashMap::new(),
+
+            max_connections: config.max_connections_per_ip,
+            room_creation_limit: config.room_creation_limit.into(),
+            room_creation_window: config.room_creation_window(),
+            packet_limit: config.packet_rate_limit_window_secs.saturating_mul(config.packet_rate_limit.into()),
+            packet_window: config.packet_rate_limit_window(),
+            ban_duration: config.ip_ban_duration(),
+        }
+    }
+
+    /// Counts a new connection from `ip`, unless it's banned or already has as many as it's allowed.
+    /// Every connection that's counted should be given back with [`IpLimits::disconnect`]
+    pub fn connect(&mut self, ip: IpAddr) -> Result<(), IpLimitExceeded> {
+        let now = Instant::now();
+        if !self.ips.contains_key(&ip) {
+            self.forget_idle(now);
+        }
+        let activity = self.ips.entry(ip).or_default();
+
+        if activity.is_banned(now) {
+            return Err(IpLimitExceeded::Banned);
+        }
+        if activity.connections >= self.max_connections {
+            return Err(IpLimitExceeded::TooManyConnections);
+        }
+        activity.connections = activity.connections.saturating_add(1);
+        Ok(())
+    }
+
+    pub fn disconnect(&mut self, ip: IpAddr) {
+        let now = Instant::now();
+        let Some(activity) = self.ips.get_mut(&ip) else {return};
+        activity.connections = activity.connections.saturating_sub(1);
+
+        if self.is_idle(ip, now) {
+            self.ips.remove(&ip);
+        }
+    }
+
+    /// Counts a room created by `ip`, unless it has created as many as it's allowed recently
+    pub fn create_room(&mut self, ip: IpAddr) -> Result<(), IpLimitExceeded> {
+        let now = Instant::now();
+        let activity = self.ips.entry(ip).or_default();
+
+        if activity.is_banned(now) {
+            return Err(IpLimitExceeded::Banned);
+        }
+        if !activity.rooms_created.count(now, self.room_creation_window, self.room_creation_limit) {
+            return Err(IpLimitExceeded::TooManyRoomsCreated);
+        }
+        Ok(())
+    }
+
+    /// Counts a packet from `ip`, banning it if that's more than it's allowed to send.
+    /// The connection the packet came from should be closed if this fails
+    pub fn packet(&mut self, ip: IpAddr) -> Result<(), IpLimitExceeded> {
+        let now = Instant::now();
+        let activity = self.ips.entry(ip).or_default();
+
+        if activity.is_banned(now) {
+            return Err(IpLimitExceeded::Banned);
+        }
+        if !activity.packets.count(now, self.packet_window, self.packet_limit) {
+            activity.banned_until = now.checked_add(self.ban_duration);
+            return Err(IpLimitExceeded::TooManyPackets);
+        }
+        Ok(())
+    }
+
+    /// Bans `ip` for `duration`, or until its current ban ends if that's later
+    pub fn ban(&mut self, ip: IpAddr, duration: Duration) {
+        let banned_until = Instant::now().checked_add(duration);
+        let activity = self.ips.entry(ip).or_default();
+        activity.banned_until = activity.banned_until.max(banned_until);
+    }
+
+    pub fn is_banned(&self, ip: IpAddr) -> bool {
+        self.ips.get(&ip).is_some_and(|activity| activity.is_banned(Instant::now()))
+    }
+
+    /// Whether nothing from `ip` would be limited if it was forgotten
+    fn is_idle(&self, ip: IpAddr, now: Instant) -> bool {
+        self.ips.get(&ip).is_none_or(|activity|
+            activity.connections == 0 &&
+            !activity.is_banned(now) &&
+            !activity.rooms_created.is_current(now, self.room_creation_window) &&
+            !activity.packets.is_current(now, self.packet_window)
+        )
+    }
+
+    /// Addresses are kept after their last connection closes until their limits reset,
+    /// so reconnecting doesn't reset them
+    fn forget_idle(&mut self, now: Instant) {
+        let idle: Vec<IpAddr> = self.ips.keys().copied().filter(|ip| self.is_idle(*ip, now)).collect();
+        for ip in idle {
+            self.ips.remove(&ip);
+        }
+    }
+}
+
+impl IpActivity {
+    fn is_banned(&self, now: Instant) -> bool {
+        self.banned_until.is_some_and(|banned_until| now < banned_until)
+    }
+}
@@ -1,16 +1,27 @@
 mod event;
 mod client;
 mod handle_message;
+mod room_code;
+mod ip_limits;
 
-pub type RoomCode = usize;
+pub use room_code::{InvalidRoomCode, RoomCode};
+pub use ip_limits::{IpLimitExceeded, IpLimits};
 
 
-use std::{collections::HashMap, net::SocketAddr, sync::{Arc, Mutex}, time::Duration};
+use std::{collections::{HashMap, HashSet}, net::{IpAddr, SocketAddr}, path::PathBuf, sync::{Arc, Mutex}, time::{Duration, Instant}};
 
-use crate::{log, packet::{RejectJoinReason, ToClientPacket}, room::{JoinRoomClientResult, RemoveRoomClientResult, Room, RoomClientID, RoomState}, websocket_connections::connection::Connection};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
 
-use self::client::{Client, ClientLocation, ClientReference, GetRoomError};
-use rand::random;
+use serde::Serialize;
+
+use crate::{
+    config::ServerConfig, log, packet::{RejectJoinReason, RoomPreviewData, ToClientPacket},
+    room::{access::RoomAccess, snapshot, task::{RoomEvent, RoomHandle, RoomUpdate}, Room},
+    vec_map::VecMap, websocket_connections::{connection::{ClientSender, Connection}, ForceLock}
+};
+
+use self::client::{Client, ClientLocation, ClientReference};
+use rand::Rng;
 
 
 
@@ -26,35 +37,116 @@ pub struct WebsocketListener {
     ///  Yes                 | No               | Disconnect listener client
     ///  Yes                 | Yes              | Hooray!
     clients: HashMap<SocketAddr, Client>,
-    rooms: HashMap<RoomCode, Room>,
+    /// Connections that were let in as soon as they were accepted, and haven't become clients yet,
+    /// see [`WebsocketListener::admit`]
+    admitted: HashSet<SocketAddr>,
+    ip_limits: IpLimits,
+    /// Each room runs on its own task, see [`crate::room::task`]
+    rooms: HashMap<RoomCode, RoomHandle>,
+    /// Given to every room, so it can tell the listener when it removes a client or closes
+    room_updates: UnboundedSender<RoomUpdate>,
+    room_updates_receiver: Option<UnboundedReceiver<RoomUpdate>>,
+    /// Where to save the replay of every game that ends, if anywhere
+    replay_directory: Option<PathBuf>,
+    /// Where to snapshot rooms so they survive a restart, if anywhere
+    data_directory: Option<PathBuf>,
+    started: Instant,
+}
+
+/// What the server is doing right now, served over HTTP at `/stats`
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerStats {
+    pub connected_clients: usize,
+    pub rooms_in_lobby: usize,
+    pub rooms_in_game: usize,
+    pub uptime_secs: u64,
 }
+
 impl WebsocketListener{
-    pub fn new() -> Self {
+    /// Restores the rooms snapshotted in `data_directory`, if there are any
+    pub fn new(replay_directory: Option<PathBuf>, data_directory: Option<PathBuf>) -> Self {
+        let (room_updates, room_updates_receiver) = mpsc::unbounded_channel();
+
+        let rooms = match &data_directory {
+            Some(data_directory) => snapshot::load_rooms(data_directory).unwrap_or_else(|err| {
+                log!(error "Snapshot"; "Failed to load rooms: {err}");
+                HashMap::new()
+            }),
+            None => HashMap::new(),
+        };
+
         Self {
-            rooms: HashMap::new(),
+            rooms: rooms.into_iter()
+                .map(|(room_code, (room, access))| (room_code, RoomHandle::spawn(room_code, room, access, room_updates.clone(), replay_directory.clone())))
+                .collect(),
             clients: HashMap::new(),
+            admitted: HashSet::new(),
+            ip_limits: IpLimits::new(ServerConfig::get()),
+            room_updates,
+            room_updates_receiver: Some(room_updates_receiver),
+            replay_directory,
+            data_directory,
+            started: Instant::now(),
         }
     }
     fn clients(&self) -> &HashMap<SocketAddr, Client> {
         &self.clients
     }
-    fn rooms(&self) -> &HashMap<RoomCode, Room> {
-        &self.rooms
-    }
-    fn rooms_mut(&mut self) -> &mut HashMap<RoomCode, Room> {
-        &mut self.rooms
-    }
     fn get_client<'a>(&'a self, address: &SocketAddr) -> Option<&'a Client> {
         self.clients.get(address)
     }
     fn get_client_mut<'a>(&'a mut self, address: &SocketAddr) -> Option<&'a mut Client> {
         self.clients.get_mut(address)
     }
-    pub(super) fn get_room<'a>(&'a self, room_code: &RoomCode) -> Option<&'a Room> {
+    /// Private rooms aren't listed
+    pub fn room_previews(&self) -> HashMap<RoomCode, RoomPreviewData> {
+        self.rooms.iter()
+            .filter(|(_, room)| !room.is_private())
+            .map(|(room_code, room)| (*room_code, room.preview()))
+            .collect()
+    }
+    pub fn stats(&self) -> ServerStats {
+        let rooms_in_game = self.rooms.values().filter(|room| room.preview().in_game).count();
+
+        ServerStats {
+            connected_clients: self.clients.len(),
+            rooms_in_lobby: self.rooms.len().saturating_sub(rooms_in_game),
+            rooms_in_game,
+            uptime_secs: self.started.elapsed().as_secs(),
+        }
+    }
+    pub fn get_room<'a>(&'a self, room_code: &RoomCode) -> Option<&'a RoomHandle> {
         self.rooms.get(room_code)
     }
-    pub(super) fn get_room_mut<'a>(&'a mut self, room_code: &RoomCode) -> Option<&'a mut Room> {
-        self.rooms.get_mut(room_code)
+    pub fn room_handles(&self) -> impl Iterator<Item = (RoomCode, &RoomHandle)> {
+        self.rooms.iter().map(|(room_code, room)| (*room_code, room))
+    }
+    /// Whether rooms are snapshotted, so they survive a restart
+    pub fn keeps_rooms(&self) -> bool {
+        self.data_directory.is_some()
+    }
+
+    /// Sends `packet` to every connected client, in a room or not. Returns how many it was sent to
+    pub fn broadcast(&self, packet: ToClientPacket) -> usize {
+        for client in self.clients.values() {
+            client.send(packet.clone());
+        }
+        self.clients.len()
+    }
+    /// Bans `ip` for `duration`, and disconnects every client it has. Returns how many were disconnected.
+    ///
+    /// Their connections are closed as soon as they send anything, which they do at least when they answer the next ping
+    pub fn ban(&mut self, ip: IpAddr, duration: Duration) -> usize {
+        self.ip_limits.ban(ip, duration);
+
+        let banned: Vec<ClientReference> = ClientReference::all_clients(self)
+            .filter(|client| client.address(self).ip() == ip)
+            .collect();
+        for client in &banned {
+            self.delete_client(client);
+        }
+        banned.len()
     }
 
 
@@ -67,78 +159,56 @@ impl WebsocketListener{
         self.clients.insert(*connection.address(), Client::new(connection.clone()));
     }
     fn delete_client(&mut self, client: &ClientReference) {
-        let Some(client) = self.clients.remove(&client.address(self).clone()) else {return};
+        let address = *client.address(self);
+        let Some(client) = self.clients.remove(&address) else {return};
 
         //This ToClientPacket is still useful in the *rare* case that the player is still connected when they're being forced to disconnect
         //A player can be forced to disconnect if a seperate connection is made with the same ip and port address
         client.send(ToClientPacket::ForcedDisconnect);
 
 
-        let ClientLocation::InRoom { room_code, room_client_id } = client.location() else {return};
-        let Some(room) = self.rooms.get_mut(room_code) else {return};
-
-        match room.remove_client_rejoinable(*room_client_id) {
-            RemoveRoomClientResult::Success |
-            RemoveRoomClientResult::ClientNotInRoom => {},
-            RemoveRoomClientResult::RoomShouldClose => self.delete_room(*room_code)
+        let ClientLocation::InRoom { room_code } = client.location() else {return};
+        if let Some(room) = self.rooms.get(room_code) {
+            room.send(RoomEvent::LoseConnection { address });
         }
     }
 
 
-    fn set_client_in_room(&mut self, client: &ClientReference, room_code: RoomCode){
-
-        let sender = &client.sender(self).clone();
-        let Some(room) = self.get_room_mut(&room_code) else {
-            client.send(self, ToClientPacket::RejectJoin { reason: RejectJoinReason::RoomDoesntExist });
-            return
-        };
-        match room.join_client(sender) {
-            Ok(JoinRoomClientResult { id: room_client_id, in_game, spectator }) => {
-                sender.send(ToClientPacket::AcceptJoin { room_code, in_game, player_id: room_client_id, spectator });
-
-                room.initialize_client(room_client_id, sender);
+    /// Sends the client's join request to the room, which answers the client itself
+    fn set_client_in_room(&mut self, client: &ClientReference, room_code: RoomCode, event: impl FnOnce(SocketAddr, ClientSender) -> RoomEvent){
+        let address = *client.address(self);
+        let sender = client.sender(self);
 
-                client.set_location(self, ClientLocation::InRoom { room_code, room_client_id });
-            }
-            Err(reason) => {
-                client.send(self, ToClientPacket::RejectJoin { reason });
+        // A client can only be in one room at a time
+        if let ClientLocation::InRoom { room_code: current_room_code } = client.location(self).clone() {
+            if let Some(room) = self.get_room(&current_room_code) {
+                room.send(RoomEvent::LoseConnection { address });
             }
+            client.set_location(self, ClientLocation::OutsideRoom);
         }
-    }
-    fn set_client_in_room_reconnect(&mut self, client: ClientReference, room_code: RoomCode, room_client_id: RoomClientID){
 
-        let sender = &client.sender(self).clone();
-        let Some(room) = self.get_room_mut(&room_code) else {
+        let Some(room) = self.get_room(&room_code) else {
             client.send(self, ToClientPacket::RejectJoin { reason: RejectJoinReason::RoomDoesntExist });
             return
         };
-        match room.rejoin_client(sender, room_client_id) {
-            Ok(JoinRoomClientResult { id: room_client_id, in_game, spectator }) => {
-                sender.send(ToClientPacket::AcceptJoin { room_code, in_game, player_id: room_client_id, spectator });
-
-                room.initialize_client(room_client_id, sender);
-
-                client.set_location(self, ClientLocation::InRoom { room_code, room_client_id });
-            }
-            Err(reason) => {
-                client.send(self, ToClientPacket::RejectJoin { reason });
-            }
+        if !room.send(event(address, sender)) {
+            client.send(self, ToClientPacket::RejectJoin { reason: RejectJoinReason::RoomDoesntExist });
+            return
         }
+
+        client.set_location(self, ClientLocation::InRoom { room_code });
     }
     fn set_client_outside_room(&mut self, client: &ClientReference, rejoinable: bool) {
         client.send(self, ToClientPacket::ForcedOutsideRoom);
-        
-        if let Ok((room, room_code, id)) = client.get_room_mut(self) {
-            let result = if rejoinable {
-                room.remove_client_rejoinable(id)
-            }else{
-                room.remove_client(id)
-            };
-
-            match result {
-                RemoveRoomClientResult::Success |
-                RemoveRoomClientResult::ClientNotInRoom => {}
-                RemoveRoomClientResult::RoomShouldClose => self.delete_room(room_code),
+
+        if let ClientLocation::InRoom { room_code } = client.location(self) {
+            if let Some(room) = self.get_room(room_code) {
+                let address = *client.address(self);
+                room.send(if rejoinable {
+                    RoomEvent::LoseConnection { address }
+                } else {
+                    RoomEvent::Leave { address }
+                });
             }
         }
 
@@ -147,21 +217,31 @@ impl WebsocketListener{
 
 
 
+    /// Codes aren't reused while their room is open
     fn generate_roomcode(&self)->Option<RoomCode>{
-        let start = random::<u16>() as usize;
-        (start..=usize::MAX)
-            .chain(0..start)
+        let numbers = RoomCode::numbers_with_length(ServerConfig::get().room_code_length);
+        if numbers.is_empty() {return None}
+        let start = rand::rng().random_range(numbers.clone());
+        (start..numbers.end)
+            .chain(numbers.start..start)
+            .map(RoomCode::new)
             .find(
                 |code| !self.rooms.contains_key(code)
             )
     }
+    /// Returns `None` if the server already has as many rooms as it allows
     pub(super) fn create_room(&mut self) -> Option<RoomCode>{
+        if self.rooms.len() >= ServerConfig::get().max_rooms {
+            return None;
+        }
         let room_code = self.generate_roomcode()?;
 
-        self.rooms.insert(room_code, Room::new());
+        let room = RoomHandle::spawn(room_code, Room::new(), RoomAccess::default(), self.room_updates.clone(), self.replay_directory.clone());
+        self.rooms.insert(room_code, room);
         Some(room_code)
     }
-    pub(super) fn delete_room(&mut self, room_code: RoomCode){
+    /// Called once the room's task has closed it
+    fn on_room_closed(&mut self, room_code: RoomCode){
         self.rooms.remove(&room_code);
 
         for client in ClientReference::all_clients(self){
@@ -173,24 +253,75 @@ impl WebsocketListener{
 
         log!(important "Room"; "Closed {room_code}.");
     }
+    fn on_room_update(&mut self, update: RoomUpdate) {
+        match update {
+            RoomUpdate::ClientRemoved { room_code, address } => {
+                if let Some(client) = ClientReference::new(&address, self) {
+                    if client.in_room(self, room_code) {
+                        client.set_location(self, ClientLocation::OutsideRoom);
+                    }
+                }
+            },
+            RoomUpdate::Closed { room_code } => self.on_room_closed(room_code),
+        }
+    }
+    /// Stops every room's task, without closing the rooms
+    pub fn stop_rooms(&mut self) {
+        self.rooms.clear();
+    }
+
+    /// Asks every room for a snapshot, then saves them.
+    /// The listener isn't locked while waiting for rooms, so a slow room doesn't block anything else.
+    pub async fn save_rooms(listener: &Arc<Mutex<Self>>) {
+        const SNAPSHOT_TIMEOUT: Duration = Duration::from_secs(10);
+
+        let (data_directory, rooms) = {
+            let listener = listener.force_lock();
+            let Some(data_directory) = listener.data_directory.clone() else {return};
+            let rooms: Vec<(RoomCode, RoomHandle)> = listener.rooms.iter()
+                .map(|(room_code, room)| (*room_code, room.clone()))
+                .collect();
+            (data_directory, rooms)
+        };
+
+        let mut snapshots = VecMap::new();
+        for (room_code, room) in rooms {
+            match tokio::time::timeout(SNAPSHOT_TIMEOUT, room.snapshot()).await {
+                Ok(Some(snapshot)) => { snapshots.insert(room_code, snapshot); },
+                Ok(None) => {},
+                Err(_) => log!(error "Snapshot"; "Room {room_code} took too long to snapshot"),
+            }
+        }
+
+        if let Err(err) = snapshot::save_rooms(&data_directory, &snapshots) {
+            log!(error "Snapshot"; "Failed to save rooms: {err}");
+        }
+    }
+
 
-    
-    pub fn start_tick(listener: Arc<Mutex<Self>>) {
-        const DESIRED_FRAME_TIME: Duration = Duration::from_secs(1);
+    /// Snapshots every room on an interval.
+    /// Rooms tick on their own tasks, and connections check they're alive themselves
+    pub fn start_snapshots(listener: Arc<Mutex<Self>>) {
+        let snapshot_interval = ServerConfig::get().snapshot_interval();
 
         tokio::spawn(async move {
-            let mut frame_start_time = tokio::time::Instant::now();
             loop {
-                let delta_time = frame_start_time.elapsed();
-                frame_start_time = tokio::time::Instant::now();
+                tokio::time::sleep(snapshot_interval).await;
+                Self::save_rooms(&listener).await;
+            }
+        });
+    }
 
-                if let Ok(mut listener) = listener.lock() {
-                    listener.tick(delta_time);                  
-                } else { 
-                    return;
-                }
+    /// Applies the updates rooms send back to the listener, until every room and the listener are gone
+    pub fn start_room_updates(listener: Arc<Mutex<Self>>) {
+        let Some(mut room_updates) = listener.force_lock().room_updates_receiver.take() else {return};
+        let listener = Arc::downgrade(&listener);
 
-                tokio::time::sleep(DESIRED_FRAME_TIME.saturating_sub(frame_start_time.elapsed())).await;
+        tokio::spawn(async move {
+            while let Some(update) = room_updates.recv().await {
+                let Some(listener) = listener.upgrade() else {return};
+                let Ok(mut listener) = listener.lock() else {return};
+                listener.on_room_update(update);
             }
         });
     }
@@ -198,10 +329,12 @@ impl WebsocketListener{
 
     fn validate_client(&self, addr: &SocketAddr)->Result<ClientReference,ValidateClientError>{
         let Some(client) = ClientReference::new(addr, self) else {return Err(ValidateClientError::ClientDoesntExist)};
-        if let Err(GetRoomError::RoomDoesntExist) = client.get_room(self) {return Err(ValidateClientError::InRoomThatDoesntExist)};
+        if let ClientLocation::InRoom { room_code } = client.location(self) {
+            if !self.rooms.contains_key(room_code) {return Err(ValidateClientError::InRoomThatDoesntExist)}
+        }
         Ok(client)
     }
-    
+
 }
 
 
@@ -0,0 +1,101 @@
+//! Room codes are written with letters that can't be mistaken for each other when read out loud or off a screen,
+//! so there's no I, L or O. Codes are read case-insensitively.
+//!
+//! Rooms used to have numeric codes. A code made only of digits is still read as one of those,
+//! which is how rooms saved by an older server are restored.
+
+use std::{fmt::{self, Display}, ops::Range, str::FromStr};
+
+use serde::{de::{self, Visitor}, Deserialize, Deserializer, Serialize, Serializer};
+
+const BASE: u32 = 23;
+const ALPHABET: &[u8; BASE as usize] = b"ABCDEFGHJKMNPQRSTUVWXYZ";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RoomCode(u32);
+
+impl RoomCode {
+    pub const fn new(number: u32) -> Self {
+        Self(number)
+    }
+
+    /// The numbers of codes that are `length` letters long
+    pub fn numbers_with_length(length: u32) -> Range<u32> {
+        BASE.saturating_pow(length.saturating_sub(1))..BASE.saturating_pow(length)
+    }
+}
+
+impl Display for RoomCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut letters = Vec::new();
+        let mut number = self.0;
+        loop {
+            letters.push(ALPHABET.get((number % BASE) as usize).copied().unwrap_or(b'A'));
+            number /= BASE;
+            if number == 0 {break}
+        }
+        letters.reverse();
+        f.write_str(&String::from_utf8_lossy(&letters))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidRoomCode;
+
+impl FromStr for RoomCode {
+    type Err = InvalidRoomCode;
+
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        let code = code.trim();
+        if code.is_empty() {
+            return Err(InvalidRoomCode);
+        }
+        if code.bytes().all(|byte| byte.is_ascii_digit()) {
+            return code.parse().map(Self).map_err(|_| InvalidRoomCode);
+        }
+
+        code.bytes().try_fold(0u32, |number, byte| {
+            let digit = ALPHABET.iter().position(|letter| *letter == byte.to_ascii_uppercase())
+                .and_then(|digit| u32::try_from(digit).ok())
+                .ok_or(InvalidRoomCode)?;
+            number.checked_mul(BASE)
+                .and_then(|number| number.checked_add(digit))
+                .ok_or(InvalidRoomCode)
+        }).map(Self)
+    }
+}
+
+impl Serialize for RoomCode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Accepts the code as a string, or as a number from an older client or save
+impl<'de> Deserialize<'de> for RoomCode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct RoomCodeVisitor;
+
+        impl Visitor<'_> for RoomCodeVisitor {
+            type Value = RoomCode;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a room code")
+            }
+
+            fn visit_str<E: de::Error>(self, code: &str) -> Result<RoomCode, E> {
+                code.parse().map_err(|_| E::invalid_value(de::Unexpected::Str(code), &self))
+            }
+
+            fn visit_u64<E: de::Error>(self, number: u64) -> Result<RoomCode, E> {
+                u32::try_from(number).map(RoomCode).map_err(|_| E::invalid_value(de::Unexpected::Unsigned(number), &self))
+            }
+
+            fn visit_i64<E: de::Error>(self, number: i64) -> Result<RoomCode, E> {
+                u32::try_from(number).map(RoomCode).map_err(|_| E::invalid_value(de::Unexpected::Signed(number), &self))
+            }
+        }
+
+        deserializer.deserialize_any(RoomCodeVisitor)
+    }
+}
@@ -0,0 +1,196 @@
+#![cfg(unix)]
+
+use std::{net::{IpAddr, SocketAddr}, os::unix::fs::PermissionsExt, sync::{Arc, Mutex}, time::Duration};
+
+use mafia_server::{
+    packet::ToClientPacket, protocol::PROTOCOL_VERSION,
+    websocket_connections::{admin::{self, AdminCommand, AdminResponse}, connection::{Connection, SequencedPacket}, ForceLock},
+    websocket_listener::{RoomCode, WebsocketListener}
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader}, net::UnixStream,
+    sync::{broadcast, mpsc::{self, UnboundedReceiver}, Notify}
+};
+use tokio_tungstenite::tungstenite::Message;
+
+const IP: IpAddr = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 1));
+
+struct Console {
+    listener: Arc<Mutex<WebsocketListener>>,
+    restart: Arc<Notify>,
+}
+
+impl Console {
+    fn new() -> Self {
+        let listener = Arc::new(Mutex::new(WebsocketListener::new(None, None)));
+        WebsocketListener::start_room_updates(listener.clone());
+        Self { listener, restart: Arc::new(Notify::new()) }
+    }
+
+    async fn run(&self, command: AdminCommand) -> AdminResponse {
+        admin::execute(&self.listener, &self.restart, command).await
+    }
+
+    fn connect(&self, port: u16) -> (Connection, UnboundedReceiver<SequencedPacket>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let connection = Connection::new(sender, SocketAddr::new(IP, port));
+        assert!(self.listener.force_lock().on_connect(&connection));
+        self.send(&connection, serde_json::json!({ "type": "hello", "protocolVersion": PROTOCOL_VERSION }));
+        (connection, receiver)
+    }
+
+    fn send(&self, connection: &Connection, packet: serde_json::Value) {
+        self.listener.force_lock().on_message(connection, &Message::text(packet.to_string()));
+    }
+
+    /// Hosts a room from a new connection
+    async fn host(&self, port: u16) -> (Connection, UnboundedReceiver<SequencedPacket>, RoomCode) {
+        let (connection, mut receiver) = self.connect(port);
+        self.send(&connection, serde_json::json!({ "type": "host" }));
+        let room_code = loop {
+            if let ToClientPacket::AcceptJoin { room_code, .. } = next_packet(&mut receiver).await {
+                break room_code;
+            }
+        };
+        (connection, receiver, room_code)
+    }
+}
+
+async fn next_packet(receiver: &mut UnboundedReceiver<SequencedPacket>) -> ToClientPacket {
+    tokio::time::timeout(Duration::from_secs(5), receiver.recv()).await
+        .expect("a packet should arrive")
+        .expect("connection should still be open").packet
+}
+
+async fn receives(receiver: &mut UnboundedReceiver<SequencedPacket>, matches: impl Fn(&ToClientPacket) -> bool) {
+    while !matches(&next_packet(receiver).await) {}
+}
+
+#[test]
+fn commands_are_read_from_json() {
+    let command: AdminCommand = serde_json::from_str(r#"{"type":"kick","roomCode":"ABCD","playerId":3}"#).expect("command parses");
+    assert!(matches!(command, AdminCommand::Kick { player_id: 3, .. }));
+
+    let command: AdminCommand = serde_json::from_str(r#"{"type":"ban","ip":"10.0.0.1"}"#).expect("command parses");
+    assert!(matches!(command, AdminCommand::Ban { ip: IP, duration_secs: None }));
+}
+
+#[tokio::test]
+async fn rooms_can_be_listed_and_inspected() {
+    let console = Console::new();
+    let (_host, _receiver, room_code) = console.host(1).await;
+
+    let AdminResponse::Rooms { rooms } = console.run(AdminCommand::ListRooms).await else { panic!("rooms should be listed") };
+    assert_eq!(rooms.len(), 1);
+
+    let AdminResponse::Room { room } = console.run(AdminCommand::InspectRoom { room_code }).await else { panic!("room should be inspected") };
+    assert_eq!(room.room_code, room_code);
+    assert!(!room.inspection.preview.in_game);
+    assert_eq!(room.inspection.phase, None);
+    let host = room.inspection.clients.first().expect("host is in the room");
+    assert!(host.host);
+    assert_eq!(host.address, Some(SocketAddr::new(IP, 1)));
+
+    assert!(matches!(console.run(AdminCommand::InspectRoom { room_code: RoomCode::new(0) }).await, AdminResponse::Error { .. }));
+}
+
+#[tokio::test]
+async fn closing_a_room_moves_everyone_out() {
+    let console = Console::new();
+    let (_host, mut receiver, room_code) = console.host(1).await;
+
+    assert!(matches!(console.run(AdminCommand::CloseRoom { room_code }).await, AdminResponse::Done));
+    receives(&mut receiver, |packet| matches!(packet, ToClientPacket::ForcedOutsideRoom)).await;
+
+    let AdminResponse::Rooms { rooms } = console.run(AdminCommand::ListRooms).await else { panic!("rooms should be listed") };
+    assert!(rooms.is_empty());
+}
+
+#[tokio::test]
+async fn operators_can_kick_without_being_host() {
+    let console = Console::new();
+    let (_host, _host_receiver, room_code) = console.host(1).await;
+    let (player, mut player_receiver) = console.connect(2);
+    console.send(&player, serde_json::json!({ "type": "join", "roomCode": room_code }));
+    let player_id = loop {
+        if let ToClientPacket::AcceptJoin { player_id, .. } = next_packet(&mut player_receiver).await {
+            break player_id;
+        }
+    };
+
+    assert!(matches!(console.run(AdminCommand::Kick { room_code, player_id }).await, AdminResponse::Done));
+    receives(&mut player_receiver, |packet| matches!(packet, ToClientPacket::ForcedOutsideRoom)).await;
+
+    let AdminResponse::Room { room } = console.run(AdminCommand::InspectRoom { room_code }).await else { panic!("room should be inspected") };
+    assert!(room.inspection.clients.iter().all(|client| client.id != player_id));
+}
+
+#[tokio::test]
+async fn announcements_reach_every_client() {
+    let console = Console::new();
+    let (_host, mut host_receiver, _) = console.host(1).await;
+    let (_outside, mut outside_receiver) = console.connect(2);
+
+    let response = console.run(AdminCommand::Announce { message: "Maintenance tonight".to_string() }).await;
+    assert!(matches!(response, AdminResponse::Announced { clients: 2 }));
+
+    for receiver in [&mut host_receiver, &mut outside_receiver] {
+        receives(receiver, |packet| matches!(packet, ToClientPacket::ServerAnnouncement { message } if message == "Maintenance tonight")).await;
+    }
+}
+
+#[tokio::test]
+async fn banning_disconnects_the_ip() {
+    let console = Console::new();
+    let (_client, mut receiver) = console.connect(1);
+
+    let response = console.run(AdminCommand::Ban { ip: IP, duration_secs: Some(60) }).await;
+    assert!(matches!(response, AdminResponse::Banned { disconnected: 1 }));
+    receives(&mut receiver, |packet| matches!(packet, ToClientPacket::ForcedDisconnect)).await;
+
+    let (sender, _receiver) = mpsc::unbounded_channel();
+    assert!(!console.listener.force_lock().on_connect(&Connection::new(sender, SocketAddr::new(IP, 2))));
+}
+
+#[tokio::test(start_paused = true)]
+async fn restarts_warn_clients_first() {
+    let console = Console::new();
+    let (_client, mut receiver) = console.connect(1);
+
+    let response = console.run(AdminCommand::Restart { delay_secs: 30 }).await;
+    assert!(matches!(response, AdminResponse::Restarting { delay_secs: 30, rooms_kept: false }));
+    receives(&mut receiver, |packet| matches!(packet, ToClientPacket::ServerRestarting { in_secs: 30 })).await;
+
+    let started = tokio::time::Instant::now();
+    console.restart.notified().await;
+    assert!(started.elapsed() >= Duration::from_secs(30));
+}
+
+#[tokio::test]
+async fn the_socket_is_private_and_closes_its_connections_when_stopped() {
+    let console = Console::new();
+    let path = std::env::temp_dir().join(format!("mafia-admin-test-{}.sock", std::process::id()));
+    let (stop, stop_receiver) = broadcast::channel(1);
+    let server = tokio::spawn(admin::serve(path.clone(), console.listener.clone(), console.restart.clone(), stop_receiver));
+
+    let stream = loop {
+        if let Ok(stream) = UnixStream::connect(&path).await {
+            break stream;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    };
+    let mode = std::fs::metadata(&path).expect("socket exists").permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    writer.write_all(b"{\"type\":\"listRooms\"}\n").await.expect("command is sent");
+    let response = lines.next_line().await.expect("response is read").expect("console answers");
+    assert!(response.contains("\"rooms\""), "{response}");
+
+    stop.send(()).expect("console is listening");
+    tokio::time::timeout(Duration::from_secs(5), server).await.expect("console stops").expect("console doesn't panic");
+    let closed = tokio::time::timeout(Duration::from_secs(5), lines.next_line()).await.expect("connection is closed");
+    assert!(matches!(closed, Ok(None)));
+    assert!(!path.exists());
+}
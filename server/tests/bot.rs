@@ -0,0 +1,108 @@
+use std::time::Duration;
+
+use mafia_server::{
+    game::{
+        player::PlayerReference, replay::playback::ReplayPlayback, role::Role, role_list::{RoleOutline, RoleSet}, Game
+    },
+    lobby::{lobby_client::Ready, on_client_message::LobbyClientMessageResult, Lobby},
+    packet::ToServerPacket,
+    room::{RemoveRoomClientResult, RoomState},
+    websocket_connections::connection::ClientSender
+};
+use tokio::sync::mpsc;
+
+const BOTS: usize = 6;
+
+fn start_game_with_bots() -> Game {
+    start_game_with_bots_and_roles(vec![RoleOutline::default(); BOTS + 1])
+}
+
+fn start_game_with_bots_and_roles(role_list: Vec<RoleOutline>) -> Game {
+    let (sender, _receiver) = mpsc::unbounded_channel();
+    let sender = ClientSender::new(sender);
+
+    let mut lobby = Lobby::new();
+    let host = lobby.join_client(&sender).expect("host should join").id;
+
+    for _ in 0..BOTS {
+        lobby.on_client_message(&sender, host, ToServerPacket::HostAddBot);
+    }
+    assert_eq!(lobby.clients.len(), BOTS + 1);
+    assert!(lobby.clients.values().filter(|client| client.is_bot()).all(|client| client.ready == Ready::Ready));
+
+    lobby.settings.role_list.0 = role_list;
+    lobby.settings.enabled_roles = Role::values().into_iter().collect();
+
+    match lobby.on_client_message(&sender, host, ToServerPacket::StartGame) {
+        LobbyClientMessageResult::StartGame(game) => game,
+        _ => panic!("game should start"),
+    }
+}
+
+#[test]
+fn bots_cant_keep_lobby_open() {
+    let (sender, _receiver) = mpsc::unbounded_channel();
+    let sender = ClientSender::new(sender);
+
+    let mut lobby = Lobby::new();
+    let host = lobby.join_client(&sender).expect("host should join").id;
+    lobby.on_client_message(&sender, host, ToServerPacket::HostAddBot);
+
+    let bot = *lobby.clients.keys().find(|id| **id != host).expect("bot was added");
+    lobby.on_client_message(&sender, host, ToServerPacket::SetPlayerHost { player_id: bot });
+    assert!(!lobby.is_host(bot));
+
+    assert!(matches!(lobby.remove_client(host), RemoveRoomClientResult::RoomShouldClose));
+}
+
+#[test]
+fn bots_play_the_game() {
+    let mut game = start_game_with_bots();
+
+    for _ in 0..300 {
+        let _ = game.tick(Duration::from_secs(1));
+    }
+
+    assert!(game.day_number() > 1);
+    let bots: Vec<PlayerReference> = PlayerReference::all_players(&game).filter(|player| player.is_bot(&game)).collect();
+    assert_eq!(bots.len(), BOTS);
+    assert!(bots.iter().all(|bot| !bot.will(&game).is_empty()));
+}
+
+#[test]
+fn bots_act_the_same_in_replays() {
+    let mut game = start_game_with_bots();
+
+    for _ in 0..600 {
+        let _ = game.tick(Duration::from_secs(1));
+    }
+
+    let mut playback = ReplayPlayback::new(game.replay().clone()).expect("replay should start");
+    playback.play_to_end().expect("replay should not diverge");
+
+    assert_eq!(playback.game().day_number(), game.day_number());
+    assert_eq!(playback.game().graves.len(), game.graves.len());
+    for player in PlayerReference::all_players(&game) {
+        assert_eq!(player.alive(playback.game()), player.alive(&game));
+        assert_eq!(player.will(playback.game()), player.will(&game));
+    }
+}
+
+#[test]
+fn bots_wills_claim_town_roles() {
+    let mut role_list = vec![RoleOutline::new_exact(Role::Villager); 4];
+    role_list.extend(vec![RoleOutline::new_exact(Role::Goon); 3]);
+    let mut game = start_game_with_bots_and_roles(role_list);
+
+    let _ = game.tick(Duration::from_secs(1));
+
+    let bots: Vec<PlayerReference> = PlayerReference::all_players(&game).filter(|player| player.is_bot(&game)).collect();
+    assert!(bots.iter().any(|bot| bot.role(&game) == Role::Goon));
+    for bot in bots {
+        let will = bot.will(&game);
+        assert!(
+            RoleSet::Town.get_roles().into_iter().any(|role| *will == format!("I am the {role:?}")),
+            "{:?} wrote {will:?}", bot.role(&game)
+        );
+    }
+}
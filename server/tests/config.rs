@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+
+use mafia_server::config::{ConfigError, ServerConfig};
+
+fn variables(variables: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+    let variables: HashMap<String, String> = variables.iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+    move |name| variables.get(name).cloned()
+}
+
+#[test]
+fn example_config_is_valid() {
+    let config = ServerConfig::from_toml(include_str!("../config.example.toml")).expect("example config parses");
+    config.validate().expect("example config is valid");
+}
+
+#[test]
+fn missing_settings_use_defaults() {
+    let config = ServerConfig::from_toml("max_rooms = 5").expect("config parses");
+
+    assert_eq!(config.max_rooms, 5);
+    assert_eq!(config.max_name_length, ServerConfig::default().max_name_length);
+}
+
+#[test]
+fn unknown_settings_are_rejected() {
+    assert!(ServerConfig::from_toml("max_romos = 5").is_err());
+}
+
+#[test]
+fn environment_variables_override_file() {
+    let mut config = ServerConfig::from_toml("ws_address = \"127.0.0.1:8081\"\nmax_rooms = 5").expect("config parses");
+
+    config.override_with(variables(&[("MAX_ROOMS", "12"), ("DATA_DIRECTORY", "data")])).expect("variables are valid");
+
+    assert_eq!(config.ws_address, "127.0.0.1:8081");
+    assert_eq!(config.max_rooms, 12);
+    assert_eq!(config.data_directory, Some("data".into()));
+}
+
+#[test]
+fn invalid_environment_variable_is_rejected() {
+    let mut config = ServerConfig::default();
+
+    let result = config.override_with(variables(&[("TICK_INTERVAL_MILLIS", "fast")]));
+
+    assert!(matches!(result, Err(ConfigError::EnvironmentVariable { name: "TICK_INTERVAL_MILLIS", .. })));
+}
+
+#[test]
+fn invalid_settings_are_rejected() {
+    for setting in [
+        "tick_interval_millis = 0",
+        "message_rate_limit = 0",
+        "room_code_length = 0",
+        "heartbeat_timeout_secs = 5",
+        "max_connections = 0",
+        "packet_rate_limit_window_secs = 0",
+        "restored_lobby_disconnect_timer_secs = 0",
+    ] {
+        let config = ServerConfig::from_toml(&format!("ws_address = \"127.0.0.1:8081\"\n{setting}")).expect("config parses");
+        assert!(config.validate().is_err(), "{config:?} should be invalid");
+    }
+
+    let config = ServerConfig::from_toml("ws_address = \"not an address\"").expect("config parses");
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn address_has_to_be_set() {
+    let config = ServerConfig::from_toml("max_rooms = 5").expect("config parses");
+    assert!(matches!(config.validate(), Err(ConfigError::Invalid(_))));
+
+    let mut config = config;
+    config.override_with(variables(&[("WS_ADDRESS", "127.0.0.1:8081")])).expect("variables are valid");
+    config.validate().expect("config is valid once the address is set");
+}
@@ -0,0 +1,59 @@
+use std::time::Duration;
+
+use mafia_server::{
+    config::ServerConfig,
+    websocket_connections::heartbeat::{ConnectionRating, Heartbeat}
+};
+
+#[tokio::test(start_paused = true)]
+async fn answered_pings_measure_the_round_trip() {
+    let mut heartbeat = Heartbeat::new();
+    assert_eq!(heartbeat.quality().rating, ConnectionRating::Unknown);
+
+    let ping = heartbeat.ping();
+    tokio::time::advance(Duration::from_millis(100)).await;
+    heartbeat.pong(&ping);
+    assert_eq!(heartbeat.quality().round_trip_millis, Some(100));
+    assert_eq!(heartbeat.quality().rating, ConnectionRating::Good);
+
+    // One slow answer only moves the average part of the way
+    let ping = heartbeat.ping();
+    tokio::time::advance(Duration::from_millis(900)).await;
+    heartbeat.pong(&ping);
+    assert_eq!(heartbeat.quality().round_trip_millis, Some(300));
+    assert_eq!(heartbeat.quality().rating, ConnectionRating::Fair);
+}
+
+#[tokio::test(start_paused = true)]
+async fn pongs_for_old_pings_are_ignored() {
+    let mut heartbeat = Heartbeat::new();
+
+    let old_ping = heartbeat.ping();
+    heartbeat.ping();
+    tokio::time::advance(Duration::from_millis(50)).await;
+    heartbeat.pong(&old_ping);
+
+    assert_eq!(heartbeat.quality().round_trip_millis, None);
+}
+
+#[tokio::test(start_paused = true)]
+async fn silent_connections_lag_then_time_out() {
+    let mut heartbeat = Heartbeat::new();
+    heartbeat.ping();
+
+    tokio::time::advance(ServerConfig::get().heartbeat_interval() + Duration::from_secs(1)).await;
+    assert_eq!(heartbeat.quality().rating, ConnectionRating::Lagging);
+    assert!(!heartbeat.timed_out());
+
+    // Pings keep being sent while the client is silent, and it's still lagging after each one
+    let timeout = tokio::time::Instant::now() + ServerConfig::get().heartbeat_timeout();
+    while tokio::time::Instant::now() < timeout {
+        heartbeat.ping();
+        assert_eq!(heartbeat.quality().rating, ConnectionRating::Lagging);
+        tokio::time::advance(ServerConfig::get().heartbeat_interval()).await;
+    }
+    assert!(heartbeat.timed_out());
+
+    heartbeat.heard();
+    assert!(!heartbeat.timed_out());
+}
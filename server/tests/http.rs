@@ -0,0 +1,100 @@
+use std::{io, sync::{Arc, Mutex}};
+
+use mafia_server::{
+    websocket_connections::http::{route, HttpRequest},
+    websocket_listener::WebsocketListener
+};
+use tokio::{io::{AsyncReadExt, AsyncWriteExt}, net::{TcpListener, TcpStream}};
+
+fn listener() -> Arc<Mutex<WebsocketListener>> {
+    Arc::new(Mutex::new(WebsocketListener::new(None, None)))
+}
+
+/// Opens a real connection, returning the client and server sides of it
+async fn connection() -> (TcpStream, TcpStream) {
+    let tcp_listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let client = TcpStream::connect(tcp_listener.local_addr().expect("address")).await.expect("connect");
+    let (server, _) = tcp_listener.accept().await.expect("accept");
+    (client, server)
+}
+
+/// Sends `request` over a real connection, and returns what the server side read
+async fn read(request: &str) -> HttpRequest {
+    let (mut client, server) = connection().await;
+    client.write_all(request.as_bytes()).await.expect("write");
+    HttpRequest::read(server).await.expect("request should be read").0
+}
+
+#[test]
+fn health_check_is_ok() {
+    let response = route(&listener(), "GET", "/health");
+
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body, r#"{"status":"ok"}"#);
+}
+
+#[test]
+fn stats_count_an_empty_server() {
+    let response = route(&listener(), "GET", "/stats");
+    let stats: serde_json::Value = serde_json::from_str(&response.body).expect("stats are json");
+
+    assert_eq!(response.status, 200);
+    assert_eq!(stats["connectedClients"], 0);
+    assert_eq!(stats["roomsInLobby"], 0);
+    assert_eq!(stats["roomsInGame"], 0);
+    assert!(stats["uptimeSecs"].is_u64());
+}
+
+#[test]
+fn room_list_is_empty_without_rooms() {
+    let response = route(&listener(), "GET", "/rooms");
+
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body, "{}");
+}
+
+#[test]
+fn unknown_routes_and_methods_are_refused() {
+    assert_eq!(route(&listener(), "GET", "/nothing").status, 404);
+    assert_eq!(route(&listener(), "POST", "/health").status, 405);
+}
+
+#[tokio::test]
+async fn plain_requests_are_told_apart_from_websocket_upgrades() {
+    let request = read("GET /stats?pretty HTTP/1.1\r\nHost: localhost\r\n\r\n").await;
+    assert_eq!(request.method, "GET");
+    assert_eq!(request.path, "/stats");
+    assert!(!request.is_websocket_upgrade());
+
+    let request = read("GET / HTTP/1.1\r\nHost: localhost\r\nConnection: Upgrade\r\nUpgrade: WebSocket\r\n\r\n").await;
+    assert!(request.is_websocket_upgrade());
+}
+
+#[tokio::test]
+async fn heads_sent_in_pieces_are_read_and_given_back_whole() {
+    let (mut client, server) = connection().await;
+    let head = "GET / HTTP/1.1\r\nHost: localhost\r\n\r\n";
+    let (start, end) = head.split_at(10);
+
+    let reading = tokio::spawn(HttpRequest::read(server));
+    client.write_all(start.as_bytes()).await.expect("write");
+    tokio::task::yield_now().await;
+    client.write_all(end.as_bytes()).await.expect("write");
+    client.write_all(b"body").await.expect("write");
+
+    let (request, mut stream) = reading.await.expect("reading doesn't panic").expect("request should be read");
+    assert_eq!(request.path, "/");
+
+    let mut replayed = vec![0; head.len().saturating_add(4)];
+    stream.read_exact(&mut replayed).await.expect("read");
+    assert_eq!(replayed, format!("{head}body").into_bytes());
+}
+
+#[tokio::test(start_paused = true)]
+async fn clients_that_stop_partway_through_the_head_are_timed_out() {
+    let (mut client, server) = connection().await;
+    client.write_all(b"GET / HTTP/1.1\r\nHost: loc").await.expect("write");
+
+    let Err(error) = HttpRequest::read(server).await else { panic!("an unfinished head shouldn't be read") };
+    assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+}
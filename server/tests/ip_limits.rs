@@ -0,0 +1,127 @@
+use std::{net::{IpAddr, SocketAddr}, time::Duration};
+
+use mafia_server::{
+    config::ServerConfig,
+    websocket_connections::connection::Connection, websocket_listener::{IpLimitExceeded, IpLimits, WebsocketListener}
+};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message;
+
+const IP: IpAddr = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 1));
+const OTHER_IP: IpAddr = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 2));
+
+fn limits() -> IpLimits {
+    IpLimits::new(&ServerConfig {
+        max_connections_per_ip: 2,
+        room_creation_limit: 2,
+        room_creation_window_secs: 60,
+        packet_rate_limit: 1,
+        packet_rate_limit_window_secs: 10,
+        ip_ban_duration_secs: 300,
+        ..ServerConfig::default()
+    })
+}
+
+#[tokio::test(start_paused = true)]
+async fn connections_are_limited_per_ip() {
+    let mut limits = limits();
+
+    assert_eq!(limits.connect(IP), Ok(()));
+    assert_eq!(limits.connect(IP), Ok(()));
+    assert_eq!(limits.connect(IP), Err(IpLimitExceeded::TooManyConnections));
+    assert_eq!(limits.connect(OTHER_IP), Ok(()));
+
+    limits.disconnect(IP);
+    assert_eq!(limits.connect(IP), Ok(()));
+}
+
+#[tokio::test(start_paused = true)]
+async fn room_creation_is_limited_until_the_window_passes() {
+    let mut limits = limits();
+
+    assert_eq!(limits.create_room(IP), Ok(()));
+    assert_eq!(limits.create_room(IP), Ok(()));
+    assert_eq!(limits.create_room(IP), Err(IpLimitExceeded::TooManyRoomsCreated));
+
+    // Reconnecting doesn't reset the limit
+    limits.connect(IP).expect("connection is allowed");
+    limits.disconnect(IP);
+    assert_eq!(limits.create_room(IP), Err(IpLimitExceeded::TooManyRoomsCreated));
+
+    tokio::time::advance(Duration::from_secs(60)).await;
+    assert_eq!(limits.create_room(IP), Ok(()));
+}
+
+#[tokio::test(start_paused = true)]
+async fn too_many_packets_ban_the_ip_for_a_while() {
+    let mut limits = limits();
+
+    for _ in 0..10 {
+        assert_eq!(limits.packet(IP), Ok(()));
+    }
+    assert_eq!(limits.packet(IP), Err(IpLimitExceeded::TooManyPackets));
+    assert!(limits.is_banned(IP));
+    assert!(!limits.is_banned(OTHER_IP));
+
+    assert_eq!(limits.connect(IP), Err(IpLimitExceeded::Banned));
+    assert_eq!(limits.create_room(IP), Err(IpLimitExceeded::Banned));
+
+    tokio::time::advance(Duration::from_secs(300)).await;
+    assert!(!limits.is_banned(IP));
+    assert_eq!(limits.packet(IP), Ok(()));
+}
+
+#[test]
+fn flooding_closes_the_connection_and_refuses_new_ones() {
+    let mut listener = WebsocketListener::new(None, None);
+    let (sender, _receiver) = mpsc::unbounded_channel();
+    let connection = Connection::new(sender.clone(), SocketAddr::new(IP, 1));
+    assert!(listener.on_connect(&connection));
+
+    let config = ServerConfig::get();
+    let allowed = config.packet_rate_limit_window_secs.saturating_mul(config.packet_rate_limit.into());
+    let ping = Message::text(r#"{"type":"ping"}"#);
+    for _ in 0..allowed {
+        assert!(listener.on_message(&connection, &ping));
+    }
+    assert!(!listener.on_message(&connection, &ping));
+    listener.on_disconnect(connection);
+
+    assert!(!listener.on_connect(&Connection::new(sender.clone(), SocketAddr::new(IP, 2))));
+    assert!(listener.on_connect(&Connection::new(sender, SocketAddr::new(OTHER_IP, 1))));
+}
+
+#[test]
+fn connections_count_before_their_request_is_read() {
+    let mut listener = WebsocketListener::new(None, None);
+    let per_ip = u16::try_from(ServerConfig::get().max_connections_per_ip).expect("limit is small");
+
+    for port in 0..per_ip {
+        assert!(listener.admit(SocketAddr::new(IP, port)));
+    }
+    assert!(!listener.admit(SocketAddr::new(IP, per_ip)));
+
+    // One only made an HTTP request, so it never connected
+    listener.on_abandon(SocketAddr::new(IP, 0));
+    assert!(listener.admit(SocketAddr::new(IP, per_ip)));
+
+    // Admitted connections aren't counted twice when they connect
+    let (sender, _receiver) = mpsc::unbounded_channel();
+    assert!(listener.on_connect(&Connection::new(sender, SocketAddr::new(IP, 1))));
+    assert!(!listener.admit(SocketAddr::new(IP, per_ip.saturating_add(1))));
+}
+
+#[test]
+fn pongs_dont_count_towards_the_packet_limit() {
+    let mut listener = WebsocketListener::new(None, None);
+    let (sender, _receiver) = mpsc::unbounded_channel();
+    let connection = Connection::new(sender, SocketAddr::new(IP, 1));
+    assert!(listener.on_connect(&connection));
+
+    let config = ServerConfig::get();
+    let allowed = config.packet_rate_limit_window_secs.saturating_mul(config.packet_rate_limit.into());
+    for _ in 0..allowed.saturating_mul(2) {
+        assert!(listener.on_message(&connection, &Message::Pong(Default::default())));
+    }
+    assert!(listener.on_message(&connection, &Message::text(r#"{"type":"ping"}"#)));
+}
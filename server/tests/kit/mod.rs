@@ -1,6 +1,7 @@
 use mafia_server::game::{
     chat::ChatMessageVariant, 
     player::PlayerReference, 
+    rng::GameSeed,
     settings::Settings, 
     test::mock_game, 
     Game
@@ -25,10 +26,16 @@ macro_rules! scenario {
     };
     ($game:ident where
         $($name:ident: $role:ident),*
+    ) => {
+        kit::scenario!($game seeded (kit::_init::DEFAULT_SEED) where $($name: $role),*);
+    };
+    ($game:ident seeded $seed:tt where
+        $($name:ident: $role:ident),*
     ) => {
         let mut scenario = kit::_init::create_basic_scenario(
             // vec![$(RoleState::$role($role::default())),*]
-            vec![$(Role::$role),*]
+            vec![$(Role::$role),*],
+            $seed
         );
 
         let game = &mut scenario.game;
@@ -91,7 +98,9 @@ pub mod _init {
 
     use super::*;
 
-    pub fn create_basic_scenario(roles: Vec<Role>) -> TestScenario {
+    pub const DEFAULT_SEED: GameSeed = 0;
+
+    pub fn create_basic_scenario(roles: Vec<Role>, seed: GameSeed) -> TestScenario {
         let mut role_list = Vec::new();
         for role in roles.iter() {
             role_list.push(RoleOutline { options: 
@@ -99,6 +108,7 @@ pub mod _init {
                     roles: RoleOutlineOptionRoles::Role { role: *role },
                     insider_groups: RoleOutlineOptionInsiderGroups::RoleDefault,
                     win_condition: RoleOutlineOptionWinCondition::RoleDefault,
+                    weight: None,
                 }]
             });
         }
@@ -107,7 +117,7 @@ pub mod _init {
             role_list: RoleList(role_list),
             enabled_roles: Role::values().into_iter().collect(),
             ..Default::default()
-        }, roles.len() as u8){
+        }, roles.len() as u8, seed){
             Ok(game) => game,
             Err(err) => panic!("Failed to create game: {:?}", err),
         };
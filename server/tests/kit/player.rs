@@ -1,4 +1,5 @@
-use mafia_server::{game::{ability_input::*, chat::ChatMessageVariant, phase::PhaseState, player::{PlayerIndex, PlayerReference}, role::{Role, RoleState}, verdict::Verdict, Game}, packet::ToServerPacket};
+use mafia_server::{game::{ability_input::*, chat::ChatMessageVariant, phase::PhaseState, player::{PlayerIndex, PlayerReference}, role::{Role, RoleState}, verdict::Verdict, Game}, packet::ToServerPacket, websocket_connections::connection::{ClientSender, Connection, SequencedPacket}};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
 
 #[derive(Clone, Copy, Debug)]
 pub struct TestPlayer(PlayerReference, *mut Game);
@@ -22,6 +23,21 @@ impl TestPlayer {
         TestPlayer(player, game as *const Game as *mut Game)
     }
 
+    /// Connects a client to this player, returning what it gets sent
+    pub fn connect(&self) -> UnboundedReceiver<SequencedPacket> {
+        let (sender, receiver) = unbounded_channel();
+        self.0.connect(game!(self), ClientSender::new(sender));
+        receiver
+    }
+
+    /// Connects a websocket client that agreed to no capabilities, returning what it gets sent
+    pub fn connect_without_capabilities(&self) -> UnboundedReceiver<SequencedPacket> {
+        let (sender, receiver) = unbounded_channel();
+        let connection = Connection::new(sender, std::net::SocketAddr::from(([127, 0, 0, 1], self.index().into())));
+        self.0.connect(game!(self), connection.sender());
+        receiver
+    }
+
     pub fn index(&self) -> PlayerIndex {
         self.0.index()
     }
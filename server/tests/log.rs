@@ -0,0 +1,64 @@
+use mafia_server::{
+    config::ServerConfig, game::phase::PhaseType,
+    log::{self, LogContext, LogFormat, LogLevel}, websocket_listener::RoomCode
+};
+
+fn json_line(level: LogLevel, message: &str) -> serde_json::Value {
+    // Every test in this file logs in JSON
+    log::init(LogLevel::Important, LogFormat::Json);
+    serde_json::from_str(&log::line(level, "Test", message)).expect("line is json")
+}
+
+#[test]
+fn levels_below_the_configured_level_are_disabled() {
+    json_line(LogLevel::Info, "");
+
+    assert!(!log::enabled(LogLevel::Info));
+    assert!(log::enabled(LogLevel::Important));
+    assert!(log::enabled(LogLevel::Fatal));
+}
+
+#[test]
+fn lines_outside_rooms_have_no_context() {
+    LogContext::set(LogContext::NONE);
+    let line = json_line(LogLevel::Error, "Something failed");
+
+    assert_eq!(line["level"], "error");
+    assert_eq!(line["prefix"], "Test");
+    assert_eq!(line["message"], "Something failed");
+    assert!(line.get("room_code").is_none());
+    assert!(line.get("client_id").is_none());
+}
+
+#[test]
+fn lines_inside_rooms_carry_the_room_client_and_game_time() {
+    LogContext::set(LogContext { room_code: Some(RoomCode::new(42)), ..LogContext::NONE });
+    LogContext::set_client(Some(3));
+    LogContext::set_game_time(2, PhaseType::Night);
+
+    let line = json_line(LogLevel::Error, "Received message from invalid client id: 3");
+    LogContext::set(LogContext::NONE);
+
+    assert_eq!(line["room_code"], "BW");
+    assert_eq!(line["client_id"], 3);
+    assert_eq!(line["day"], 2);
+    assert_eq!(line["phase"], "night");
+}
+
+#[test]
+fn games_outside_rooms_dont_set_game_time() {
+    LogContext::set(LogContext::NONE);
+    LogContext::set_game_time(2, PhaseType::Night);
+
+    assert_eq!(LogContext::get(), LogContext::NONE);
+}
+
+#[test]
+fn log_settings_are_read_from_config() {
+    let mut config = ServerConfig::from_toml("log_level = \"error\"").expect("config parses");
+    config.override_with(|name| (name == "LOG_FORMAT").then(|| "json".to_string())).expect("variables are valid");
+
+    assert_eq!(config.log_level, LogLevel::Error);
+    assert_eq!(config.log_format, LogFormat::Json);
+    assert!(config.override_with(|name| (name == "LOG_LEVEL").then(|| "loud".to_string())).is_err());
+}
@@ -0,0 +1,63 @@
+use std::time::Duration;
+
+use mafia_server::{
+    game::{game_conclusion::GameConclusion, RejectStartReason},
+    metrics, packet::ToServerPacket, websocket_listener::ServerStats
+};
+
+fn stats() -> ServerStats {
+    ServerStats { connected_clients: 3, rooms_in_lobby: 2, rooms_in_game: 1, uptime_secs: 60 }
+}
+
+/// The value of the sample named `sample`, like `mafia_games_started_total` or `mafia_rooms_open{state="game"}`
+fn sample(rendered: &str, sample: &str) -> Option<f64> {
+    rendered.lines()
+        .filter(|line| !line.starts_with('#'))
+        .find_map(|line| line.strip_prefix(sample)?.strip_prefix(' ')?.parse().ok())
+}
+
+#[test]
+fn gauges_come_from_stats() {
+    let rendered = metrics::render(&stats());
+
+    assert_eq!(sample(&rendered, "mafia_rooms_open{state=\"lobby\"}"), Some(2.0));
+    assert_eq!(sample(&rendered, "mafia_rooms_open{state=\"game\"}"), Some(1.0));
+    assert_eq!(sample(&rendered, "mafia_connected_clients"), Some(3.0));
+    assert_eq!(sample(&rendered, "mafia_uptime_seconds"), Some(60.0));
+}
+
+#[test]
+fn counters_are_labelled_by_wire_name() {
+    // Metrics are global, so only check what this test recorded
+    let before = metrics::render(&stats());
+
+    metrics::packet_received(&ToServerPacket::Ping);
+    metrics::packet_received(&ToServerPacket::SendChatMessage { text: "hi".to_string(), block: false });
+    metrics::packet_received(&ToServerPacket::RoomListRequest);
+    metrics::start_rejected(&RejectStartReason::RoleListTooSmall);
+    metrics::game_ended(&GameConclusion::Town, Duration::from_secs(600));
+    metrics::rejoined(true);
+
+    let after = metrics::render(&stats());
+    let increase = |name: &str| sample(&after, name).unwrap_or(0.0) - sample(&before, name).unwrap_or(0.0);
+
+    assert_eq!(increase("mafia_packets_received_total{type=\"ping\"}"), 1.0);
+    assert_eq!(increase("mafia_packets_received_total{type=\"sendChatMessage\"}"), 1.0);
+    // Renamed packets are counted by the name they're sent with
+    assert_eq!(increase("mafia_packets_received_total{type=\"lobbyListRequest\"}"), 1.0);
+    assert_eq!(increase("mafia_starts_rejected_total{reason=\"roleListTooSmall\"}"), 1.0);
+    assert_eq!(increase("mafia_games_ended_total{conclusion=\"town\"}"), 1.0);
+    assert_eq!(increase("mafia_game_length_seconds_sum"), 600.0);
+    assert_eq!(increase("mafia_rejoins_total{result=\"accepted\"}"), 1.0);
+}
+
+#[test]
+fn every_metric_has_a_type() {
+    let rendered = metrics::render(&stats());
+
+    for line in rendered.lines().filter(|line| !line.starts_with('#')) {
+        let name = line.split(['{', ' ']).next().unwrap_or_default();
+        let name = name.trim_end_matches("_sum").trim_end_matches("_count");
+        assert!(rendered.contains(&format!("# TYPE {name} ")), "{name} has no type");
+    }
+}
@@ -0,0 +1,78 @@
+use mafia_server::{
+    config::ServerConfig, packet::ToClientPacket,
+    websocket_connections::{connection::{ClientSender, SequencedPacket}, outbox::Outbox}
+};
+
+fn outbox_with(count: u64) -> Outbox {
+    let mut outbox = Outbox::new();
+    for _ in 0..count {
+        outbox.push(ToClientPacket::Pong);
+    }
+    outbox
+}
+
+fn sequences(missed: Option<Vec<(u64, ToClientPacket)>>) -> Option<Vec<u64>> {
+    missed.map(|missed| missed.into_iter().map(|(sequence, _)| sequence).collect())
+}
+
+#[test]
+fn packets_are_numbered_from_one() {
+    let mut outbox = Outbox::new();
+    assert_eq!(outbox.push(ToClientPacket::Pong), 1);
+    assert_eq!(outbox.push(ToClientPacket::Pong), 2);
+}
+
+#[test]
+fn only_packets_after_the_last_received_are_missed() {
+    let outbox = outbox_with(5);
+
+    assert_eq!(sequences(outbox.missed_since(3)), Some(vec![4, 5]));
+    assert_eq!(sequences(outbox.missed_since(5)), Some(vec![]));
+    assert_eq!(sequences(outbox.missed_since(0)), Some(vec![1, 2, 3, 4, 5]));
+    // The client got packets this outbox never sent, like from before a restart
+    assert_eq!(sequences(outbox.missed_since(6)), None);
+}
+
+#[test]
+fn forgotten_packets_cant_be_replayed() {
+    let size = u64::try_from(ServerConfig::get().client_outbox_size).expect("outbox size fits");
+    let outbox = outbox_with(size.saturating_add(10));
+
+    assert!(outbox.missed_since(0).is_none());
+    assert!(outbox.missed_since(9).is_none());
+    assert_eq!(sequences(outbox.missed_since(10)).map(|missed| missed.len()), Some(ServerConfig::get().client_outbox_size));
+}
+
+#[test]
+fn held_packets_are_numbered_but_only_sent_once_released() {
+    let (tx, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+    let outbox = std::sync::Arc::new(std::sync::Mutex::new(outbox_with(2)));
+    let sender = ClientSender::new(tx).with_outbox(outbox.clone());
+
+    outbox.lock().expect("outbox isn't poisoned").hold();
+    sender.send(ToClientPacket::Pong);
+    sender.send(ToClientPacket::Pong);
+    assert!(receiver.try_recv().is_err());
+
+    let held = outbox.lock().expect("outbox isn't poisoned").release();
+    assert_eq!(sequences(Some(held)), Some(vec![3, 4]));
+
+    sender.send(ToClientPacket::Pong);
+    assert_eq!(receiver.try_recv().ok().and_then(|packet| packet.sequence), Some(5));
+}
+
+#[test]
+fn sequence_is_written_alongside_the_packet() {
+    let sequenced = SequencedPacket { sequence: Some(3), packet: ToClientPacket::InviteCode { code: "abc".to_string() } };
+    assert_eq!(
+        serde_json::to_value(&sequenced).expect("packet serializes"),
+        serde_json::json!({ "type": "inviteCode", "code": "abc", "sequence": 3 })
+    );
+
+    let unsequenced = SequencedPacket { sequence: None, packet: ToClientPacket::Pong };
+    assert_eq!(serde_json::to_value(&unsequenced).expect("packet serializes"), serde_json::json!({ "type": "pong" }));
+
+    let binary = rmp_serde::to_vec_named(&sequenced).expect("packet encodes");
+    let decoded: serde_json::Value = rmp_serde::from_slice(&binary).expect("packet decodes");
+    assert_eq!(decoded, serde_json::to_value(&sequenced).expect("packet serializes"));
+}
@@ -0,0 +1,194 @@
+use std::{collections::HashMap, net::SocketAddr, sync::{Arc, Mutex}, time::Duration};
+
+use mafia_server::{
+    game::chat::{ChatMessage, ChatMessageVariant}, packet::{RejectJoinReason, RoomPreviewData, ToClientPacket, ToServerPacket},
+    protocol::{self, Capability, RejectHelloReason, WireFormat, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION},
+    vec_map::VecMap, vec_set::VecSet, websocket_connections::{connection::{Connection, SequencedPacket}, ForceLock}, websocket_listener::{RoomCode, WebsocketListener}
+};
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+use tokio_tungstenite::tungstenite::Message;
+
+fn connect(listener: &mut WebsocketListener) -> (Connection, UnboundedReceiver<SequencedPacket>) {
+    connect_from(listener, 1)
+}
+
+fn connect_from(listener: &mut WebsocketListener, port: u16) -> (Connection, UnboundedReceiver<SequencedPacket>) {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let connection = Connection::new(sender, SocketAddr::from(([127, 0, 0, 1], port)));
+    assert!(listener.on_connect(&connection));
+    (connection, receiver)
+}
+
+fn send(listener: &mut WebsocketListener, connection: &Connection, packet: serde_json::Value) {
+    listener.on_message(connection, &Message::text(packet.to_string()));
+}
+
+#[test]
+fn incompatible_versions_are_rejected() {
+    let none = VecSet::new();
+
+    assert_eq!(protocol::negotiate(MIN_PROTOCOL_VERSION - 1, &none).err(), Some(RejectHelloReason::ClientTooOld));
+    assert_eq!(protocol::negotiate(PROTOCOL_VERSION.saturating_add(1), &none).err(), Some(RejectHelloReason::ClientTooNew));
+    assert!(protocol::negotiate(PROTOCOL_VERSION, &none).is_ok());
+}
+
+#[test]
+fn only_shared_capabilities_are_agreed_to() {
+    let capabilities: VecSet<Capability> = serde_json::from_str(r#"["roomAccess", "somethingFromTheFuture"]"#).expect("unknown capabilities still parse");
+
+    let agreed = protocol::negotiate(PROTOCOL_VERSION, &capabilities).expect("version is supported");
+
+    assert!(agreed.contains(&Capability::RoomAccess));
+    assert!(!agreed.contains(&Capability::Unknown));
+}
+
+#[test]
+fn clients_must_say_hello_first() {
+    let mut listener = WebsocketListener::new(None, None);
+    let (connection, mut receiver) = connect(&mut listener);
+
+    send(&mut listener, &connection, serde_json::json!({ "type": "lobbyListRequest" }));
+    assert!(matches!(receiver.try_recv().map(|packet| packet.packet), Ok(ToClientPacket::RejectHello { reason: RejectHelloReason::HelloRequired, .. })));
+
+    send(&mut listener, &connection, serde_json::json!({ "type": "hello", "protocolVersion": PROTOCOL_VERSION, "capabilities": [] }));
+    assert!(matches!(receiver.try_recv().map(|packet| packet.packet), Ok(ToClientPacket::Hello { .. })));
+
+    send(&mut listener, &connection, serde_json::json!({ "type": "lobbyListRequest" }));
+    assert!(matches!(receiver.try_recv().map(|packet| packet.packet), Ok(ToClientPacket::RoomList { .. })));
+}
+
+#[test]
+fn optional_packets_need_their_capability() {
+    let mut listener = WebsocketListener::new(None, None);
+    let (connection, _receiver) = connect(&mut listener);
+    let room_access = ToClientPacket::RoomAccess { private: true, has_password: false };
+
+    send(&mut listener, &connection, serde_json::json!({ "type": "hello", "protocolVersion": PROTOCOL_VERSION }));
+    assert!(!connection.supports(&room_access));
+    assert!(connection.supports(&ToClientPacket::Pong));
+
+    send(&mut listener, &connection, serde_json::json!({ "type": "hello", "protocolVersion": PROTOCOL_VERSION, "capabilities": ["roomAccess"] }));
+    assert!(connection.supports(&room_access));
+}
+
+#[test]
+fn packets_added_after_the_handshake_need_capabilities_too() {
+    let mut listener = WebsocketListener::new(None, None);
+    let (connection, _receiver) = connect(&mut listener);
+    let optional = [
+        ToClientPacket::ServerAnnouncement { message: "Maintenance tonight".to_string() },
+        ToClientPacket::ServerRestarting { in_secs: 30 },
+        ToClientPacket::RoleListAnalysis { analysis: Default::default() },
+        ToClientPacket::RoleListConstraints { constraints: Vec::new() },
+        ToClientPacket::YourAllowedControllersUpdate { changed: VecMap::new(), removed: Vec::new() },
+    ];
+
+    send(&mut listener, &connection, serde_json::json!({ "type": "hello", "protocolVersion": PROTOCOL_VERSION }));
+    assert!(optional.iter().all(|packet| !connection.supports(packet)));
+
+    send(&mut listener, &connection, serde_json::json!({ "type": "hello", "protocolVersion": PROTOCOL_VERSION, "capabilities": Capability::ALL }));
+    assert!(optional.iter().all(|packet| connection.supports(packet)));
+}
+
+fn large_packets() -> Vec<ToClientPacket> {
+    let chat_messages = (0..50)
+        .map(|i| ChatMessage::new_private(ChatMessageVariant::LobbyMessage { sender: format!("Player {i}"), text: "Hello everyone".to_string() }))
+        .collect();
+    let rooms = (0..20)
+        .map(|i| (RoomCode::new(i), RoomPreviewData { name: "Mafia Lobby".to_string(), in_game: i % 2 == 0, players: vec![(1, "Sarah Good".to_string()); 15] }))
+        .collect::<HashMap<_, _>>();
+
+    vec![
+        ToClientPacket::AddChatMessages { chat_messages },
+        ToClientPacket::RoomList { rooms },
+        ToClientPacket::RejectJoin { reason: RejectJoinReason::RoomFull },
+    ]
+}
+
+#[test]
+fn message_pack_carries_the_same_data_as_json() {
+    for packet in large_packets() {
+        let Message::Text(json) = WireFormat::Json.encode(&packet).expect("packet encodes") else { panic!("json should be text") };
+        let Message::Binary(binary) = WireFormat::MessagePack.encode(&packet).expect("packet encodes") else { panic!("MessagePack should be binary") };
+
+        let from_json: serde_json::Value = serde_json::from_str(json.as_str()).expect("json decodes");
+        let from_binary: serde_json::Value = rmp_serde::from_slice(&binary).expect("MessagePack decodes");
+        assert_eq!(from_json, from_binary);
+        assert!(binary.len() < json.len());
+    }
+}
+
+#[test]
+fn clients_can_send_either_format() {
+    let packet = ToServerPacket::SendLobbyMessage { text: "hi".to_string() };
+    let binary = rmp_serde::to_vec_named(&packet).expect("packet encodes");
+    let json = serde_json::to_string(&packet).expect("packet encodes");
+
+    assert!(matches!(WireFormat::decode(&Message::binary(binary)), Ok(ToServerPacket::SendLobbyMessage { text }) if text == "hi"));
+    assert!(matches!(WireFormat::decode(&Message::text(json)), Ok(ToServerPacket::SendLobbyMessage { text }) if text == "hi"));
+}
+
+#[test]
+fn message_pack_is_only_used_once_agreed_to() {
+    let mut listener = WebsocketListener::new(None, None);
+    let (connection, _receiver) = connect(&mut listener);
+
+    assert!(connection.encode(&ToClientPacket::Pong).is_ok_and(|message| message.is_text()));
+
+    send(&mut listener, &connection, serde_json::json!({ "type": "hello", "protocolVersion": PROTOCOL_VERSION, "capabilities": ["messagePack"] }));
+    assert!(connection.encode(&ToClientPacket::Pong).is_ok_and(|message| message.is_binary()));
+}
+
+async fn next_packet(receiver: &mut UnboundedReceiver<SequencedPacket>) -> SequencedPacket {
+    tokio::time::timeout(Duration::from_secs(5), receiver.recv()).await
+        .expect("a packet should arrive")
+        .expect("connection should still be open")
+}
+
+/// Everything the client has been sent, once the room has settled
+async fn received(receiver: &mut UnboundedReceiver<SequencedPacket>) -> Vec<SequencedPacket> {
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let mut packets = Vec::new();
+    while let Ok(packet) = receiver.try_recv() {
+        packets.push(packet);
+    }
+    packets
+}
+
+#[tokio::test]
+async fn dropped_clients_resume_where_they_left_off() {
+    let listener = Arc::new(Mutex::new(WebsocketListener::new(None, None)));
+    WebsocketListener::start_room_updates(listener.clone());
+    let hello = serde_json::json!({ "type": "hello", "protocolVersion": PROTOCOL_VERSION, "capabilities": [] });
+
+    let (host, mut host_receiver) = connect(&mut listener.force_lock());
+    send(&mut listener.force_lock(), &host, hello.clone());
+    send(&mut listener.force_lock(), &host, serde_json::json!({ "type": "host" }));
+    let (room_code, player_id, reconnect_token) = loop {
+        if let ToClientPacket::AcceptJoin { room_code, player_id, reconnect_token, .. } = next_packet(&mut host_receiver).await.packet {
+            break (room_code, player_id, reconnect_token);
+        }
+    };
+    let last_sequence = received(&mut host_receiver).await.iter().filter_map(|packet| packet.sequence).max().expect("room sent packets");
+
+    // The host's socket drops, and someone joins while it's gone
+    listener.force_lock().on_disconnect(host);
+    let (player, mut player_receiver) = connect_from(&mut listener.force_lock(), 2);
+    send(&mut listener.force_lock(), &player, hello.clone());
+    send(&mut listener.force_lock(), &player, serde_json::json!({ "type": "join", "roomCode": room_code }));
+    received(&mut player_receiver).await;
+
+    let (host, mut host_receiver) = connect_from(&mut listener.force_lock(), 3);
+    send(&mut listener.force_lock(), &host, hello);
+    send(&mut listener.force_lock(), &host, serde_json::json!({
+        "type": "reJoin", "roomCode": room_code, "playerId": player_id, "reconnectToken": reconnect_token, "lastSequence": last_sequence
+    }));
+
+    assert!(matches!(next_packet(&mut host_receiver).await.packet, ToClientPacket::Hello { .. }));
+    assert!(matches!(next_packet(&mut host_receiver).await.packet, ToClientPacket::AcceptJoin { resumed: true, .. }));
+    let packets = received(&mut host_receiver).await;
+    let sequences: Vec<u64> = packets.iter().filter_map(|packet| packet.sequence).collect();
+    assert_eq!(sequences.first(), Some(&last_sequence.saturating_add(1)));
+    assert!(sequences.windows(2).all(|pair| matches!(pair, [a, b] if b.checked_sub(*a) == Some(1))));
+    assert!(packets.iter().any(|packet| matches!(&packet.packet, ToClientPacket::LobbyClients { clients } if clients.len() == 2)));
+}
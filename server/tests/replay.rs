@@ -0,0 +1,257 @@
+use std::{collections::VecDeque, time::Duration};
+
+use mafia_server::{
+    client_connection::ClientConnection, config::ServerConfig,
+    game::{
+        game_client::{GameClient, GameClientLocation},
+        player::{PlayerInitializeParameters, PlayerReference},
+        replay::{playback::ReplayPlayback, Replay},
+        role_list::{RoleList, RoleOutline},
+        role::Role,
+        settings::Settings,
+        spectator::{spectator_pointer::SpectatorPointer, SpectatorInitializeParameters},
+        Game
+    },
+    packet::{ToClientPacket, ToServerPacket},
+    room::{snapshot::{RestoreError, RoomSnapshot}, Room, RoomClientID, RoomState},
+    vec_map::VecMap,
+    websocket_connections::connection::{ClientSender, SequencedPacket}
+};
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+
+const PLAYERS: u8 = 7;
+
+/// A game driven by hand, collecting packets the same way [`ReplayPlayback`] does
+struct LiveGame {
+    game: Game,
+    senders: VecMap<RoomClientID, ClientSender>,
+    receivers: Vec<(RoomClientID, UnboundedReceiver<SequencedPacket>)>,
+    packets: VecMap<RoomClientID, Vec<ToClientPacket>>,
+}
+
+impl LiveGame {
+    fn new(seed: u64) -> Self {
+        let mut senders = VecMap::new();
+        let mut receivers = Vec::new();
+        let mut clients = VecMap::new();
+        let mut players = Vec::new();
+
+        for index in 0..PLAYERS {
+            let room_client_id = RoomClientID::from(index) + 1;
+            let (sender, receiver) = mpsc::unbounded_channel();
+            let sender = ClientSender::new(sender);
+
+            clients.insert(room_client_id, GameClient {
+                client_location: GameClientLocation::Player(unsafe { PlayerReference::new_unchecked(index) }),
+                host: index == 0,
+                last_message_times: VecDeque::new(),
+            });
+            players.push(PlayerInitializeParameters {
+                connection: ClientConnection::Connected(sender.clone()),
+                name: String::new(),
+                host: index == 0,
+            });
+            senders.insert(room_client_id, sender);
+            receivers.push((room_client_id, receiver));
+        }
+
+        let spectator_id = RoomClientID::from(PLAYERS) + 1;
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let sender = ClientSender::new(sender);
+        clients.insert(spectator_id, GameClient::new_spectator(SpectatorPointer::new(0), false));
+        let spectators = vec![SpectatorInitializeParameters {
+            connection: ClientConnection::Connected(sender.clone()),
+            host: false,
+        }];
+        senders.insert(spectator_id, sender);
+        receivers.push((spectator_id, receiver));
+
+        let settings = Settings {
+            role_list: RoleList(vec![RoleOutline::default(); PLAYERS as usize]),
+            enabled_roles: Role::values().into_iter().collect(),
+            ..Default::default()
+        };
+
+        let game = Game::new("Replay".to_string(), settings, clients, players, spectators, seed)
+            .expect("game should start");
+
+        let mut live = Self { game, senders, receivers, packets: VecMap::new() };
+        live.collect_packets();
+        live
+    }
+
+    fn send(&mut self, room_client_id: RoomClientID, packet: ToServerPacket) {
+        let sender = self.senders.get(&room_client_id).expect("client exists").clone();
+        let _ = self.game.on_client_message(&sender, room_client_id, packet);
+        self.collect_packets();
+    }
+
+    fn tick(&mut self, seconds: u64) {
+        for _ in 0..seconds {
+            let _ = self.game.tick(Duration::from_secs(1));
+        }
+        self.collect_packets();
+    }
+
+    fn new_sender(&mut self, room_client_id: RoomClientID) -> ClientSender {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let sender = ClientSender::new(sender);
+        self.senders.insert(room_client_id, sender.clone());
+        self.receivers.push((room_client_id, receiver));
+        sender
+    }
+
+    fn collect_packets(&mut self) {
+        for (room_client_id, receiver) in self.receivers.iter_mut() {
+            while let Ok(SequencedPacket { packet, .. }) = receiver.try_recv() {
+                if let Some(packets) = self.packets.get_mut(room_client_id) {
+                    packets.push(packet);
+                } else {
+                    self.packets.insert(*room_client_id, vec![packet]);
+                }
+            }
+        }
+    }
+}
+
+fn play_game(seed: u64) -> LiveGame {
+    let mut live = LiveGame::new(seed);
+
+    for room_client_id in 1..=RoomClientID::from(PLAYERS) {
+        live.send(room_client_id, ToServerPacket::SetName { name: String::new() });
+        live.send(room_client_id, ToServerPacket::SaveWill { will: format!("I am client {room_client_id}") });
+    }
+
+    live.tick(50);
+    live.send(2, ToServerPacket::SendChatMessage { text: "Hello".to_string(), block: false });
+
+    // A new spectator joins
+    let sender = live.new_sender(9);
+    let result = live.game.join_client(&sender).expect("spectator should join");
+    assert_eq!(result.id, 9);
+    live.game.initialize_client(9, &sender);
+    live.collect_packets();
+
+    live.tick(120);
+
+    // A player loses connection and comes back
+    let _ = live.game.remove_client_rejoinable(3);
+    live.tick(10);
+    let sender = live.new_sender(3);
+    let _ = live.game.rejoin_client(&sender, 3).expect("player should rejoin");
+    live.game.initialize_client(3, &sender);
+    live.collect_packets();
+
+    live.send(1, ToServerPacket::HostForceSkipPhase);
+    live.tick(200);
+    live.send(4, ToServerPacket::Leave);
+    live.tick(400);
+
+    live
+}
+
+fn packets_json(packets: &VecMap<RoomClientID, Vec<ToClientPacket>>) -> String {
+    serde_json::to_string(packets).expect("packets serialize")
+}
+
+#[test]
+fn replay_reproduces_game() {
+    for seed in 0..5 {
+        let live = play_game(seed);
+
+        let mut playback = ReplayPlayback::new(live.game.replay().clone()).expect("replay should start");
+        playback.play_to_end().expect("replay should not diverge");
+
+        assert_eq!(packets_json(playback.packets()), packets_json(&live.packets));
+        assert_eq!(playback.game().current_phase().phase(), live.game.current_phase().phase());
+        assert_eq!(playback.game().day_number(), live.game.day_number());
+    }
+}
+
+#[test]
+fn replay_survives_serialization() {
+    let live = play_game(7);
+
+    let json = serde_json::to_string(live.game.replay()).expect("replay serializes");
+    let replay: Replay = serde_json::from_str(&json).expect("replay deserializes");
+
+    let mut playback = ReplayPlayback::new(replay).expect("replay should start");
+    playback.play_to_end().expect("replay should not diverge");
+
+    assert_eq!(packets_json(playback.packets()), packets_json(&live.packets));
+}
+
+#[test]
+fn restored_game_can_be_rejoined() {
+    let live = play_game(3);
+    let day_number = live.game.day_number();
+
+    let json = serde_json::to_string(&Room::Game(live.game).snapshot()).expect("snapshot serializes");
+    let snapshot: RoomSnapshot = serde_json::from_str(&json).expect("snapshot deserializes");
+
+    let Ok(Room::Game(mut game)) = Room::restore(snapshot) else {
+        panic!("game should be restored");
+    };
+    assert_eq!(game.day_number(), day_number);
+
+    let (sender, _receiver) = mpsc::unbounded_channel();
+    let sender = ClientSender::new(sender);
+
+    let result = game.rejoin_client(&sender, 2).expect("player should be able to rejoin");
+    assert_eq!(result.id, 2);
+    // Client 4 left the game before it was snapshotted
+    assert!(game.rejoin_client(&sender, 4).is_err());
+}
+
+fn snapshot_round_trip(room: &Room) -> (serde_json::Value, Result<Room, RestoreError>) {
+    let json = serde_json::to_value(room.snapshot()).expect("snapshot serializes");
+    let snapshot: RoomSnapshot = serde_json::from_value(json.clone()).expect("snapshot deserializes");
+    (json, Room::restore(snapshot))
+}
+
+#[test]
+fn snapshots_restore_to_the_same_room() {
+    let game = Room::Game(play_game(5).game);
+    let (json, restored) = snapshot_round_trip(&game);
+    let restored = serde_json::to_value(restored.expect("game should be restored").snapshot()).expect("snapshot serializes");
+    assert_eq!(restored.get("checkpoint"), json.get("checkpoint"));
+    assert_eq!(restored.pointer("/replay/settings"), json.pointer("/replay/settings"));
+    // Restoring adds every client losing connection
+    let events = |json: &serde_json::Value| json.pointer("/replay/events").and_then(|events| events.as_array()).cloned().unwrap_or_default();
+    assert!(events(&restored).starts_with(&events(&json)));
+
+    let lobby = Room::new();
+    let (json, restored) = snapshot_round_trip(&lobby);
+    let restored = restored.expect("lobby should be restored");
+    assert_eq!(serde_json::to_value(restored.snapshot()).expect("snapshot serializes"), json);
+}
+
+#[test]
+fn restored_lobbies_wait_longer_for_their_clients() {
+    let mut lobby = Room::new();
+    let (sender, _receiver) = mpsc::unbounded_channel();
+    let _ = lobby.join_client(&ClientSender::new(sender)).expect("client joins");
+
+    let (_, restored) = snapshot_round_trip(&lobby);
+    let Ok(Room::Lobby(lobby)) = restored else { panic!("lobby should be restored") };
+
+    let config = ServerConfig::get();
+    assert!(config.restored_lobby_disconnect_timer() > config.lobby_disconnect_timer());
+    for (_, client) in lobby.clients.iter() {
+        assert!(matches!(
+            client.connection,
+            ClientConnection::CouldReconnect { disconnect_timer, .. } if disconnect_timer == config.restored_lobby_disconnect_timer()
+        ));
+    }
+    assert!(!lobby.clients.is_empty());
+}
+
+#[test]
+fn games_that_play_back_somewhere_else_arent_restored() {
+    let mut json = serde_json::to_value(Room::Game(play_game(5).game).snapshot()).expect("snapshot serializes");
+    let day = json.pointer_mut("/checkpoint/day").expect("snapshot has a checkpoint");
+    *day = serde_json::json!(day.as_u64().expect("day is a number").saturating_add(1));
+    let snapshot: RoomSnapshot = serde_json::from_value(json).expect("snapshot deserializes");
+
+    assert!(matches!(Room::restore(snapshot), Err(RestoreError::Diverged { .. })));
+}
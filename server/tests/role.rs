@@ -398,8 +398,8 @@ fn rabble_rouser_dies(){
 
 #[test]
 fn psychic_auras(){
-    for _ in 0..20 {
-        kit::scenario!(game in Night 1 where
+    for seed in 0..20 {
+        kit::scenario!(game in Night 1 seeded seed where
             psy: Psychic,
             god: Godfather,
             maf: Framer,
@@ -444,6 +444,27 @@ fn psychic_auras(){
     }
 }
 
+#[test]
+fn same_seed_same_results(){
+    for seed in 0..20 {
+        let results: Vec<_> = (0..2).map(|_|{
+            kit::scenario!(game in Night 1 seeded seed where
+                psy: Psychic,
+                _god: Godfather,
+                maf: Framer,
+                _town1: Detective,
+                _town2: Vigilante
+            );
+
+            psy.send_ability_input_player_list_typical(maf);
+            game.next_phase();
+            psy.get_messages_after_night(1)
+        }).collect();
+
+        assert_eq!(results.first(), results.get(1));
+    }
+}
+
 #[test]
 fn tally_clerk_basic(){
     kit::scenario!(game in Nomination 2 where
@@ -744,38 +765,41 @@ fn witch_basic(){
 
 #[test]
 fn cop_basic(){
-    kit::scenario!(game in Night 2 where
-        crus: Cop,
-        protected_player: Jester,
-        townie1: Detective,
-        townie2: Detective,
-        mafioso: Mafioso,
-        _maf2: Framer
-    );
+    for seed in 0..20 {
+        kit::scenario!(game in Night 2 seeded seed where
+            crus: Cop,
+            protected_player: Jester,
+            townie1: Detective,
+            townie2: Detective,
+            mafioso: Mafioso,
+            _maf2: Framer
+        );
 
-    crus.send_ability_input_player_list_typical(protected_player);
-    townie1.send_ability_input_player_list_typical(protected_player);
-    townie2.send_ability_input_player_list_typical(protected_player);
-    mafioso.send_ability_input_player_list_typical(protected_player);
+        crus.send_ability_input_player_list_typical(protected_player);
+        townie1.send_ability_input_player_list_typical(protected_player);
+        townie2.send_ability_input_player_list_typical(protected_player);
+        mafioso.send_ability_input_player_list_typical(protected_player);
 
-    game.skip_to(Night, 3);
+        game.skip_to(Night, 3);
 
-    assert!(crus.alive());
-    assert!(protected_player.alive());
-    assert!(townie1.alive());
-    assert!(townie2.alive());
-    assert!(!mafioso.alive());
+        assert!(crus.alive());
+        assert!(protected_player.alive());
+        assert!(townie1.alive());
+        assert!(townie2.alive());
+        assert!(!mafioso.alive());
 
-    crus.send_ability_input_player_list_typical(protected_player);
-    townie1.send_ability_input_player_list_typical(protected_player);
-    townie2.send_ability_input_player_list_typical(protected_player);
+        crus.send_ability_input_player_list_typical(protected_player);
+        townie1.send_ability_input_player_list_typical(protected_player);
+        townie2.send_ability_input_player_list_typical(protected_player);
 
-    game.next_phase();
-    
-    assert!(crus.alive());
-    assert!(protected_player.alive());
-    assert!(townie1.alive() || townie2.alive());
-    assert!(!townie1.alive() || !townie2.alive());
+        game.next_phase();
+        
+        assert!(crus.alive());
+        assert!(protected_player.alive());
+        // Which of them is attacked is random, but it's always exactly one
+        assert!(townie1.alive() || townie2.alive());
+        assert!(!townie1.alive() || !townie2.alive());
+    }
 }
 
 #[test]
@@ -803,45 +827,47 @@ fn cop_does_not_kill_framed_player(){
 
 #[test]
 fn ambusher_basic(){
-    kit::scenario!(game in Night 2 where
-        ambusher: Ambusher,
-        protected_player: Jester,
-        townie1: Detective,
-        townie2: Detective,
-        blackmailer: Blackmailer
-    );
+    for seed in 0..20 {
+        kit::scenario!(game in Night 2 seeded seed where
+            ambusher: Ambusher,
+            protected_player: Jester,
+            townie1: Detective,
+            townie2: Detective,
+            blackmailer: Blackmailer
+        );
 
-    
-    ambusher.send_ability_input_player_list_typical(protected_player);
-    townie1.send_ability_input_player_list_typical(protected_player);
-    townie2.send_ability_input_player_list_typical(protected_player);
-    blackmailer.send_ability_input_player_list_typical(protected_player);
+        
+        ambusher.send_ability_input_player_list_typical(protected_player);
+        townie1.send_ability_input_player_list_typical(protected_player);
+        townie2.send_ability_input_player_list_typical(protected_player);
+        blackmailer.send_ability_input_player_list_typical(protected_player);
 
-    game.skip_to(Night, 3);
+        game.skip_to(Night, 3);
 
-    assert!(ambusher.alive());
-    assert!(protected_player.alive());
-    assert!(townie1.alive() || townie2.alive());
-    assert!(!townie1.alive() || !townie2.alive());
-    assert!(blackmailer.alive());
+        assert!(ambusher.alive());
+        assert!(protected_player.alive());
+        // Which of them is attacked is random, but it's always exactly one
+        assert!(townie1.alive() || townie2.alive());
+        assert!(!townie1.alive() || !townie2.alive());
+        assert!(blackmailer.alive());
 
-    let townie1_status = townie1.alive();
-    let townie2_status = townie2.alive();
+        let townie1_status = townie1.alive();
+        let townie2_status = townie2.alive();
 
-    ambusher.send_ability_input_player_list_typical(protected_player);
-    blackmailer.send_ability_input_player_list_typical(protected_player);
+        ambusher.send_ability_input_player_list_typical(protected_player);
+        blackmailer.send_ability_input_player_list_typical(protected_player);
 
-    game.next_phase();
-    
-    assert!(ambusher.alive());
-    assert!(protected_player.alive());
-    assert!(townie1.alive() || townie2.alive());
-    assert!(!townie1.alive() || !townie2.alive());
-    // assert!(!blackmailer.alive());
-    assert!(blackmailer.get_messages().contains(&ChatMessageVariant::YouSurvivedAttack)||!blackmailer.alive());
-    assert!(townie1.alive() == townie1_status);
-    assert!(townie2.alive() == townie2_status);
-    
+        game.next_phase();
+        
+        assert!(ambusher.alive());
+        assert!(protected_player.alive());
+        assert!(townie1.alive() || townie2.alive());
+        assert!(!townie1.alive() || !townie2.alive());
+        // assert!(!blackmailer.alive());
+        assert!(blackmailer.get_messages().contains(&ChatMessageVariant::YouSurvivedAttack)||!blackmailer.alive());
+        assert!(townie1.alive() == townie1_status);
+        assert!(townie2.alive() == townie2_status);
+    }
 }
 
 #[test]
@@ -1142,8 +1168,8 @@ fn double_transport_single_player() {
 
 #[test]
 fn double_transport_three_players() {
-    for _ in 0..50 {
-        kit::scenario!(game in Night 2 where
+    for seed in 0..50 {
+        kit::scenario!(game in Night 2 seeded seed where
             mafioso: Mafioso,
      
             townie_a: Detective,
@@ -2321,8 +2347,8 @@ fn ojo_transporter(){
 
 #[test]
 fn cult_alternates() {
-    for _ in 0..20 {
-        kit::scenario!(game in Night 1 where
+    for seed in 0..20 {
+        kit::scenario!(game in Night 1 seeded seed where
             apostle: Apostle,
             b: Detective,
             c: Detective,
@@ -2374,8 +2400,8 @@ fn cult_alternates() {
 
 #[test]//writing "cult" here so if you ctrl+f for cult related tests you find this
 fn apostle_converting_trapped_player_day_later() {
-    for _ in 0..20 {
-        kit::scenario!(game in Night 2 where
+    for seed in 0..20 {
+        kit::scenario!(game in Night 2 seeded seed where
             apostle: Apostle,
             zealot: Zealot,
             trapped: Detective,
@@ -2403,8 +2429,8 @@ fn apostle_converting_trapped_player_day_later() {
 
 #[test]//writing "cult" here so if you ctrl+f for cult related tests you find this
 fn apostle_converting_trapped_player_same_day(){
-    for _ in 0..20 {
-        kit::scenario!(game in Night 2 where
+    for seed in 0..20 {
+        kit::scenario!(game in Night 2 seeded seed where
             apostle: Apostle,
             _zealot: Zealot,
             trapped: Detective,
@@ -2960,8 +2986,8 @@ fn only_santa_and_krampus_ends_instantly() {
 
 #[test]
 fn santa_always_gets_their_naughty_selection() {
-    for _ in 0..20 {
-        kit::scenario!(game in Night 1 where
+    for seed in 0..20 {
+        kit::scenario!(game in Night 1 seeded seed where
             santa: SantaClaus,
             nice: Villager,
             naughty: Villager,
@@ -0,0 +1,256 @@
+use std::collections::VecDeque;
+
+use mafia_server::{
+    client_connection::ClientConnection,
+    game::{
+        game_client::{GameClient, GameClientLocation}, game_conclusion::GameConclusion, player::{PlayerInitializeParameters, PlayerReference},
+        role::Role, role_generation::{self, Generator, RoleGenerationError}, role_list::{RoleList, RoleOutline, RoleOutlineOption, RoleOutlineOptionRoles, RoleSet},
+        settings::Settings, Game, RejectStartReason
+    },
+    room::RoomClientID, vec_map::VecMap, vec_set::VecSet, websocket_connections::connection::ClientSender
+};
+use rand::{rngs::StdRng, SeedableRng};
+use tokio::sync::mpsc;
+use vec1::Vec1;
+
+fn outline(roles: &[Role]) -> RoleOutline {
+    let options = roles.iter()
+        .map(|role| RoleOutlineOption { roles: RoleOutlineOptionRoles::Role { role: *role }, ..Default::default() })
+        .collect();
+    RoleOutline { options: Vec1::try_from_vec(options).expect("outline has options") }
+}
+
+fn role_set(role_set: RoleSet) -> RoleOutline {
+    RoleOutline { options: vec1::vec1![RoleOutlineOption { roles: RoleOutlineOptionRoles::RoleSet { role_set }, ..Default::default() }] }
+}
+
+/// Starts a game with a player for every outline
+fn start(role_list: RoleList, enabled_roles: VecSet<Role>) -> Result<Game, RejectStartReason> {
+    // Nobody listens to what the game sends
+    let sender = ClientSender::new(mpsc::unbounded_channel().0);
+    let players = u8::try_from(role_list.0.len()).expect("role list is small");
+
+    let mut clients = VecMap::new();
+    let mut player_parameters = Vec::new();
+    for index in 0..players {
+        clients.insert(RoomClientID::from(index), GameClient {
+            client_location: GameClientLocation::Player(unsafe { PlayerReference::new_unchecked(index) }),
+            host: index == 0,
+            last_message_times: VecDeque::new(),
+        });
+        player_parameters.push(PlayerInitializeParameters {
+            connection: ClientConnection::Connected(sender.clone()),
+            name: index.to_string(),
+            host: index == 0,
+        });
+    }
+
+    let settings = Settings { role_list, enabled_roles, ..Default::default() };
+    Game::new("Test".to_string(), settings, clients, player_parameters, Vec::new(), 0)
+}
+
+fn generate(role_list: &RoleList, enabled_roles: &VecSet<Role>, rng: &mut StdRng) -> Result<Vec<Role>, RoleGenerationError> {
+    role_generation::generate(role_list, enabled_roles, &[], rng)
+        .map(|assignments| assignments.iter().map(|assignment| assignment.role()).collect())
+}
+
+#[test]
+fn tight_lists_always_generate() {
+    // Every mafia role in these sets can only be picked once, so there's exactly one of each.
+    // Picking mafia support roles at random almost never gives each once, so those are filled one at a time
+    for set in [RoleSet::MafiaKilling, RoleSet::MafiaSupport] {
+        let role_list = RoleList(vec![role_set(set.clone()); set.get_roles().count()]);
+        let mut rng = StdRng::seed_from_u64(0);
+
+        for _ in 0..20 {
+            let roles: VecSet<Role> = generate(&role_list, &Role::values(), &mut rng).expect("list can be generated").into_iter().collect();
+            assert_eq!(roles, set.get_roles());
+        }
+    }
+}
+
+#[test]
+fn impossible_lists_name_the_outlines_that_cant_be_filled() {
+    let mafia_killing_roles = RoleSet::MafiaKilling.get_roles().count();
+    let mut outlines = vec![role_set(RoleSet::Town)];
+    outlines.extend(vec![role_set(RoleSet::MafiaKilling); mafia_killing_roles.saturating_add(1)]);
+
+    let error = generate(&RoleList(outlines), &Role::values(), &mut StdRng::seed_from_u64(0)).expect_err("list can't be generated");
+
+    assert_eq!(error, RoleGenerationError::MaximumCountExhausted {
+        outlines: (1..=mafia_killing_roles.saturating_add(1)).collect(),
+        roles: RoleSet::MafiaKilling.get_roles().into_iter().collect(),
+    });
+}
+
+#[test]
+fn outlines_without_enabled_roles_are_named() {
+    let role_list = RoleList(vec![outline(&[Role::Villager]), outline(&[Role::Jester])]);
+    let enabled_roles: VecSet<Role> = [Role::Villager].into_iter().collect();
+
+    let error = generate(&role_list, &enabled_roles, &mut StdRng::seed_from_u64(0)).expect_err("list can't be generated");
+
+    assert_eq!(error, RoleGenerationError::NoEnabledRoles { outline: 1 });
+
+    let role_list = RoleList(vec![outline(&[Role::Villager]), outline(&[Role::Jester, Role::Doctor])]);
+    let error = generate(&role_list, &enabled_roles, &mut StdRng::seed_from_u64(0)).expect_err("list can't be generated");
+
+    assert_eq!(error, RoleGenerationError::AllOptionsDisabled { outline: 1 });
+}
+
+#[test]
+fn rejected_starts_say_which_outline_is_wrong() {
+    let role_list = RoleList(vec![outline(&[Role::Villager]), outline(&[Role::Jester]), outline(&[Role::Godfather])]);
+    let enabled_roles = [Role::Villager, Role::Godfather].into_iter().collect();
+
+    let reason = start(role_list, enabled_roles).err().expect("game can't start");
+
+    assert_eq!(reason, RejectStartReason::RoleListCannotCreateRoles(RoleGenerationError::NoEnabledRoles { outline: 1 }));
+    assert_eq!(
+        serde_json::to_value(&reason).expect("reason serializes"),
+        serde_json::json!({ "type": "roleListCannotCreateRoles", "problem": "noEnabledRoles", "outline": 1 })
+    );
+}
+
+#[test]
+fn rejected_starts_say_how_the_game_would_end() {
+    let role_list = RoleList(vec![outline(&[Role::Villager]); 3]);
+
+    let reason = start(role_list, Role::values()).err().expect("game can't start");
+
+    assert_eq!(reason, RejectStartReason::GameEndsInstantly { conclusion: GameConclusion::Town });
+    assert_eq!(
+        serde_json::to_value(&reason).expect("reason serializes"),
+        serde_json::json!({ "type": "gameEndsInstantly", "conclusion": "town" })
+    );
+}
+
+#[test]
+fn lists_that_only_sometimes_end_instantly_start() {
+    // Half of these lists are all villagers, which the game tries again instead of starting
+    let role_list = RoleList(vec![outline(&[Role::Villager]), outline(&[Role::Villager]), outline(&[Role::Villager, Role::Godfather])]);
+
+    let game = start(role_list, Role::values()).expect("game starts");
+
+    assert!(PlayerReference::all_players(&game).any(|player| player.role(&game) == Role::Godfather));
+}
+
+#[test]
+fn valid_assignments_are_equally_likely() {
+    // Godfather and Mafioso can only be picked once each,
+    // so the valid assignments are (Godfather, Impostor), (Mafioso, Godfather) and (Mafioso, Impostor)
+    let role_list = RoleList(vec![outline(&[Role::Godfather, Role::Mafioso]), outline(&[Role::Godfather, Role::Impostor])]);
+    let mut rng = StdRng::seed_from_u64(0);
+
+    const SAMPLES: u32 = 3000;
+    let mut first_is_godfather = 0u32;
+    for _ in 0..SAMPLES {
+        let roles = generate(&role_list, &Role::values(), &mut rng).expect("list can be generated");
+        assert_ne!(roles.first(), roles.get(1));
+        if roles.first() == Some(&Role::Godfather) {
+            first_is_godfather = first_is_godfather.saturating_add(1);
+        }
+    }
+
+    // A third of the time, give or take
+    assert!((900..1100).contains(&first_is_godfather), "{first_is_godfather} out of {SAMPLES}");
+}
+
+#[test]
+fn valid_assignments_of_tight_lists_are_equally_likely() {
+    // The mafia support outlines make drawing fail, so these come from the Markov chain.
+    // Filling the two outlines one at a time would make the first one Godfather 3/8 of the time
+    let mut outlines = vec![outline(&[Role::Godfather, Role::Mafioso]), outline(&[Role::Godfather, Role::Impostor])];
+    outlines.extend(vec![role_set(RoleSet::MafiaSupport); RoleSet::MafiaSupport.get_roles().count()]);
+    let mut generator = Generator::new(&RoleList(outlines), &Role::values(), &[]).expect("list can be generated");
+    let mut rng = StdRng::seed_from_u64(0);
+
+    const SAMPLES: u32 = 4000;
+    let mut first_is_godfather = 0u32;
+    for _ in 0..SAMPLES {
+        let roles: Vec<Role> = generator.generate(&mut rng).iter().map(|assignment| assignment.role()).collect();
+        assert_eq!(roles.iter().collect::<VecSet<_>>().count(), roles.len());
+        if roles.first() == Some(&Role::Godfather) {
+            first_is_godfather = first_is_godfather.saturating_add(1);
+        }
+    }
+
+    // A third of the time, give or take
+    assert!((1230..1440).contains(&first_is_godfather), "{first_is_godfather} out of {SAMPLES}");
+}
+
+#[test]
+fn options_are_picked_by_weight() {
+    let outline: RoleOutline = serde_json::from_value(serde_json::json!([
+        { "roleSet": "townKilling", "weight": 70 },
+        { "roleSet": "neutral", "weight": 30 },
+        { "role": "jester", "weight": 0 }
+    ])).expect("outline parses");
+    let role_list = RoleList(vec![outline]);
+    let mut rng = StdRng::seed_from_u64(0);
+
+    const SAMPLES: u32 = 2000;
+    let mut town_killing = 0u32;
+    for _ in 0..SAMPLES {
+        let roles = generate(&role_list, &Role::values(), &mut rng).expect("list can be generated");
+        let role = roles.first().expect("outline was filled");
+        // Jester is neutral, so it can still be picked through that option
+        if RoleSet::TownKilling.get_roles().contains(role) {
+            town_killing = town_killing.saturating_add(1);
+        }
+    }
+
+    // 70% of the time, give or take
+    assert!((1300..1500).contains(&town_killing), "{town_killing} out of {SAMPLES}");
+}
+
+#[test]
+fn weights_only_count_enabled_roles() {
+    let outline: RoleOutline = serde_json::from_value(serde_json::json!([
+        { "roleSet": "townKilling", "weight": 1 },
+        { "role": "jester", "weight": 1 }
+    ])).expect("outline parses");
+    let enabled_roles: VecSet<Role> = [Role::Vigilante, Role::Jester].into_iter().collect();
+
+    let weights = outline.get_weighted_role_assignments(&enabled_roles);
+
+    assert_eq!(weights.iter().map(|(assignment, weight)| (assignment.role(), *weight)).collect::<Vec<_>>(), vec![(Role::Vigilante, 1.0), (Role::Jester, 1.0)]);
+}
+
+#[test]
+fn outlines_with_only_zero_weights_cant_be_generated() {
+    let outline: RoleOutline = serde_json::from_value(serde_json::json!([
+        { "role": "villager", "weight": 0 },
+        { "role": "jester", "weight": 0 }
+    ])).expect("outline parses");
+
+    let error = generate(&RoleList(vec![outline]), &Role::values(), &mut StdRng::seed_from_u64(0)).expect_err("list can't be generated");
+
+    assert_eq!(error, RoleGenerationError::ZeroWeights { outline: 0 });
+}
+
+#[test]
+fn a_lone_option_with_zero_weight_says_so() {
+    let outline: RoleOutline = serde_json::from_value(serde_json::json!([{ "role": "villager", "weight": 0 }])).expect("outline parses");
+
+    let error = generate(&RoleList(vec![outline]), &Role::values(), &mut StdRng::seed_from_u64(0)).expect_err("list can't be generated");
+
+    assert_eq!(error, RoleGenerationError::ZeroWeights { outline: 0 });
+}
+
+#[test]
+fn invalid_weights_are_rejected() {
+    for weight in [serde_json::json!(-1), serde_json::json!(70_000), serde_json::json!(0.5), serde_json::json!("heavy")] {
+        let parsed = serde_json::from_value::<RoleOutline>(serde_json::json!([{ "role": "jester", "weight": weight }]));
+        assert!(parsed.is_err(), "weight {weight} should be rejected");
+    }
+}
+
+#[test]
+fn weights_are_only_written_when_set() {
+    let json = serde_json::json!([{ "roleSet": "townKilling", "weight": 70 }, { "role": "jester" }]);
+    let outline: RoleOutline = serde_json::from_value(json.clone()).expect("outline parses");
+
+    assert_eq!(outline.options.first().weight, Some(70));
+    assert_eq!(serde_json::to_value(&outline).expect("outline serializes"), json);
+}
@@ -0,0 +1,115 @@
+use std::{net::SocketAddr, time::Duration};
+
+use mafia_server::{
+    game::{
+        role::Role, role_generation::RoleGenerationError, role_list::{RoleList, RoleOutline, RoleOutlineOption, RoleOutlineOptionRoles, RoleSet},
+        role_list_analysis::{self, RoleListAnalysis}
+    },
+    packet::{ToClientPacket, ToServerPacket},
+    room::{access::RoomAccess, task::{RoomEvent, RoomHandle}, Room},
+    websocket_connections::connection::{ClientSender, SequencedPacket},
+    websocket_listener::RoomCode
+};
+use rand::{rngs::StdRng, SeedableRng};
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+use vec1::Vec1;
+
+fn outline(roles: &[Role]) -> RoleOutline {
+    let options = roles.iter()
+        .map(|role| RoleOutlineOption { roles: RoleOutlineOptionRoles::Role { role: *role }, ..Default::default() })
+        .collect();
+    RoleOutline { options: Vec1::try_from_vec(options).expect("outline has options") }
+}
+
+fn analyze(role_list: Vec<RoleOutline>) -> RoleListAnalysis {
+    role_list_analysis::analyze(&RoleList(role_list), &Role::values(), &[], &mut StdRng::seed_from_u64(0))
+}
+
+#[test]
+fn fixed_lists_are_certain() {
+    let analysis = analyze(vec![outline(&[Role::Villager]), outline(&[Role::Villager]), outline(&[Role::Godfather])]);
+
+    assert_eq!(analysis.problem, None);
+    assert_eq!(analysis.role_set_counts.get(&RoleSet::Town), Some(&vec![0.0, 0.0, 1.0]));
+    assert_eq!(analysis.role_set_counts.get(&RoleSet::Mafia), Some(&vec![0.0, 1.0]));
+    assert_eq!(analysis.role_set_counts.get(&RoleSet::Fiends), Some(&vec![1.0]));
+    assert_eq!(analysis.roles.get(&Role::Villager), Some(&1.0));
+    assert_eq!(analysis.roles.get(&Role::Godfather), Some(&1.0));
+    assert_eq!(analysis.roles.get(&Role::Jester), None);
+    assert_eq!(analysis.ends_instantly, 0.0);
+}
+
+#[test]
+fn chances_follow_the_outlines() {
+    let analysis = analyze(vec![outline(&[Role::Villager]), outline(&[Role::Godfather]), outline(&[Role::Jester, Role::Doctor])]);
+
+    let jester = analysis.roles.get(&Role::Jester).copied().unwrap_or_default();
+    assert!((0.45..0.55).contains(&jester), "{jester}");
+
+    let neutral = analysis.role_set_counts.get(&RoleSet::Neutral).expect("neutral is counted");
+    assert_eq!(neutral.len(), 2);
+    assert!(neutral.iter().sum::<f64>() > 0.999);
+}
+
+#[test]
+fn lists_of_one_faction_end_instantly() {
+    let analysis = analyze(vec![outline(&[Role::Villager]); 3]);
+
+    assert_eq!(analysis.ends_instantly, 1.0);
+}
+
+#[test]
+fn lists_that_cant_be_generated_say_why() {
+    let godfathers = vec![outline(&[Role::Godfather]); 2];
+
+    let analysis = analyze(godfathers);
+
+    assert!(matches!(analysis.problem, Some(RoleGenerationError::MaximumCountExhausted { .. })));
+    assert!(analysis.roles.is_empty());
+}
+
+#[test]
+fn analysis_is_requested_by_packet() {
+    let packet: ToServerPacket = serde_json::from_str(r#"{"type":"analyzeRoleList"}"#).expect("packet parses");
+    assert!(matches!(packet, ToServerPacket::AnalyzeRoleList));
+}
+
+const HOST: SocketAddr = SocketAddr::new(std::net::IpAddr::V4(std::net::Ipv4Addr::LOCALHOST), 1);
+
+async fn analysis_from(room: &RoomHandle, receiver: &mut UnboundedReceiver<SequencedPacket>) -> RoleListAnalysis {
+    assert!(room.send(RoomEvent::Packet { address: HOST, packet: ToServerPacket::AnalyzeRoleList }));
+    loop {
+        let packet = tokio::time::timeout(Duration::from_secs(5), receiver.recv()).await
+            .expect("lobby should answer")
+            .expect("room should still be open").packet;
+        if let ToClientPacket::RoleListAnalysis { analysis } = packet {
+            return analysis;
+        }
+    }
+}
+
+#[tokio::test]
+async fn lobbies_answer_with_an_analysis_of_their_settings() {
+    let (updates, _update_receiver) = mpsc::unbounded_channel();
+    let room = RoomHandle::spawn(RoomCode::new(1), Room::new(), RoomAccess::default(), updates, None);
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    room.send(RoomEvent::Join { address: HOST, sender: ClientSender::new(sender), password: None, invite_code: None });
+    room.send(RoomEvent::Packet { address: HOST, packet: ToServerPacket::SetEnabledRoles { roles: Role::values().into_iter().collect() } });
+
+    room.send(RoomEvent::Packet { address: HOST, packet: ToServerPacket::SetRoleList {
+        role_list: RoleList(vec![outline(&[Role::Jester, Role::Doctor, Role::Detective])])
+    } });
+    let analysis = analysis_from(&room, &mut receiver).await;
+    assert_eq!(analysis.problem, None);
+    assert!(analysis.roles.contains_key(&Role::Jester));
+
+    // Asking again before the settings change gives back the same analysis, rather than sampling again
+    assert_eq!(analysis_from(&room, &mut receiver).await, analysis);
+
+    room.send(RoomEvent::Packet { address: HOST, packet: ToServerPacket::SetRoleList {
+        role_list: RoleList(vec![outline(&[Role::Villager])])
+    } });
+    let analysis = analysis_from(&room, &mut receiver).await;
+    assert_eq!(analysis.roles.get(&Role::Villager), Some(&1.0));
+    assert!(!analysis.roles.contains_key(&Role::Jester));
+}
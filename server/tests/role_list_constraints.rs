@@ -0,0 +1,193 @@
+use mafia_server::{
+    game::{
+        role::Role, role_generation::{self, RoleGenerationError},
+        role_list::{RoleList, RoleOutline, RoleOutlineOption, RoleOutlineOptionRoles, RoleSet},
+        role_list_analysis, role_list_constraints::{ConstraintCount, RoleListConstraint}, settings::Settings
+    },
+    packet::ToServerPacket
+};
+use rand::{rngs::StdRng, SeedableRng};
+use vec1::Vec1;
+
+fn outline(roles: &[Role]) -> RoleOutline {
+    let options = roles.iter()
+        .map(|role| RoleOutlineOption { roles: RoleOutlineOptionRoles::Role { role: *role }, ..Default::default() })
+        .collect();
+    RoleOutline { options: Vec1::try_from_vec(options).expect("outline has options") }
+}
+
+fn role_set(role_set: RoleSet) -> RoleOutline {
+    RoleOutline { options: vec1::vec1![RoleOutlineOption { roles: RoleOutlineOptionRoles::RoleSet { role_set }, ..Default::default() }] }
+}
+
+fn count(role_set: RoleSet, min: Option<ConstraintCount>, max: Option<ConstraintCount>) -> RoleListConstraint {
+    RoleListConstraint::Count { roles: RoleOutlineOptionRoles::RoleSet { role_set }, min, max }
+}
+
+fn exclusive(first: Role, second: Role) -> RoleListConstraint {
+    RoleListConstraint::Exclusive { first: RoleOutlineOptionRoles::Role { role: first }, second: RoleOutlineOptionRoles::Role { role: second } }
+}
+
+fn generate(role_list: &RoleList, constraints: &[RoleListConstraint], rng: &mut StdRng) -> Result<Vec<Role>, RoleGenerationError> {
+    role_generation::generate(role_list, &Role::values(), constraints, rng)
+        .map(|assignments| assignments.iter().map(|assignment| assignment.role()).collect())
+}
+
+fn count_in(roles: &[Role], role_set: RoleSet) -> usize {
+    let set = role_set.get_roles();
+    roles.iter().filter(|role| set.contains(role)).count()
+}
+
+#[test]
+fn counts_are_met() {
+    let role_list = RoleList(vec![role_set(RoleSet::Any); 10]);
+    let constraints = [
+        count(RoleSet::TownKilling, None, Some(ConstraintCount::Fixed(2))),
+        count(RoleSet::Fiends, Some(ConstraintCount::Fixed(1)), Some(ConstraintCount::Fixed(1))),
+    ];
+    let mut rng = StdRng::seed_from_u64(0);
+
+    for _ in 0..50 {
+        let roles = generate(&role_list, &constraints, &mut rng).expect("list can be generated");
+        assert!(count_in(&roles, RoleSet::TownKilling) <= 2);
+        assert_eq!(count_in(&roles, RoleSet::Fiends), 1);
+    }
+}
+
+#[test]
+fn counts_can_scale_with_players() {
+    // One mafia for every 4 players, so exactly 3 among 12
+    let role_list = RoleList(vec![role_set(RoleSet::Any); 12]);
+    let constraints = [count(RoleSet::Mafia, Some(ConstraintCount::PerPlayers(4)), Some(ConstraintCount::PerPlayers(4)))];
+    let mut rng = StdRng::seed_from_u64(0);
+
+    for _ in 0..50 {
+        let roles = generate(&role_list, &constraints, &mut rng).expect("list can be generated");
+        assert_eq!(count_in(&roles, RoleSet::Mafia), 3);
+    }
+}
+
+#[test]
+fn exclusive_roles_never_appear_together() {
+    let role_list = RoleList(vec![outline(&[Role::Jester, Role::Villager]), outline(&[Role::Politician, Role::Villager])]);
+    let constraints = [exclusive(Role::Jester, Role::Politician)];
+    let mut rng = StdRng::seed_from_u64(0);
+
+    for _ in 0..200 {
+        let roles = generate(&role_list, &constraints, &mut rng).expect("list can be generated");
+        assert!(!(roles.contains(&Role::Jester) && roles.contains(&Role::Politician)), "{roles:?}");
+    }
+}
+
+#[test]
+fn impossible_constraints_are_named() {
+    let role_list = RoleList(vec![outline(&[Role::Villager]), outline(&[Role::Jester]), role_set(RoleSet::Town)]);
+    let constraints = [
+        count(RoleSet::Town, Some(ConstraintCount::Fixed(1)), None),
+        // Only the Jester outline could be neutral
+        count(RoleSet::Neutral, Some(ConstraintCount::Fixed(3)), None),
+        exclusive(Role::Villager, Role::Jester),
+    ];
+
+    let error = generate(&role_list, &constraints, &mut StdRng::seed_from_u64(0)).expect_err("list can't be generated");
+
+    assert_eq!(error, RoleGenerationError::ConstraintsCantBeMet { constraints: vec![1, 2] });
+}
+
+#[test]
+fn constraints_that_only_conflict_together_are_all_named() {
+    let role_list = RoleList(vec![role_set(RoleSet::Any); 3]);
+    let constraints = [
+        count(RoleSet::Town, Some(ConstraintCount::Fixed(2)), None),
+        count(RoleSet::Mafia, Some(ConstraintCount::Fixed(2)), None),
+    ];
+
+    let error = generate(&role_list, &constraints, &mut StdRng::seed_from_u64(0)).expect_err("list can't be generated");
+
+    assert_eq!(error, RoleGenerationError::ConstraintsCantBeMet { constraints: vec![0, 1] });
+}
+
+#[test]
+fn analysis_follows_constraints() {
+    let role_list = RoleList(vec![role_set(RoleSet::Any); 6]);
+    let constraints = [count(RoleSet::Fiends, Some(ConstraintCount::Fixed(1)), Some(ConstraintCount::Fixed(1)))];
+
+    let analysis = role_list_analysis::analyze(&role_list, &Role::values(), &constraints, &mut StdRng::seed_from_u64(0));
+
+    assert_eq!(analysis.role_set_counts.get(&RoleSet::Fiends), Some(&vec![0.0, 1.0]));
+}
+
+#[test]
+fn constraints_serialize_compactly() {
+    let json = serde_json::json!([
+        { "type": "count", "roles": { "roleSet": "fiends" }, "min": { "fixed": 1 } },
+        { "type": "count", "roles": { "roleSet": "mafia" }, "max": { "perPlayers": 4 } },
+        { "type": "exclusive", "first": { "role": "jester" }, "second": { "role": "politician" } }
+    ]);
+
+    let constraints: Vec<RoleListConstraint> = serde_json::from_value(json.clone()).expect("constraints parse");
+
+    assert_eq!(constraints.first(), Some(&count(RoleSet::Fiends, Some(ConstraintCount::Fixed(1)), None)));
+    assert_eq!(serde_json::to_value(&constraints).expect("constraints serialize"), json);
+}
+
+#[test]
+fn settings_without_constraints_still_parse() {
+    let mut json = serde_json::to_value(Settings::default()).expect("settings serialize");
+    json.as_object_mut().expect("settings are an object").remove("roleListConstraints");
+
+    let settings: Settings = serde_json::from_value(json).expect("settings parse");
+
+    assert!(settings.role_list_constraints.is_empty());
+}
+
+#[test]
+fn constraints_are_set_by_packet() {
+    let packet: ToServerPacket = serde_json::from_value(serde_json::json!({
+        "type": "setRoleListConstraints",
+        "constraints": [{ "type": "exclusive", "first": { "role": "jester" }, "second": { "roleSet": "mafia" } }]
+    })).expect("packet parses");
+
+    assert!(matches!(packet, ToServerPacket::SetRoleListConstraints { constraints } if constraints.len() == 1));
+}
+
+#[test]
+fn overlapping_minimums_over_any_outlines_generate() {
+    let role_list = RoleList(vec![role_set(RoleSet::Any); 15]);
+    let exactly = |role_set: RoleSet, number: u8| count(role_set, Some(ConstraintCount::Fixed(number)), Some(ConstraintCount::Fixed(number)));
+    let at_least = |role_set: RoleSet, number: u8| count(role_set, Some(ConstraintCount::Fixed(number)), None);
+
+    for constraints in [
+        vec![exactly(RoleSet::Town, 9), exactly(RoleSet::Mafia, 3), exactly(RoleSet::Neutral, 3)],
+        vec![exactly(RoleSet::Town, 8), exactly(RoleSet::Mafia, 7)],
+        vec![at_least(RoleSet::Town, 8), at_least(RoleSet::Mafia, 4)],
+        // Town killing roles count towards town too
+        vec![at_least(RoleSet::Town, 8), at_least(RoleSet::TownKilling, 2), at_least(RoleSet::Mafia, 4), at_least(RoleSet::Neutral, 3)],
+    ] {
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..20 {
+            let roles = generate(&role_list, &constraints, &mut rng).expect("list can be generated");
+            for constraint in &constraints {
+                let RoleListConstraint::Count { roles: RoleOutlineOptionRoles::RoleSet { role_set }, min, max } = constraint else {continue};
+                let number = count_in(&roles, role_set.clone());
+                assert!(min.is_none_or(|min| number >= min.resolve(15)), "{constraint:?} {roles:?}");
+                assert!(max.is_none_or(|max| number <= max.resolve(15)), "{constraint:?} {roles:?}");
+            }
+        }
+    }
+}
+
+#[test]
+fn minimums_that_need_more_outlines_than_there_are_cant_be_met() {
+    let role_list = RoleList(vec![role_set(RoleSet::Any); 15]);
+    let constraints = [
+        count(RoleSet::Town, Some(ConstraintCount::Fixed(8)), None),
+        count(RoleSet::TownKilling, Some(ConstraintCount::Fixed(2)), None),
+        count(RoleSet::Mafia, Some(ConstraintCount::Fixed(4)), None),
+        count(RoleSet::Neutral, Some(ConstraintCount::Fixed(4)), None),
+    ];
+
+    let error = generate(&role_list, &constraints, &mut StdRng::seed_from_u64(0)).expect_err("list can't be generated");
+
+    assert_eq!(error, RoleGenerationError::ConstraintsCantBeMet { constraints: vec![0, 1, 2, 3] });
+}
@@ -0,0 +1,33 @@
+use mafia_server::websocket_listener::{InvalidRoomCode, RoomCode};
+
+#[test]
+fn codes_are_read_back_whatever_their_case() {
+    for number in RoomCode::numbers_with_length(4).step_by(997) {
+        let code = RoomCode::new(number);
+        let written = code.to_string();
+
+        assert_eq!(written.len(), 4);
+        assert_eq!(written.parse(), Ok(code));
+        assert_eq!(written.to_lowercase().parse(), Ok(code));
+    }
+}
+
+#[test]
+fn codes_never_contain_confusable_characters() {
+    for number in RoomCode::numbers_with_length(3) {
+        let written = RoomCode::new(number).to_string();
+        assert!(!written.contains(['I', 'L', 'O', '0', '1']), "{written}");
+    }
+
+    assert_eq!("BOOK".parse::<RoomCode>(), Err(InvalidRoomCode));
+    assert_eq!("B1RD".parse::<RoomCode>(), Err(InvalidRoomCode));
+    assert_eq!("".parse::<RoomCode>(), Err(InvalidRoomCode));
+}
+
+#[test]
+fn numeric_codes_are_still_accepted() {
+    assert_eq!("42".parse(), Ok(RoomCode::new(42)));
+    assert_eq!(serde_json::from_str::<RoomCode>("42").ok(), Some(RoomCode::new(42)));
+    assert_eq!(serde_json::from_str::<RoomCode>("\"bw\"").ok(), Some(RoomCode::new(42)));
+    assert_eq!(serde_json::to_string(&RoomCode::new(42)).ok().as_deref(), Some("\"BW\""));
+}
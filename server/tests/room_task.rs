@@ -0,0 +1,372 @@
+use std::{net::SocketAddr, time::Duration};
+
+use mafia_server::{
+    game::{role::Role, role_list::{RoleList, RoleOutline}},
+    packet::{RejectJoinReason, ToClientPacket, ToServerPacket},
+    room::{access::{RoomAccess, Secret, MAX_INVITE_CODES}, reconnect_token::ReconnectToken, snapshot::SavedRoom, task::{RoomEvent, RoomHandle, RoomUpdate}, Room},
+    websocket_connections::connection::{ClientSender, SequencedPacket}, websocket_listener::RoomCode
+};
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+
+const ROOM_CODE: RoomCode = RoomCode::new(7);
+
+fn address(port: u16) -> SocketAddr {
+    SocketAddr::from(([127, 0, 0, 1], port))
+}
+
+fn client() -> (ClientSender, UnboundedReceiver<SequencedPacket>) {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    (ClientSender::new(sender), receiver)
+}
+
+async fn accept_join(receiver: &mut UnboundedReceiver<SequencedPacket>) -> ToClientPacket {
+    loop {
+        let packet = tokio::time::timeout(Duration::from_secs(5), receiver.recv()).await
+            .expect("room should answer")
+            .expect("room should still be open").packet;
+        if let ToClientPacket::AcceptJoin { .. } = packet {
+            return packet;
+        }
+    }
+}
+
+#[tokio::test]
+async fn room_answers_joins_and_closes_when_empty() {
+    let (updates, mut update_receiver) = mpsc::unbounded_channel();
+    let room = RoomHandle::spawn(ROOM_CODE, Room::new(), RoomAccess::default(), updates, None);
+
+    let (host, mut host_receiver) = client();
+    assert!(room.send(RoomEvent::Join { address: address(1), sender: host, password: None, invite_code: None }));
+    assert!(matches!(
+        accept_join(&mut host_receiver).await,
+        ToClientPacket::AcceptJoin { room_code: ROOM_CODE, player_id: 1, in_game: false, spectator: false, .. }
+    ));
+
+    let (player, mut player_receiver) = client();
+    assert!(room.send(RoomEvent::Join { address: address(2), sender: player, password: None, invite_code: None }));
+    assert!(matches!(accept_join(&mut player_receiver).await, ToClientPacket::AcceptJoin { player_id: 2, .. }));
+
+    // The host kicks the player
+    assert!(room.send(RoomEvent::Packet { address: address(1), packet: ToServerPacket::Kick { player_id: 2 } }));
+    let update = tokio::time::timeout(Duration::from_secs(5), update_receiver.recv()).await.expect("room should update");
+    assert!(matches!(update, Some(RoomUpdate::ClientRemoved { room_code: ROOM_CODE, address }) if address == self::address(2)));
+
+    assert!(room.send(RoomEvent::Leave { address: address(1) }));
+    let update = tokio::time::timeout(Duration::from_secs(5), update_receiver.recv()).await.expect("room should update");
+    assert!(matches!(update, Some(RoomUpdate::Closed { room_code: ROOM_CODE })));
+
+    // The room's task has stopped
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(!room.send(RoomEvent::Leave { address: address(1) }));
+}
+
+#[tokio::test]
+async fn room_can_be_snapshotted_while_running() {
+    let (updates, _update_receiver) = mpsc::unbounded_channel();
+    let room = RoomHandle::spawn(ROOM_CODE, Room::new(), RoomAccess::default(), updates, None);
+
+    let (host, _host_receiver) = client();
+    room.send(RoomEvent::Join { address: address(1), sender: host, password: None, invite_code: None });
+
+    assert!(room.snapshot().await.is_some());
+    assert_eq!(room.preview().players.len(), 1);
+}
+
+fn reconnect_token(packet: ToClientPacket) -> ReconnectToken {
+    match packet {
+        ToClientPacket::AcceptJoin { reconnect_token, .. } => reconnect_token,
+        _ => panic!("expected AcceptJoin"),
+    }
+}
+
+async fn reject_join(receiver: &mut UnboundedReceiver<SequencedPacket>) -> RejectJoinReason {
+    loop {
+        let packet = tokio::time::timeout(Duration::from_secs(5), receiver.recv()).await
+            .expect("room should answer")
+            .expect("room should still be open").packet;
+        match packet {
+            ToClientPacket::RejectJoin { reason } => return reason,
+            ToClientPacket::AcceptJoin { .. } => panic!("join should be rejected"),
+            _ => {}
+        }
+    }
+}
+
+#[tokio::test]
+async fn rejoining_needs_the_latest_reconnect_token() {
+    let (updates, _update_receiver) = mpsc::unbounded_channel();
+    let room = RoomHandle::spawn(ROOM_CODE, Room::new(), RoomAccess::default(), updates, None);
+
+    let (host, mut host_receiver) = client();
+    room.send(RoomEvent::Join { address: address(1), sender: host, password: None, invite_code: None });
+    accept_join(&mut host_receiver).await;
+
+    let (player, mut player_receiver) = client();
+    room.send(RoomEvent::Join { address: address(2), sender: player, password: None, invite_code: None });
+    let first_token = reconnect_token(accept_join(&mut player_receiver).await);
+    room.send(RoomEvent::LoseConnection { address: address(2) });
+
+    // Someone else can't take the player's place without the token
+    let (thief, mut thief_receiver) = client();
+    room.send(RoomEvent::Rejoin { address: address(3), sender: thief, room_client_id: 2, reconnect_token: ReconnectToken::generate(), last_sequence: None });
+    reject_join(&mut thief_receiver).await;
+
+    let (player, mut player_receiver) = client();
+    room.send(RoomEvent::Rejoin { address: address(4), sender: player, room_client_id: 2, reconnect_token: first_token.clone(), last_sequence: None });
+    let second_token = reconnect_token(accept_join(&mut player_receiver).await);
+    assert!(!second_token.matches(&first_token));
+    room.send(RoomEvent::LoseConnection { address: address(4) });
+
+    // The token is rotated, so the first one no longer works
+    let (player, mut player_receiver) = client();
+    room.send(RoomEvent::Rejoin { address: address(5), sender: player, room_client_id: 2, reconnect_token: first_token, last_sequence: None });
+    reject_join(&mut player_receiver).await;
+
+    let (player, mut player_receiver) = client();
+    room.send(RoomEvent::Rejoin { address: address(6), sender: player, room_client_id: 2, reconnect_token: second_token, last_sequence: None });
+    accept_join(&mut player_receiver).await;
+}
+
+#[tokio::test]
+async fn reconnect_tokens_are_snapshotted() {
+    let (updates, _update_receiver) = mpsc::unbounded_channel();
+    let room = RoomHandle::spawn(ROOM_CODE, Room::new(), RoomAccess::default(), updates, None);
+
+    let (host, mut host_receiver) = client();
+    room.send(RoomEvent::Join { address: address(1), sender: host, password: None, invite_code: None });
+    let token = reconnect_token(accept_join(&mut host_receiver).await);
+
+    let saved = room.snapshot().await.expect("room should still be open");
+    let json = serde_json::to_string(&saved).expect("room serializes");
+    let saved: SavedRoom = serde_json::from_str(&json).expect("room deserializes");
+
+    assert!(saved.access.check_rejoin(1, &token));
+}
+
+#[test]
+fn rooms_saved_without_reconnect_tokens_still_load() {
+    let json = serde_json::to_string(&Room::new().snapshot()).expect("room serializes");
+    let saved: SavedRoom = serde_json::from_str(&json).expect("room deserializes");
+
+    assert!(!saved.access.is_private());
+    assert!(saved.access.check_join(None, None).is_ok());
+}
+
+#[test]
+fn passwords_are_checked_on_join() {
+    let mut access = RoomAccess::default();
+    access.set_password(Some("  hunter2 ".to_string()));
+
+    assert!(access.has_password());
+    assert!(matches!(access.check_join(None, None), Err(RejectJoinReason::PasswordRequired)));
+    assert!(matches!(access.check_join(Some("hunter3"), None), Err(RejectJoinReason::WrongPassword)));
+    assert!(access.check_join(Some("hunter2"), None).is_ok());
+
+    access.set_password(Some(String::new()));
+    assert!(!access.has_password());
+    assert!(access.check_join(None, None).is_ok());
+}
+
+#[test]
+fn invite_codes_only_work_once() {
+    let mut access = RoomAccess::default();
+    access.set_private(true);
+    assert!(matches!(access.check_join(None, None), Err(RejectJoinReason::InviteRequired)));
+
+    let code = access.create_invite_code();
+    assert!(access.check_join(None, Some(&code)).is_ok());
+
+    access.use_invite_code(&code);
+    assert!(matches!(access.check_join(None, Some(&code)), Err(RejectJoinReason::InvalidInviteCode)));
+}
+
+#[test]
+fn the_oldest_invite_code_is_dropped_first() {
+    let mut access = RoomAccess::default();
+    access.set_private(true);
+
+    let codes: Vec<String> = (0..MAX_INVITE_CODES).map(|_| access.create_invite_code()).collect();
+    // Using a code in the middle doesn't change which is oldest
+    let used = codes.get(10).expect("enough codes were made");
+    access.use_invite_code(used);
+    access.create_invite_code();
+    let newest = access.create_invite_code();
+
+    let first = codes.first().expect("codes were made");
+    assert!(matches!(access.check_join(None, Some(first)), Err(RejectJoinReason::InvalidInviteCode)));
+    for code in codes.iter().skip(1).filter(|code| *code != used) {
+        assert!(access.check_join(None, Some(code)).is_ok());
+    }
+    assert!(access.check_join(None, Some(&newest)).is_ok());
+}
+
+#[test]
+fn passwords_arent_saved_in_plain_text() {
+    let mut access = RoomAccess::default();
+    access.set_password(Some("hunter2".to_string()));
+
+    let saved = serde_json::to_string(&access).expect("access serializes");
+    assert!(!saved.contains("hunter2"), "{saved}");
+
+    let loaded: RoomAccess = serde_json::from_str(&saved).expect("access deserializes");
+    assert!(loaded.check_join(Some("hunter2"), None).is_ok());
+    assert!(matches!(loaded.check_join(Some("hunter3"), None), Err(RejectJoinReason::WrongPassword)));
+}
+
+#[test]
+fn access_saved_before_hashing_still_loads() {
+    let loaded: RoomAccess = serde_json::from_str(r#"{"private":true,"password":"hunter2","inviteCodes":["abcde"]}"#)
+        .expect("access deserializes");
+
+    assert!(loaded.check_join(Some("hunter2"), None).is_ok());
+    assert!(loaded.check_join(None, Some("abcde")).is_ok());
+    assert!(!serde_json::to_string(&loaded).expect("access serializes").contains("hunter2"));
+}
+
+#[test]
+fn secrets_in_packets_arent_logged() {
+    let packet: ToServerPacket = serde_json::from_str(r#"{"type":"join","roomCode":1,"password":"hunter2","inviteCode":"abcde"}"#)
+        .expect("packet parses");
+
+    let logged = format!("{packet:?}");
+    assert!(!logged.contains("hunter2") && !logged.contains("abcde"), "{logged}");
+}
+
+#[tokio::test]
+async fn only_the_host_can_make_a_room_private() {
+    let (updates, _update_receiver) = mpsc::unbounded_channel();
+    let room = RoomHandle::spawn(ROOM_CODE, Room::new(), RoomAccess::default(), updates, None);
+
+    let (host, mut host_receiver) = client();
+    room.send(RoomEvent::Join { address: address(1), sender: host, password: None, invite_code: None });
+    accept_join(&mut host_receiver).await;
+
+    let (player, mut player_receiver) = client();
+    room.send(RoomEvent::Join { address: address(2), sender: player, password: None, invite_code: None });
+    accept_join(&mut player_receiver).await;
+
+    room.send(RoomEvent::Packet { address: address(2), packet: ToServerPacket::HostSetRoomPrivate { private: true } });
+    room.snapshot().await;
+    assert!(!room.is_private());
+
+    room.send(RoomEvent::Packet { address: address(1), packet: ToServerPacket::HostSetRoomPrivate { private: true } });
+    room.snapshot().await;
+    assert!(room.is_private());
+
+    let (stranger, mut stranger_receiver) = client();
+    room.send(RoomEvent::Join { address: address(3), sender: stranger, password: None, invite_code: None });
+    assert!(matches!(reject_join(&mut stranger_receiver).await, RejectJoinReason::InviteRequired));
+
+    room.send(RoomEvent::Packet { address: address(1), packet: ToServerPacket::HostCreateInviteCode });
+    let code = loop {
+        if let ToClientPacket::InviteCode { code } = host_receiver.recv().await.expect("room should still be open").packet {
+            break code;
+        }
+    };
+
+    let (friend, mut friend_receiver) = client();
+    room.send(RoomEvent::Join { address: address(4), sender: friend, password: None, invite_code: Some(Secret::new(code)) });
+    accept_join(&mut friend_receiver).await;
+}
+
+/// Waits for the room's answer, then returns it with everything else the client has been sent so far
+async fn join_answer(receiver: &mut UnboundedReceiver<SequencedPacket>) -> (ToClientPacket, Vec<SequencedPacket>) {
+    let answer = accept_join(receiver).await;
+    room_settles().await;
+    let mut packets = Vec::new();
+    while let Ok(packet) = receiver.try_recv() {
+        packets.push(packet);
+    }
+    (answer, packets)
+}
+
+async fn room_settles() {
+    tokio::time::sleep(Duration::from_millis(50)).await;
+}
+
+#[tokio::test]
+async fn rejoining_resends_only_missed_packets() {
+    let (updates, _update_receiver) = mpsc::unbounded_channel();
+    let room = RoomHandle::spawn(ROOM_CODE, Room::new(), RoomAccess::default(), updates, None);
+
+    let (host, mut host_receiver) = client();
+    room.send(RoomEvent::Join { address: address(1), sender: host, password: None, invite_code: None });
+    accept_join(&mut host_receiver).await;
+
+    let (player, mut player_receiver) = client();
+    room.send(RoomEvent::Join { address: address(2), sender: player, password: None, invite_code: None });
+    let (answer, packets) = join_answer(&mut player_receiver).await;
+    let token = reconnect_token(answer);
+    let last_sequence = packets.iter().filter_map(|packet| packet.sequence).max().expect("room sent packets");
+
+    room.send(RoomEvent::LoseConnection { address: address(2) });
+    room.send(RoomEvent::Packet { address: address(1), packet: ToServerPacket::SetRoomName { name: "Missed".to_string() } });
+
+    let (player, mut player_receiver) = client();
+    room.send(RoomEvent::Rejoin { address: address(3), sender: player, room_client_id: 2, reconnect_token: token, last_sequence: Some(last_sequence) });
+    let (answer, packets) = join_answer(&mut player_receiver).await;
+
+    let ToClientPacket::AcceptJoin { resumed, reconnect_token: token, .. } = answer else { panic!("expected AcceptJoin") };
+    assert!(resumed);
+    let sequences: Vec<u64> = packets.iter().filter_map(|packet| packet.sequence).collect();
+    assert_eq!(sequences.first(), Some(&last_sequence.saturating_add(1)));
+    assert!(sequences.windows(2).all(|pair| matches!(pair, [a, b] if b.checked_sub(*a) == Some(1))));
+    assert!(packets.iter().any(|packet| matches!(&packet.packet, ToClientPacket::RoomName { name } if name == "Missed")));
+    // It isn't initialized again
+    assert!(!packets.iter().any(|packet| matches!(packet.packet, ToClientPacket::RoleList { .. })));
+
+    // A client that got packets the room doesn't know about is sent everything again
+    room.send(RoomEvent::LoseConnection { address: address(3) });
+    let (player, mut player_receiver) = client();
+    room.send(RoomEvent::Rejoin { address: address(4), sender: player, room_client_id: 2, reconnect_token: token, last_sequence: Some(u64::MAX) });
+    let (answer, packets) = join_answer(&mut player_receiver).await;
+
+    assert!(matches!(answer, ToClientPacket::AcceptJoin { resumed: false, .. }));
+    assert_eq!(packets.first().and_then(|packet| packet.sequence), Some(1));
+    assert!(packets.iter().any(|packet| matches!(packet.packet, ToClientPacket::RoleList { .. })));
+}
+
+#[tokio::test]
+async fn rejoined_clients_get_their_answer_before_anything_else() {
+    let (updates, _update_receiver) = mpsc::unbounded_channel();
+    let room = RoomHandle::spawn(ROOM_CODE, Room::new(), RoomAccess::default(), updates, None);
+
+    let (host, mut host_receiver) = client();
+    room.send(RoomEvent::Join { address: address(1), sender: host, password: None, invite_code: None });
+    let token = reconnect_token(first_packet(&mut host_receiver).await);
+
+    for _ in 0..6 {
+        room.send(RoomEvent::Packet { address: address(1), packet: ToServerPacket::HostAddBot });
+    }
+    room.send(RoomEvent::Packet { address: address(1), packet: ToServerPacket::SetEnabledRoles { roles: Role::values().into_iter().collect() } });
+    room.send(RoomEvent::Packet { address: address(1), packet: ToServerPacket::SetRoleList { role_list: RoleList(vec![RoleOutline::default(); 7]) } });
+    room.send(RoomEvent::Packet { address: address(1), packet: ToServerPacket::StartGame });
+    room_settles().await;
+    let mut last_sequence = 0;
+    while let Ok(packet) = host_receiver.try_recv() {
+        last_sequence = packet.sequence.unwrap_or(last_sequence);
+    }
+    assert!(room.inspect().await.is_some_and(|inspection| inspection.day.is_some()), "game should start");
+
+    // Rejoining a game sends every host the clients' connections again, which has to wait for the answer
+    room.send(RoomEvent::LoseConnection { address: address(1) });
+    let (host, mut host_receiver) = client();
+    room.send(RoomEvent::Rejoin { address: address(2), sender: host, room_client_id: 1, reconnect_token: token, last_sequence: Some(last_sequence) });
+    assert!(matches!(first_packet(&mut host_receiver).await, ToClientPacket::AcceptJoin { resumed: true, .. }));
+    room_settles().await;
+    let mut packets = Vec::new();
+    while let Ok(packet) = host_receiver.try_recv() {
+        packets.push(packet);
+    }
+
+    let sequences: Vec<u64> = packets.iter().filter_map(|packet| packet.sequence).collect();
+    assert_eq!(sequences.first(), Some(&last_sequence.saturating_add(1)));
+    assert!(sequences.windows(2).all(|pair| matches!(pair, [a, b] if b.checked_sub(*a) == Some(1))));
+    assert!(packets.iter().any(|packet| matches!(packet.packet, ToClientPacket::HostData { .. })));
+}
+
+async fn first_packet(receiver: &mut UnboundedReceiver<SequencedPacket>) -> ToClientPacket {
+    tokio::time::timeout(Duration::from_secs(5), receiver.recv()).await
+        .expect("room should answer")
+        .expect("room should still be open").packet
+}
@@ -0,0 +1,64 @@
+mod kit;
+
+use mafia_server::{
+    game::{ability_input::ControllerID, phase::PhaseType, role::Role},
+    packet::ToClientPacket,
+    websocket_connections::connection::SequencedPacket
+};
+
+#[test]
+fn only_changed_controllers_are_sent() {
+    kit::scenario!(game in Night 1 where
+        detective: Detective,
+        mafioso: Mafioso,
+        townie: Detective
+    );
+    let mut detective_client = detective.connect();
+    let mut townie_client = townie.connect();
+
+    let mut join_data = Vec::new();
+    while let Ok(SequencedPacket { packet, .. }) = detective_client.try_recv() {
+        join_data.push(packet);
+    }
+    assert!(join_data.iter().any(|packet| matches!(packet, ToClientPacket::YourAllowedControllers { .. })));
+    while townie_client.try_recv().is_ok() {}
+
+    detective.send_ability_input_player_list_typical(mafioso);
+
+    let mut updates = Vec::new();
+    while let Ok(SequencedPacket { packet, .. }) = detective_client.try_recv() {
+        assert!(!matches!(packet, ToClientPacket::YourAllowedControllers { .. }));
+        if let ToClientPacket::YourAllowedControllersUpdate { changed, removed } = packet {
+            updates.push((changed, removed));
+        }
+    }
+    let [(changed, removed)] = updates.as_slice() else { panic!("expected one update, got {updates:?}") };
+    assert_eq!(changed.keys().collect::<Vec<_>>(), vec![&ControllerID::role(detective.player_ref(), Role::Detective, 0)]);
+    assert!(removed.is_empty());
+
+    while let Ok(SequencedPacket { packet, .. }) = townie_client.try_recv() {
+        assert!(!matches!(packet, ToClientPacket::YourAllowedControllersUpdate { .. } | ToClientPacket::YourAllowedControllers { .. }));
+    }
+}
+
+#[test]
+fn clients_without_the_capability_get_every_controller() {
+    kit::scenario!(game in Night 1 where
+        detective: Detective,
+        mafioso: Mafioso
+    );
+    let mut detective_client = detective.connect_without_capabilities();
+    while detective_client.try_recv().is_ok() {}
+
+    detective.send_ability_input_player_list_typical(mafioso);
+
+    let mut sent = Vec::new();
+    while let Ok(SequencedPacket { packet, .. }) = detective_client.try_recv() {
+        assert!(!matches!(packet, ToClientPacket::YourAllowedControllersUpdate { .. }));
+        if let ToClientPacket::YourAllowedControllers { save } = packet {
+            sent.push(save);
+        }
+    }
+    let [save] = sent.as_slice() else { panic!("expected every controller once, got {sent:?}") };
+    assert!(save.contains_key(&ControllerID::role(detective.player_ref(), Role::Detective, 0)));
+}
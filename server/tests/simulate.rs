@@ -0,0 +1,21 @@
+use std::process::Command;
+
+const GAME_MODES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../client/src/resources/defaultGameModes.json");
+
+fn simulate(seed: &str) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_simulate"))
+        .args([GAME_MODES, "--mode", "Classic", "--players", "7", "--games", "20", "--seed", seed])
+        .output()
+        .expect("simulator runs");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).expect("output is text")
+}
+
+#[test]
+fn simulations_with_the_same_seed_play_out_the_same() {
+    let output = simulate("42");
+
+    assert!(output.starts_with("Simulated 20 games with 7 players, starting from seed 42"), "{output}");
+    assert!(!output.contains("crashed"), "{output}");
+    assert_eq!(simulate("42"), output);
+}